@@ -0,0 +1,351 @@
+use std::{error::Error, fmt::Display};
+
+use engine::{board::Board, piece::Color, tactics::Motif};
+
+use crate::{
+    moves::{self, Move},
+    pgn::{self, PgnGame},
+    search::{hash, SearchLimits, Searcher},
+};
+
+/// Plies searched for every position when no other limit is given
+pub const DEFAULT_ANALYSIS_DEPTH: u8 = 10;
+
+/// Evaluations are capped before the loss is computed, so a slower mate or a won position that
+/// stays won costs at most this much
+const SCORE_CAP: i32 = 1000;
+
+/// Moves losing up to this many centipawns are as good as the engine's
+const BEST_MAX_LOSS: i32 = 10;
+/// Smallest centipawn loss of the errors
+const INACCURACY_LOSS: i32 = 50;
+const MISTAKE_LOSS: i32 = 100;
+const BLUNDER_LOSS: i32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisError {
+    /// The move played at this ply is not legal
+    IllegalMove(usize),
+}
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisError::IllegalMove(ply) => write!(f, "Illegal move at ply {}", ply),
+        }
+    }
+}
+
+impl Error for AnalysisError {}
+
+/// How much a move gave away compared to the engine's choice, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MoveClass {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveClass {
+    pub fn from_loss(loss: i32) -> MoveClass {
+        if loss >= BLUNDER_LOSS {
+            MoveClass::Blunder
+        } else if loss >= MISTAKE_LOSS {
+            MoveClass::Mistake
+        } else if loss >= INACCURACY_LOSS {
+            MoveClass::Inaccuracy
+        } else if loss > BEST_MAX_LOSS {
+            MoveClass::Good
+        } else {
+            MoveClass::Best
+        }
+    }
+
+    /// Inaccuracies, mistakes and blunders
+    pub fn is_error(&self) -> bool {
+        *self >= MoveClass::Inaccuracy
+    }
+
+    /// `?!`, `?` and `??`, empty for the other classes
+    pub fn annotation(&self) -> &'static str {
+        match self {
+            MoveClass::Best | MoveClass::Good => "",
+            MoveClass::Inaccuracy => "?!",
+            MoveClass::Mistake => "?",
+            MoveClass::Blunder => "??",
+        }
+    }
+}
+
+impl Display for MoveClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MoveClass::Best => "best",
+            MoveClass::Good => "good",
+            MoveClass::Inaccuracy => "inaccuracy",
+            MoveClass::Mistake => "mistake",
+            MoveClass::Blunder => "blunder",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A move of the game. Scores are capped centipawns from the point of view of the player who
+/// made the move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveAnalysis {
+    pub ply: usize,
+    pub color: Color,
+    pub movement: Move,
+    /// The engine's choice, None when the position had no legal move
+    pub best_move: Option<Move>,
+    /// Score of the position before the move, with the best move played
+    pub best_score: i32,
+    /// Score of the position after the move
+    pub score: i32,
+    /// Centipawns given away by the move, never negative
+    pub loss: i32,
+    pub class: MoveClass,
+    /// The engine's line from the position before an inaccuracy, a mistake or a blunder, empty
+    /// for the other moves
+    pub better_line: Vec<Move>,
+    /// Tactical patterns of the engine's move, what the player missed. Empty like the line
+    pub missed_motifs: Vec<Motif>,
+}
+
+impl MoveAnalysis {
+    /// From 0 to 100, how much of the winning chances the move kept
+    pub fn accuracy(&self) -> f64 {
+        let lost = win_percent(self.best_score) - win_percent(self.score);
+        (103.1668 * (-0.04354 * lost.max(0.0)).exp() - 3.1669).clamp(0.0, 100.0)
+    }
+}
+
+/// Winning chances from 0 to 100 of a centipawn score, the curve fitted on online games by
+/// the big sites
+fn win_percent(score: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * score as f64).exp()) - 1.0)
+}
+
+/// The report of a whole game
+#[derive(Clone)]
+pub struct GameAnalysis {
+    /// The position before the first move
+    pub start: Board,
+    pub moves: Vec<MoveAnalysis>,
+}
+
+impl GameAnalysis {
+    pub fn player_moves(&self, color: Color) -> impl Iterator<Item = &MoveAnalysis> {
+        self.moves
+            .iter()
+            .filter(move |analysis| analysis.color == color)
+    }
+
+    /// Average accuracy of the player's moves, None when they didn't move
+    pub fn accuracy(&self, color: Color) -> Option<f64> {
+        let accuracies: Vec<f64> = self.player_moves(color).map(|m| m.accuracy()).collect();
+        match accuracies.is_empty() {
+            true => None,
+            false => Some(accuracies.iter().sum::<f64>() / accuracies.len() as f64),
+        }
+    }
+
+    /// Average centipawn loss of the player's moves
+    pub fn average_loss(&self, color: Color) -> Option<i32> {
+        let losses: Vec<i32> = self.player_moves(color).map(|m| m.loss).collect();
+        match losses.is_empty() {
+            true => None,
+            false => Some(losses.iter().sum::<i32>() / losses.len() as i32),
+        }
+    }
+
+    pub fn count(&self, color: Color, class: MoveClass) -> usize {
+        self.player_moves(color)
+            .filter(|analysis| analysis.class == class)
+            .count()
+    }
+
+    /// The inaccuracies, mistakes and blunders of both players in the order they were played
+    pub fn errors(&self) -> impl Iterator<Item = &MoveAnalysis> {
+        self.moves
+            .iter()
+            .filter(|analysis| analysis.class.is_error())
+    }
+
+    /// Move number of a ply, counted from 1 at the starting position
+    fn move_number(&self, ply: usize) -> u32 {
+        let offset = match self.start.get_turn() {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        (ply + offset) as u32 / 2 + 1
+    }
+
+    /// The position before a ply
+    fn position(&self, ply: usize) -> Board {
+        let mut board = self.start;
+        for analysis in &self.moves[..ply] {
+            board = moves::apply(&board, analysis.movement).unwrap_or(board);
+        }
+        board
+    }
+}
+
+/// A summary line for each player, then every error with the engine's better line
+impl Display for GameAnalysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for color in [Color::White, Color::Black] {
+            let (Some(accuracy), Some(loss)) = (self.accuracy(color), self.average_loss(color))
+            else {
+                continue;
+            };
+            writeln!(
+                f,
+                "{}: accuracy {:.1}%, average loss {} cp, {} inaccuracies, {} mistakes, {} blunders",
+                color,
+                accuracy,
+                loss,
+                self.count(color, MoveClass::Inaccuracy),
+                self.count(color, MoveClass::Mistake),
+                self.count(color, MoveClass::Blunder)
+            )?;
+        }
+
+        for analysis in self.errors() {
+            let board = self.position(analysis.ply);
+            let number = self.move_number(analysis.ply);
+            let played = pgn::line_to_san(&board, number, &[analysis.movement]);
+            write!(
+                f,
+                "{}{} {}, loses {} cp. Better: {}",
+                played,
+                analysis.class.annotation(),
+                analysis.class,
+                analysis.loss,
+                pgn::line_to_san(&board, number, &analysis.better_line)
+            )?;
+            let mut missed: Vec<String> = analysis
+                .missed_motifs
+                .iter()
+                .map(|m| m.to_string())
+                .collect();
+            missed.dedup();
+            match missed.is_empty() {
+                true => writeln!(f)?,
+                false => writeln!(f, " ({})", missed.join(", "))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates every position of a game with the search and grades the moves by how many
+/// centipawns they lose against the engine's choice
+pub struct Analyser {
+    searcher: Searcher,
+    limits: SearchLimits,
+}
+
+impl Default for Analyser {
+    fn default() -> Self {
+        Analyser::new(SearchLimits::depth(DEFAULT_ANALYSIS_DEPTH))
+    }
+}
+
+impl Analyser {
+    pub fn new(limits: SearchLimits) -> Analyser {
+        Analyser {
+            searcher: Searcher::default(),
+            limits,
+        }
+    }
+
+    /// The searcher used for the evaluations, to set its threads, hash size or tables
+    pub fn searcher_mut(&mut self) -> &mut Searcher {
+        &mut self.searcher
+    }
+
+    pub fn analyse_pgn(&mut self, game: &PgnGame) -> Result<GameAnalysis, AnalysisError> {
+        self.analyse(&game.start, &game.moves)
+    }
+
+    /// Every position is searched once: its score grades the move played from it, and its
+    /// negation is the score of the move that led to it
+    pub fn analyse(
+        &mut self,
+        start: &Board,
+        game_moves: &[Move],
+    ) -> Result<GameAnalysis, AnalysisError> {
+        let mut positions = vec![*start];
+        for (ply, movement) in game_moves.iter().enumerate() {
+            let board = positions[ply];
+            let legal =
+                board.get_winner().is_none() && moves::legal_moves(&board).contains(movement);
+            let child = moves::apply(&board, *movement).filter(|_| legal);
+            positions.push(child.ok_or(AnalysisError::IllegalMove(ply))?);
+        }
+
+        let mut history = Vec::with_capacity(positions.len());
+        let mut results = Vec::with_capacity(positions.len());
+        for board in &positions {
+            let result = match board.get_winner() {
+                // Checkmated
+                Some(_) => None,
+                None => {
+                    self.searcher.set_history(history.clone());
+                    Some(self.searcher.search(board, self.limits))
+                }
+            };
+            results.push(result);
+            history.push(hash::hash(board));
+        }
+
+        let mut analyses = Vec::with_capacity(game_moves.len());
+        for (ply, movement) in game_moves.iter().enumerate() {
+            let Some(result) = &results[ply] else {
+                break;
+            };
+            let best_score = cap(result.score);
+            let score = match &results[ply + 1] {
+                Some(child) => -cap(child.score),
+                None => SCORE_CAP,
+            };
+            // The engine's own move keeps the score it was chosen for
+            let played_best = result.best_move == Some(*movement);
+            let score = if played_best { best_score } else { score };
+            let loss = (best_score - score).max(0);
+            let class = MoveClass::from_loss(loss);
+            let missed_motifs = match (class.is_error(), result.best_move) {
+                (true, Some(best)) => positions[ply].move_motifs(best.from, best.to),
+                _ => Vec::new(),
+            };
+            analyses.push(MoveAnalysis {
+                ply,
+                color: positions[ply].get_turn(),
+                movement: *movement,
+                best_move: result.best_move,
+                best_score,
+                score,
+                loss,
+                class,
+                better_line: match class.is_error() {
+                    true => result.pv.clone(),
+                    false => Vec::new(),
+                },
+                missed_motifs,
+            });
+        }
+
+        Ok(GameAnalysis {
+            start: *start,
+            moves: analyses,
+        })
+    }
+}
+
+fn cap(score: i32) -> i32 {
+    score.clamp(-SCORE_CAP, SCORE_CAP)
+}
@@ -0,0 +1,64 @@
+use std::{env, fs, process};
+
+use ai::{
+    pgn,
+    puzzle::{PuzzleFinder, CSV_HEADER, DEFAULT_PUZZLE_DEPTH},
+};
+
+const USAGE: &str = "Usage:
+  puzzles [--depth N] [--output FILE] <PGN FILE>...
+
+Writes the puzzles found in the games as CSV lines (FEN, moves, difficulty, themes), to the
+standard output or to FILE";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(error) = run(&args) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut depth = DEFAULT_PUZZLE_DEPTH;
+    let mut output = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                depth = args
+                    .next()
+                    .and_then(|depth| depth.parse().ok())
+                    .ok_or("Invalid value for --depth")?;
+            }
+            "--output" => output = Some(args.next().ok_or("Missing value for --output")?),
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut finder = PuzzleFinder::new(depth);
+    let mut lines = vec![CSV_HEADER.to_string()];
+    for file in files {
+        let text = fs::read_to_string(file).map_err(|error| format!("{}: {}", file, error))?;
+        let games = pgn::parse(&text).map_err(|error| format!("{}: {}", file, error))?;
+        for (index, game) in games.iter().enumerate() {
+            let puzzles = finder
+                .find_in_pgn(game)
+                .map_err(|error| format!("{}, game {}: {}", file, index + 1, error))?;
+            eprintln!("{}, game {}: {} puzzles", file, index + 1, puzzles.len());
+            lines.extend(puzzles.iter().map(|puzzle| puzzle.to_string()));
+        }
+    }
+
+    match output {
+        Some(output) => fs::write(output, lines.join("\n") + "\n").map_err(|e| e.to_string()),
+        None => {
+            println!("{}", lines.join("\n"));
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::{env, process, time::Instant};
+
+use ai::tablebase::{
+    self,
+    dtm::{DtmTablebase, MAX_PIECES},
+    generator,
+    material::Material,
+    Tablebase,
+};
+use engine::{board::Board, piece::Color};
+
+const USAGE: &str = "Usage:
+  tablebase generate <directory> [--max-pieces N] [MATERIAL...]
+  tablebase verify [--max-pieces N] [--stride N] [MATERIAL...]
+  tablebase probe <directory> <FEN>
+
+Materials are written like KQvKR, every endgame with up to --max-pieces pieces (4 by default)
+is used when none is given";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("generate") => generate(&args[1..]),
+        Some("verify") => verify(&args[1..]),
+        Some("probe") => probe(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let (directory, materials) = match args.split_first() {
+        Some((directory, args)) if !directory.starts_with("--") => (directory, materials(args)?),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut tablebase = DtmTablebase::new();
+    for material in materials {
+        let start = Instant::now();
+        tablebase.add(&material);
+        if let Some(table) = tablebase.table(&material) {
+            let longest = |color: Color| match table.longest_mate(color) {
+                Some(plies) => plies.to_string(),
+                None => "-".to_string(),
+            };
+            println!(
+                "{}: longest mates {} plies with White to move, {} with Black, {:.1?}",
+                table.material(),
+                longest(Color::White),
+                longest(Color::Black),
+                start.elapsed()
+            );
+        }
+    }
+    tablebase.save(directory).map_err(|error| error.to_string())
+}
+
+/// Compares the move generator of the tables with the engine's
+fn verify(args: &[String]) -> Result<(), String> {
+    let stride = flag_value(args, "--stride")?.unwrap_or(1);
+    let mut failed = false;
+    for material in materials(args)? {
+        let mismatches = generator::engine_mismatches(&material, stride);
+        println!("{}: {} mismatches", material, mismatches.len());
+        for fen in mismatches.iter().take(10) {
+            println!("  {}", fen);
+        }
+        failed |= !mismatches.is_empty();
+    }
+    match failed {
+        true => Err("The engine and the tables disagree".to_string()),
+        false => Ok(()),
+    }
+}
+
+fn probe(args: &[String]) -> Result<(), String> {
+    let [directory, fen] = args else {
+        return Err(USAGE.to_string());
+    };
+    let tablebase = DtmTablebase::open(directory).map_err(|error| error.to_string())?;
+    let board = Board::from_fen(fen).map_err(|error| error.to_string())?;
+
+    let (wdl, dtm) = match (tablebase.probe_wdl(&board), tablebase.probe_dtz(&board)) {
+        (Some(wdl), Some(dtm)) => (wdl, dtm),
+        _ => return Err("The position is not in the tables".to_string()),
+    };
+    println!("{:?}, mate in {} plies", wdl, dtm.abs());
+    if let Some(moves) = tablebase::root_moves(&tablebase, &board) {
+        let moves: Vec<String> = moves.iter().map(|movement| movement.to_string()).collect();
+        println!("Moves keeping the result, best first: {}", moves.join(" "));
+    }
+    Ok(())
+}
+
+/// The materials given as arguments, or every one up to `--max-pieces`
+fn materials(args: &[String]) -> Result<Vec<Material>, String> {
+    let max_pieces = flag_value(args, "--max-pieces")?.unwrap_or(MAX_PIECES);
+    let mut materials = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            args.next();
+            continue;
+        }
+        let material: Material = arg
+            .parse()
+            .map_err(|_| format!("Invalid material: {}", arg))?;
+        if material.pieces() > MAX_PIECES {
+            return Err(format!("Endgames have at most {} pieces", MAX_PIECES));
+        }
+        materials.push(material);
+    }
+
+    if materials.is_empty() {
+        materials = Material::all(max_pieces.min(MAX_PIECES));
+    }
+    Ok(materials)
+}
+
+fn flag_value(args: &[String], flag: &str) -> Result<Option<usize>, String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => args
+            .get(index + 1)
+            .and_then(|value| value.parse().ok())
+            .map(Some)
+            .ok_or_else(|| format!("Invalid value for {}", flag)),
+        None => Ok(None),
+    }
+}
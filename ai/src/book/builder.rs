@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use engine::piece::Color;
+
+use crate::pgn::{self, GameResult, PgnError, PgnGame};
+
+use super::{polyglot, Book};
+
+/// Plies of every game added to the book by default
+pub const DEFAULT_DEPTH: usize = 24;
+
+/// Which games count for the moves of a player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResultFilter {
+    /// Every game, unfinished ones included
+    #[default]
+    All,
+    /// Games the player won or drew
+    NoLosses,
+    /// Games the player won
+    WinsOnly,
+}
+
+impl ResultFilter {
+    fn accepts(&self, result: GameResult, player: Color) -> bool {
+        match self {
+            ResultFilter::All => true,
+            ResultFilter::NoLosses => match result {
+                GameResult::Draw => true,
+                result => result.winner() == Some(player),
+            },
+            ResultFilter::WinsOnly => result.winner() == Some(player),
+        }
+    }
+}
+
+/// Builds a Polyglot book out of PGN games. A move is weighted by the number of games it was
+/// played in, and only kept if it was played in at least `min_frequency` of them
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    depth: usize,
+    min_frequency: u32,
+    result_filter: ResultFilter,
+    /// Games each move was played in, by position key and encoded move
+    frequencies: HashMap<(u64, u16), u32>,
+}
+
+impl Default for BookBuilder {
+    fn default() -> Self {
+        BookBuilder::new()
+    }
+}
+
+impl BookBuilder {
+    pub fn new() -> BookBuilder {
+        BookBuilder {
+            depth: DEFAULT_DEPTH,
+            min_frequency: 1,
+            result_filter: ResultFilter::default(),
+            frequencies: HashMap::new(),
+        }
+    }
+
+    /// Plies of every game that go into the book
+    pub fn depth(mut self, depth: usize) -> BookBuilder {
+        self.depth = depth;
+        self
+    }
+
+    /// Games a move must be played in to be kept
+    pub fn min_frequency(mut self, min_frequency: u32) -> BookBuilder {
+        self.min_frequency = min_frequency;
+        self
+    }
+
+    pub fn result_filter(mut self, result_filter: ResultFilter) -> BookBuilder {
+        self.result_filter = result_filter;
+        self
+    }
+
+    pub fn add_game(&mut self, game: &PgnGame) {
+        for (board, movement) in game.positions().take(self.depth) {
+            if !self.result_filter.accepts(game.result, board.get_turn()) {
+                continue;
+            }
+            let entry = (
+                polyglot::key(&board),
+                polyglot::encode_move(&board, movement),
+            );
+            *self.frequencies.entry(entry).or_default() += 1;
+        }
+    }
+
+    /// Adds every game of a PGN text, returns how many games were read
+    pub fn add_pgn(&mut self, pgn: &str) -> Result<usize, PgnError> {
+        let games = pgn::parse(pgn)?;
+        for game in &games {
+            self.add_game(game);
+        }
+        Ok(games.len())
+    }
+
+    pub fn build(&self) -> Book {
+        let kept: Vec<((u64, u16), u32)> = self
+            .frequencies
+            .iter()
+            .filter(|(_, frequency)| **frequency >= self.min_frequency.max(1))
+            .map(|(entry, frequency)| (*entry, *frequency))
+            .collect();
+
+        // Weights are 16 bits, bigger collections are scaled down
+        let max = kept
+            .iter()
+            .map(|(_, frequency)| *frequency)
+            .max()
+            .unwrap_or(0);
+        let scale = (u16::MAX as f64 / max as f64).min(1.0);
+        Book::from_entries(kept.into_iter().map(|((key, movement), frequency)| {
+            let weight = ((frequency as f64 * scale) as u16).max(1);
+            (key, movement, weight)
+        }))
+    }
+}
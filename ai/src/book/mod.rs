@@ -0,0 +1,204 @@
+use std::{error::Error, fmt::Display, fs, path::Path};
+
+use engine::board::Board;
+use rand::Rng;
+
+use crate::moves::Move;
+
+pub mod builder;
+pub mod polyglot;
+mod random;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookError {
+    Io(String),
+    /// Polyglot books are made of 16 bytes entries
+    InvalidSize(usize),
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookError::Io(error) => write!(f, "Could not access the book: {}", error),
+            BookError::InvalidSize(size) => write!(
+                f,
+                "Invalid book size: {} bytes is not a multiple of {}",
+                size,
+                polyglot::ENTRY_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for BookError {}
+
+/// A book move and how often it should be played compared to the other moves of the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    pub movement: Move,
+    pub weight: u16,
+}
+
+/// How the book is used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookSettings {
+    /// Plies after which the book is not used anymore, None to follow it as long as it has moves
+    pub depth: Option<usize>,
+    /// 0 always plays the most weighted move, 1 follows the book weights and higher values
+    /// play every book move more evenly
+    pub variety: f64,
+}
+
+impl Default for BookSettings {
+    fn default() -> Self {
+        BookSettings {
+            depth: None,
+            variety: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RawEntry {
+    key: u64,
+    movement: u16,
+    weight: u16,
+    learn: u32,
+}
+
+/// An opening book in the Polyglot `.bin` format
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Book {
+    /// Sorted by key, like on disk
+    entries: Vec<RawEntry>,
+}
+
+impl Book {
+    pub fn open(path: impl AsRef<Path>) -> Result<Book, BookError> {
+        let bytes = fs::read(path).map_err(|error| BookError::Io(error.to_string()))?;
+        Book::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Book, BookError> {
+        if !bytes.len().is_multiple_of(polyglot::ENTRY_SIZE) {
+            return Err(BookError::InvalidSize(bytes.len()));
+        }
+
+        let mut entries: Vec<RawEntry> = bytes
+            .chunks_exact(polyglot::ENTRY_SIZE)
+            .map(|entry| RawEntry {
+                key: u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                movement: u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(entry[12..16].try_into().unwrap()),
+            })
+            .collect();
+        // Lookups rely on the order, don't trust the file for it
+        entries.sort_by_key(|entry| entry.key);
+
+        Ok(Book { entries })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BookError> {
+        fs::write(path, self.to_bytes()).map_err(|error| BookError::Io(error.to_string()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * polyglot::ENTRY_SIZE);
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.key.to_be_bytes());
+            bytes.extend_from_slice(&entry.movement.to_be_bytes());
+            bytes.extend_from_slice(&entry.weight.to_be_bytes());
+            bytes.extend_from_slice(&entry.learn.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Number of entries, one per position and move
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The legal book moves of the position, most weighted first
+    pub fn moves(&self, board: &Board) -> Vec<BookEntry> {
+        let key = polyglot::key(board);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let mut moves: Vec<BookEntry> = self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                let movement = polyglot::decode_move(board, entry.movement)?;
+                Some(BookEntry {
+                    movement,
+                    weight: entry.weight,
+                })
+            })
+            .collect();
+        moves.sort_by_key(|entry| std::cmp::Reverse(entry.weight));
+        moves
+    }
+
+    /// Picks a book move at random according to the weights, `ply` is the number of moves
+    /// already played in the game. None when the position is out of the book
+    pub fn choose_move<R: Rng + ?Sized>(
+        &self,
+        board: &Board,
+        ply: usize,
+        settings: BookSettings,
+        rng: &mut R,
+    ) -> Option<Move> {
+        if settings.depth.is_some_and(|depth| ply >= depth) {
+            return None;
+        }
+
+        // Moves with no weight are kept in books only to be avoided
+        let moves: Vec<BookEntry> = self
+            .moves(board)
+            .into_iter()
+            .filter(|entry| entry.weight > 0)
+            .collect();
+        let best = moves.first()?;
+        if settings.variety <= 0.0 {
+            return Some(best.movement);
+        }
+
+        let weights: Vec<f64> = moves
+            .iter()
+            .map(|entry| (entry.weight as f64).powf(1.0 / settings.variety))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if !total.is_finite() {
+            return Some(best.movement);
+        }
+
+        let mut choice = rng.gen_range(0.0..total);
+        for (entry, weight) in moves.iter().zip(weights) {
+            if choice < weight {
+                return Some(entry.movement);
+            }
+            choice -= weight;
+        }
+        moves.last().map(|entry| entry.movement)
+    }
+
+    pub(crate) fn from_entries(entries: impl IntoIterator<Item = (u64, u16, u16)>) -> Book {
+        let mut entries: Vec<RawEntry> = entries
+            .into_iter()
+            .map(|(key, movement, weight)| RawEntry {
+                key,
+                movement,
+                weight,
+                learn: 0,
+            })
+            .collect();
+        entries.sort_by_key(|entry| (entry.key, std::cmp::Reverse(entry.weight)));
+        Book { entries }
+    }
+}
@@ -0,0 +1,120 @@
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+};
+
+use crate::{
+    moves::{self, Move},
+    search::hash,
+};
+
+use super::random::RANDOM64;
+
+const CASTLING_KEYS: usize = 768;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const TURN_KEY: usize = EN_PASSANT_KEYS + 8;
+
+/// Size of an entry on disk: key, move, weight and learn data, all big endian
+pub(crate) const ENTRY_SIZE: usize = 16;
+
+/// Polyglot hash of the position, the key books are sorted by
+pub fn key(board: &Board) -> u64 {
+    let mut key = 0;
+
+    for (y, row) in board.get_pieces().iter().enumerate() {
+        for (x, piece) in row.iter().enumerate() {
+            if let Some(piece) = piece {
+                let color = match piece.get_color() {
+                    Color::Black => 0,
+                    Color::White => 1,
+                };
+                let kind = hash::type_index(piece.get_type()) * 2 + color;
+                key ^= RANDOM64[kind * 64 + y * 8 + x];
+            }
+        }
+    }
+
+    for (i, castling) in hash::castling_rights(board).into_iter().enumerate() {
+        if castling {
+            key ^= RANDOM64[CASTLING_KEYS + i];
+        }
+    }
+
+    if let Some(file) = hash::en_passant_file(board) {
+        key ^= RANDOM64[EN_PASSANT_KEYS + file as usize];
+    }
+
+    if board.get_turn() == Color::White {
+        key ^= RANDOM64[TURN_KEY];
+    }
+
+    key
+}
+
+/// Packs a move as `to file, to row, from file, from row, promotion` in 3 bits each, from the
+/// lowest bits. Castling is written as the king taking its own rook
+pub(crate) fn encode_move(board: &Board, movement: Move) -> u16 {
+    let mut to = movement.to;
+    if is_castling(board, movement.from, movement.to) {
+        to.x = if to.x > movement.from.x { 7 } else { 0 };
+    }
+    let promotion = match movement.promotion {
+        Some(Type::Knight) => 1,
+        Some(Type::Bishop) => 2,
+        Some(Type::Rook) => 3,
+        Some(Type::Queen) => 4,
+        _ => 0,
+    };
+
+    (to.x as u16)
+        | (to.y as u16) << 3
+        | (movement.from.x as u16) << 6
+        | (movement.from.y as u16) << 9
+        | promotion << 12
+}
+
+/// Unpacks a move written by [`encode_move`], None if it is not legal on this board
+pub(crate) fn decode_move(board: &Board, data: u16) -> Option<Move> {
+    let square = |shift: u16| Position {
+        x: ((data >> shift) & 7) as i32,
+        y: ((data >> (shift + 3)) & 7) as i32,
+    };
+    let from = square(6);
+    let mut to = square(0);
+    let promotion = match (data >> 12) & 7 {
+        0 => None,
+        1 => Some(Type::Knight),
+        2 => Some(Type::Bishop),
+        3 => Some(Type::Rook),
+        4 => Some(Type::Queen),
+        _ => return None,
+    };
+
+    // The king takes its own rook when castling
+    let castling = board.get_piece_at(&to).is_some_and(|rook| {
+        rook.get_type() == Type::Rook && Some(rook.get_color()) == king_color(board, from)
+    });
+    if castling {
+        to.x = if to.x > from.x { 6 } else { 2 };
+    }
+
+    let movement = Move {
+        from,
+        to,
+        promotion,
+    };
+    moves::legal_moves(board)
+        .contains(&movement)
+        .then_some(movement)
+}
+
+fn king_color(board: &Board, position: Position) -> Option<Color> {
+    board
+        .get_piece_at(&position)
+        .filter(|piece| piece.get_type() == Type::King)
+        .map(|piece| piece.get_color())
+}
+
+fn is_castling(board: &Board, from: Position, to: Position) -> bool {
+    king_color(board, from).is_some() && (to.x - from.x).abs() == 2
+}
@@ -0,0 +1,181 @@
+use std::str::FromStr;
+
+use engine::board::Board;
+
+use crate::{
+    book::{
+        builder::{BookBuilder, ResultFilter},
+        polyglot, Book, BookError, BookSettings,
+    },
+    moves::{self, Move},
+    seeded_rng,
+};
+
+fn play(moves: &[&str]) -> Board {
+    let mut board = Board::new();
+    for movement in moves {
+        board = moves::apply(&board, Move::from_str(movement).unwrap()).unwrap();
+    }
+    board
+}
+
+/// Keys given in the Polyglot format description
+#[test]
+fn polyglot_keys() {
+    let positions: [(&[&str], u64); 9] = [
+        (&[], 0x463b96181691fc9c),
+        (&["e2e4"], 0x823c9b50fd114196),
+        (&["e2e4", "d7d5"], 0x0756b94461c50fb0),
+        (&["e2e4", "d7d5", "e4e5"], 0x662fafb965db29d4),
+        (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a48b5a8e47ff78),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"],
+            0x652a607ca3f242c1,
+        ),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"],
+            0x00fdd303c946bdd9,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"],
+            0x3c8123ea7b067637,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"],
+            0x5c3f9b829b279560,
+        ),
+    ];
+
+    for (moves, key) in positions {
+        assert_eq!(polyglot::key(&play(moves)), key, "{:?}", moves);
+    }
+}
+
+#[test]
+fn move_encoding() {
+    let board = play(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5"]);
+    let castling = Move::from_str("e1g1").unwrap();
+    let data = polyglot::encode_move(&board, castling);
+    // The king takes its own rook
+    assert_eq!(data, 4 << 6 | 7);
+    assert_eq!(polyglot::decode_move(&board, data), Some(castling));
+
+    let board = Board::from_fen("8/1P6/8/8/8/8/8/K6k w - - 0 1").unwrap();
+    let promotion = Move::from_str("b7b8n").unwrap();
+    let data = polyglot::encode_move(&board, promotion);
+    assert_eq!(polyglot::decode_move(&board, data), Some(promotion));
+
+    // b1 to b8 is not a legal move
+    assert_eq!(polyglot::decode_move(&board, 1 << 6 | 7 << 3 | 1), None);
+}
+
+fn italian_book() -> Book {
+    let mut builder = BookBuilder::new();
+    builder
+        .add_pgn(
+            "1. e4 e5 2. Nf3 Nc6 3. Bc4 1-0\n\
+             1. e4 e5 2. Nf3 Nc6 3. Bb5 1/2-1/2\n\
+             1. e4 e5 2. Nf3 Nc6 3. Bb5 0-1\n\
+             1. d4 d5 0-1",
+        )
+        .unwrap();
+    builder.build()
+}
+
+#[test]
+fn bytes_round_trip() {
+    let book = italian_book();
+    let bytes = book.to_bytes();
+    assert_eq!(bytes.len(), book.len() * 16);
+    assert_eq!(Book::from_bytes(&bytes), Ok(book));
+    assert_eq!(Book::from_bytes(&[0; 17]), Err(BookError::InvalidSize(17)));
+}
+
+#[test]
+fn book_moves() {
+    let book = italian_book();
+    let moves = book.moves(&Board::new());
+    assert_eq!(moves.len(), 2);
+    assert_eq!(moves[0].movement, Move::from_str("e2e4").unwrap());
+    assert_eq!(moves[0].weight, 3);
+    assert_eq!(moves[1].movement, Move::from_str("d2d4").unwrap());
+    assert_eq!(moves[1].weight, 1);
+
+    let out_of_book = play(&["a2a3"]);
+    assert!(book.moves(&out_of_book).is_empty());
+}
+
+#[test]
+fn weighted_choice() {
+    let book = italian_book();
+    let board = Board::new();
+    let e4 = Move::from_str("e2e4").unwrap();
+    let mut rng = seeded_rng(0);
+
+    let best_only = BookSettings {
+        depth: None,
+        variety: 0.0,
+    };
+    for _ in 0..20 {
+        assert_eq!(book.choose_move(&board, 0, best_only, &mut rng), Some(e4));
+    }
+
+    let choices: Vec<Option<Move>> = (0..400)
+        .map(|_| book.choose_move(&board, 0, BookSettings::default(), &mut rng))
+        .collect();
+    let e4_count = choices.iter().filter(|choice| **choice == Some(e4)).count();
+    // 3 to 1
+    assert!((250..350).contains(&e4_count), "{}", e4_count);
+    assert!(choices.iter().all(|choice| choice.is_some()));
+
+    let shallow = BookSettings {
+        depth: Some(2),
+        variety: 1.0,
+    };
+    assert!(book.choose_move(&board, 1, shallow, &mut rng).is_some());
+    assert_eq!(book.choose_move(&board, 2, shallow, &mut rng), None);
+}
+
+#[test]
+fn builder_filters() {
+    let pgn = "1. e4 e5 2. Nf3 1-0\n1. e4 c5 0-1\n1. d4 d5 1/2-1/2\n1. c4 *";
+
+    let mut builder = BookBuilder::new().min_frequency(2);
+    builder.add_pgn(pgn).unwrap();
+    let book = builder.build();
+    assert_eq!(book.len(), 1);
+    assert_eq!(book.moves(&Board::new())[0].weight, 2);
+
+    let mut builder = BookBuilder::new().result_filter(ResultFilter::WinsOnly);
+    builder.add_pgn(pgn).unwrap();
+    let book = builder.build();
+    let first_moves: Vec<String> = book
+        .moves(&Board::new())
+        .iter()
+        .map(|entry| entry.movement.to_string())
+        .collect();
+    assert_eq!(first_moves, vec!["e2e4"]);
+    let sicilian = book.moves(&play(&["e2e4"]));
+    assert_eq!(sicilian.len(), 1);
+    assert_eq!(sicilian[0].movement, Move::from_str("c7c5").unwrap());
+
+    let mut builder = BookBuilder::new()
+        .result_filter(ResultFilter::NoLosses)
+        .depth(1);
+    builder.add_pgn(pgn).unwrap();
+    let book = builder.build();
+    // e4 won once and lost once, d4 drew, c4 has no result
+    assert_eq!(book.len(), 2);
+    assert!(book.moves(&play(&["e2e4"])).is_empty());
+}
+
+#[test]
+fn save_and_open() {
+    let book = italian_book();
+    let file = std::env::temp_dir().join(format!("book-{}.bin", std::process::id()));
+    book.save(&file).unwrap();
+    assert_eq!(Book::open(&file), Ok(book));
+    std::fs::remove_file(&file).unwrap();
+
+    assert!(matches!(Book::open(&file), Err(BookError::Io(_))));
+}
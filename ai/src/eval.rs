@@ -0,0 +1,170 @@
+use engine::{
+    board::Board,
+    piece::{position::Position, ChessPiece, Color, Type},
+};
+
+// Piece square tables from the "Simplified Evaluation Function" by Tomasz Michniewski.
+// Tables are written from white's point of view, the first row is the 8th rank.
+
+#[rustfmt::skip]
+const PAWN_TABLE: [[i32; 8]; 8] = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [ 50,  50,  50,  50,  50,  50,  50,  50],
+    [ 10,  10,  20,  30,  30,  20,  10,  10],
+    [  5,   5,  10,  25,  25,  10,   5,   5],
+    [  0,   0,   0,  20,  20,   0,   0,   0],
+    [  5,  -5, -10,   0,   0, -10,  -5,   5],
+    [  5,  10,  10, -20, -20,  10,  10,   5],
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [[i32; 8]; 8] = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20,   0,   0,   0,   0, -20, -40],
+    [-30,   0,  10,  15,  15,  10,   0, -30],
+    [-30,   5,  15,  20,  20,  15,   5, -30],
+    [-30,   0,  15,  20,  20,  15,   0, -30],
+    [-30,   5,  10,  15,  15,  10,   5, -30],
+    [-40, -20,   0,   5,   5,   0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-10,   0,   5,  10,  10,   5,   0, -10],
+    [-10,   5,   5,  10,  10,   5,   5, -10],
+    [-10,   0,  10,  10,  10,  10,   0, -10],
+    [-10,  10,  10,  10,  10,  10,  10, -10],
+    [-10,   5,   0,   0,   0,   0,   5, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [[i32; 8]; 8] = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [  5,  10,  10,  10,  10,  10,  10,   5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [  0,   0,   0,   5,   5,   0,   0,   0],
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-10,   0,   5,   5,   5,   5,   0, -10],
+    [ -5,   0,   5,   5,   5,   5,   0,  -5],
+    [  0,   0,   5,   5,   5,   5,   0,  -5],
+    [-10,   5,   5,   5,   5,   5,   0, -10],
+    [-10,   0,   5,   0,   0,   0,   0, -10],
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+];
+
+#[rustfmt::skip]
+const KING_MIDDLE_GAME_TABLE: [[i32; 8]; 8] = [
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [ 20,  20,   0,   0,   0,   0,  20,  20],
+    [ 20,  30,  10,   0,   0,  10,  30,  20],
+];
+
+#[rustfmt::skip]
+const KING_END_GAME_TABLE: [[i32; 8]; 8] = [
+    [-50, -40, -30, -20, -20, -30, -40, -50],
+    [-30, -20, -10,   0,   0, -10, -20, -30],
+    [-30, -10,  20,  30,  30,  20, -10, -30],
+    [-30, -10,  30,  40,  40,  30, -10, -30],
+    [-30, -10,  30,  40,  40,  30, -10, -30],
+    [-30, -10,  20,  30,  30,  20, -10, -30],
+    [-30, -30,   0,   0,   0,   0, -30, -30],
+    [-50, -30, -30, -30, -30, -30, -30, -50],
+];
+
+/// Non pawn material (per side) under which the king should walk to the center
+const END_GAME_MATERIAL: i32 = 1300;
+
+/// Returns the value of a piece in centipawns
+pub fn piece_value(piece_type: Type) -> i32 {
+    match piece_type {
+        Type::Pawn => 100,
+        Type::Knight => 320,
+        Type::Bishop => 330,
+        Type::Rook => 500,
+        Type::Queen => 900,
+        Type::King => 20_000,
+    }
+}
+
+/// Returns the sum of the values of all the non pawn pieces (king excluded) of the given color
+pub fn non_pawn_material(board: &Board, color: Color) -> i32 {
+    pieces(board)
+        .filter(|(_, piece)| piece.get_color() == color)
+        .map(|(_, piece)| piece.get_type())
+        .filter(|piece_type| *piece_type != Type::Pawn && *piece_type != Type::King)
+        .map(piece_value)
+        .sum()
+}
+
+/// Static evaluation of the board in centipawns, from the point of view of the side to move
+pub fn evaluate(board: &Board) -> i32 {
+    let end_game = non_pawn_material(board, Color::White) <= END_GAME_MATERIAL
+        && non_pawn_material(board, Color::Black) <= END_GAME_MATERIAL;
+
+    let mut score = 0;
+    for (position, piece) in pieces(board) {
+        let value = piece_value(piece.get_type()) + square_value(&piece, position, end_game);
+        match piece.get_color() {
+            Color::White => score += value,
+            Color::Black => score -= value,
+        }
+    }
+
+    match board.get_turn() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+fn square_value(piece: &ChessPiece, position: Position, end_game: bool) -> i32 {
+    let table = match piece.get_type() {
+        Type::Pawn => &PAWN_TABLE,
+        Type::Knight => &KNIGHT_TABLE,
+        Type::Bishop => &BISHOP_TABLE,
+        Type::Rook => &ROOK_TABLE,
+        Type::Queen => &QUEEN_TABLE,
+        Type::King if end_game => &KING_END_GAME_TABLE,
+        Type::King => &KING_MIDDLE_GAME_TABLE,
+    };
+
+    // Black reads the tables upside down
+    let row = match piece.get_color() {
+        Color::White => 7 - position.y,
+        Color::Black => position.y,
+    };
+
+    table[row as usize][position.x as usize]
+}
+
+fn pieces(board: &Board) -> impl Iterator<Item = (Position, ChessPiece)> + '_ {
+    board.get_pieces().iter().enumerate().flat_map(|(y, row)| {
+        row.iter().enumerate().filter_map(move |(x, piece)| {
+            piece.map(|piece| {
+                let position = Position {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                (position, piece)
+            })
+        })
+    })
+}
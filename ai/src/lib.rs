@@ -2,17 +2,75 @@ use engine::{
     board::Board,
     piece::{position::Position, ChessPiece},
 };
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use result::AiError;
+use search::{SearchLimits, SearchOptions, Searcher};
+
+pub mod analysis;
+pub mod book;
+pub mod eval;
+pub mod moves;
+pub mod pgn;
+pub mod player;
+pub mod puzzle;
+pub mod result;
+pub mod search;
+pub mod solver;
+pub mod strength;
+pub mod tablebase;
 
 #[cfg(test)]
 mod test;
 
-pub fn make_move(board: &Board) -> (Position, Position) {
-    let board = *board;
+/// Plies searched after each promotion choice
+const PROMOTION_SEARCH_DEPTH: u8 = 3;
+const PROMOTION_HASH_SIZE_MB: usize = 1;
+
+/// Random number generator for the AI functions, the same seed always replays the same moves
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Plays a random legal move
+pub fn make_move<R: Rng + ?Sized>(
+    board: &Board,
+    rng: &mut R,
+) -> Result<(Position, Position), AiError> {
     let valid_moves = board.legal_moves();
-    let rand_value = rand::random::<usize>() % valid_moves.len();
-    valid_moves[rand_value]
+    valid_moves
+        .choose(rng)
+        .copied()
+        .ok_or(AiError::NoLegalMoves)
 }
 
-pub fn make_promotion(board: &Board) -> ChessPiece {
-    return ChessPiece::create_queen(board.get_turn());
+/// Chooses the piece for the pending promotion. A piece that mates is taken at once, otherwise
+/// every piece is searched and a stalemated opponent scores as a draw, so knights, rooks and
+/// bishops are chosen when the queen would throw the win away
+pub fn make_promotion(board: &Board) -> Result<ChessPiece, AiError> {
+    if board.get_promotion().is_none() {
+        return Err(AiError::NoPromotion);
+    }
+
+    let color = board.get_turn();
+    let mut searcher = Searcher::with_hash_size(SearchOptions::default(), PROMOTION_HASH_SIZE_MB);
+    let mut best: Option<(i32, ChessPiece)> = None;
+    for promotion in moves::PROMOTION_TYPES {
+        let piece = ChessPiece::new(promotion, color);
+        let mut child = *board;
+        if child.promote(piece).is_err() {
+            continue;
+        }
+        if child.get_winner() == Some(color) {
+            return Ok(piece);
+        }
+
+        let result = searcher.search(&child, SearchLimits::depth(PROMOTION_SEARCH_DEPTH));
+        let score = -result.score;
+        // Ties keep the most valuable piece
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, piece));
+        }
+    }
+
+    best.map(|(_, piece)| piece).ok_or(AiError::NoPromotion)
 }
@@ -0,0 +1,145 @@
+use std::{fmt::Display, str::FromStr};
+
+use engine::{
+    board::Board,
+    piece::{position::Position, ChessPiece, Type},
+};
+
+/// The pieces a pawn can be promoted to, from the most to the least valuable
+pub const PROMOTION_TYPES: [Type; 4] = [Type::Queen, Type::Rook, Type::Bishop, Type::Knight];
+
+/// A complete move, including the promotion choice that the engine asks for separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    pub promotion: Option<Type>,
+}
+
+impl Move {
+    pub fn new(from: Position, to: Position) -> Move {
+        Move {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    pub fn with_promotion(from: Position, to: Position, promotion: Type) -> Move {
+        Move {
+            from,
+            to,
+            promotion: Some(promotion),
+        }
+    }
+}
+
+/// Long algebraic notation, e.g. `e2e4` or `e7e8q`
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            let letter = match promotion {
+                Type::Queen => "q",
+                Type::Rook => "r",
+                Type::Bishop => "b",
+                Type::Knight => "n",
+                Type::Pawn => "p",
+                Type::King => "k",
+            };
+            write!(f, "{}", letter)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the long algebraic notation written by [`Display`]
+impl FromStr for Move {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 5) {
+            return Err(());
+        }
+        let from = Position::from_str(&s[0..2])?;
+        let to = Position::from_str(&s[2..4])?;
+        let promotion = match &s[4..] {
+            "" => None,
+            "q" => Some(Type::Queen),
+            "r" => Some(Type::Rook),
+            "b" => Some(Type::Bishop),
+            "n" => Some(Type::Knight),
+            _ => return Err(()),
+        };
+        Ok(Move {
+            from,
+            to,
+            promotion,
+        })
+    }
+}
+
+/// Returns every legal move of the side to move, pawn moves to the last rank are
+/// expanded into one move per promotion piece
+pub fn legal_moves(board: &Board) -> Vec<Move> {
+    let mut moves = Vec::new();
+    for (from, to) in board.legal_moves() {
+        if is_promotion(board, from, to) {
+            for promotion in PROMOTION_TYPES {
+                moves.push(Move::with_promotion(from, to, promotion));
+            }
+        } else {
+            moves.push(Move::new(from, to));
+        }
+    }
+    moves
+}
+
+/// Plays the move on a copy of the board, returns None if the move is not legal
+pub fn apply(board: &Board, movement: Move) -> Option<Board> {
+    let mut board = *board;
+    let color = board.get_turn();
+    board.move_piece(movement.from, movement.to).ok()?;
+
+    // A promoting move can end the game before the promotion happens
+    if board.get_promotion().is_some() {
+        let promotion = movement.promotion.unwrap_or(Type::Queen);
+        board.promote(ChessPiece::new(promotion, color)).ok()?;
+    }
+
+    Some(board)
+}
+
+/// Returns true if the move is a capture (en passant included)
+pub fn is_capture(board: &Board, movement: Move) -> bool {
+    if board.get_piece_at(&movement.to).is_some() {
+        return true;
+    }
+    let piece = board.get_piece_at(&movement.from);
+    match piece {
+        Some(piece) => piece.get_type() == Type::Pawn && movement.from.x != movement.to.x,
+        None => false,
+    }
+}
+
+/// Returns the type of the captured piece, if any
+pub fn captured_type(board: &Board, movement: Move) -> Option<Type> {
+    match board.get_piece_at(&movement.to) {
+        Some(piece) => Some(piece.get_type()),
+        None if is_capture(board, movement) => Some(Type::Pawn),
+        None => None,
+    }
+}
+
+/// Returns true if the side to move is in check
+pub fn in_check(board: &Board) -> bool {
+    board.get_check() == Some(board.get_turn())
+}
+
+fn is_promotion(board: &Board, from: Position, to: Position) -> bool {
+    let piece = match board.get_piece_at(&from) {
+        Some(piece) => piece,
+        None => return false,
+    };
+    piece.get_type() == Type::Pawn && (to.y == 0 || to.y == 7)
+}
@@ -0,0 +1,430 @@
+use std::{error::Error, fmt::Display};
+
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+    result::FenError,
+};
+
+use crate::moves::{self, Move};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    InvalidTag(String),
+    InvalidFen(FenError),
+    /// The move doesn't match any legal move, with the ply it was played at
+    IllegalMove(String, usize),
+    /// The move matches more than one legal move
+    AmbiguousMove(String, usize),
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::InvalidTag(tag) => write!(f, "Invalid tag: {}", tag),
+            PgnError::InvalidFen(error) => write!(f, "Invalid FEN tag: {}", error),
+            PgnError::IllegalMove(san, ply) => write!(f, "Illegal move at ply {}: {}", ply, san),
+            PgnError::AmbiguousMove(san, ply) => {
+                write!(f, "Ambiguous move at ply {}: {}", ply, san)
+            }
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// `*`, the game is unfinished or the result is unknown
+    Unknown,
+}
+
+impl GameResult {
+    fn parse(token: &str) -> Option<GameResult> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            GameResult::WhiteWins => Some(Color::White),
+            GameResult::BlackWins => Some(Color::Black),
+            GameResult::Draw | GameResult::Unknown => None,
+        }
+    }
+}
+
+/// `1-0`, `0-1`, `1/2-1/2` or `*`
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let token = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        };
+        write!(f, "{}", token)
+    }
+}
+
+/// A game of a PGN file, its moves are checked against the rules when parsed
+#[derive(Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    /// The position the game started from, set by the `FEN` tag
+    pub start: Board,
+    pub moves: Vec<Move>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The positions before every move, along with the move
+    pub fn positions(&self) -> impl Iterator<Item = (Board, Move)> + '_ {
+        let mut board = self.start;
+        self.moves.iter().map(move |movement| {
+            let before = board;
+            // Moves were checked when the game was parsed
+            board = moves::apply(&board, *movement).unwrap_or(board);
+            (before, *movement)
+        })
+    }
+}
+
+/// The tags, then the moves in standard algebraic notation followed by the result
+impl Display for PgnGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+        let moves = line_to_san(&self.start, 1, &self.moves);
+        match moves.is_empty() {
+            true => writeln!(f, "{}", self.result),
+            false => writeln!(f, "{} {}", moves, self.result),
+        }
+    }
+}
+
+/// Reads every game of a PGN text. Comments, variations and annotations are skipped
+pub fn parse(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut sans: Vec<String> = Vec::new();
+
+    let mut chars = pgn.chars().peekable();
+    let mut variation_depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' | '%' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth = (variation_depth - 1).max(0),
+            '[' if variation_depth == 0 => {
+                // Tags after moves start a new game, the last one had no result
+                if !sans.is_empty() {
+                    games.push(finish_game(tags, sans, GameResult::Unknown)?);
+                    tags = Vec::new();
+                    sans = Vec::new();
+                }
+                let mut tag = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    tag.push(c);
+                }
+                tags.push(parse_tag(&tag)?);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "{;()[".contains(c) {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                if variation_depth > 0 {
+                    continue;
+                }
+
+                if let Some(result) = GameResult::parse(&token) {
+                    games.push(finish_game(tags, sans, result)?);
+                    tags = Vec::new();
+                    sans = Vec::new();
+                    continue;
+                }
+                // Move numbers can be glued to the move, `12.e4` or `12...e5`
+                let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                if !san.is_empty() && !san.starts_with('$') {
+                    sans.push(san.to_string());
+                }
+            }
+        }
+    }
+
+    if !sans.is_empty() || !tags.is_empty() {
+        games.push(finish_game(tags, sans, GameResult::Unknown)?);
+    }
+    Ok(games)
+}
+
+/// `Event "Casual game"`
+fn parse_tag(tag: &str) -> Result<(String, String), PgnError> {
+    let invalid = || PgnError::InvalidTag(tag.to_string());
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((name.to_string(), value.replace("\\\"", "\"")))
+}
+
+fn finish_game(
+    tags: Vec<(String, String)>,
+    sans: Vec<String>,
+    result: GameResult,
+) -> Result<PgnGame, PgnError> {
+    let start = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => Board::from_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => Board::new(),
+    };
+
+    let mut board = start;
+    let mut moves = Vec::with_capacity(sans.len());
+    for (ply, san) in sans.iter().enumerate() {
+        let movement = parse_san(&board, san).map_err(|error| match error {
+            PgnError::IllegalMove(san, _) => PgnError::IllegalMove(san, ply),
+            PgnError::AmbiguousMove(san, _) => PgnError::AmbiguousMove(san, ply),
+            error => error,
+        })?;
+        board = moves::apply(&board, movement).ok_or(PgnError::IllegalMove(san.clone(), ply))?;
+        moves.push(movement);
+    }
+
+    Ok(PgnGame {
+        tags,
+        start,
+        moves,
+        result,
+    })
+}
+
+/// Reads a move in standard algebraic notation (`e4`, `Nbd7`, `exd8=Q+`, `O-O`...)
+pub fn parse_san(board: &Board, san: &str) -> Result<Move, PgnError> {
+    let illegal = || PgnError::IllegalMove(san.to_string(), 0);
+    let notation = san.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = moves::legal_moves(board);
+
+    let row = match board.get_turn() {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    let castling = match notation {
+        "O-O" | "0-0" => Some(6),
+        "O-O-O" | "0-0-0" => Some(2),
+        _ => None,
+    };
+    if let Some(x) = castling {
+        let movement = Move::new(Position { x: 4, y: row }, Position { x, y: row });
+        let king = board
+            .get_piece_at(&movement.from)
+            .is_some_and(|piece| piece.get_type() == Type::King);
+        return match king && legal_moves.contains(&movement) {
+            true => Ok(movement),
+            false => Err(illegal()),
+        };
+    }
+
+    if !notation.is_ascii() || notation.len() < 2 {
+        return Err(illegal());
+    }
+    let (piece_type, rest) = match type_from_letter(notation.as_bytes()[0] as char) {
+        Some(piece_type) if piece_type != Type::Pawn => (piece_type, &notation[1..]),
+        _ => (Type::Pawn, notation),
+    };
+    // `e8=Q` or `e8Q`
+    let (rest, promotion) = match rest.split_once('=') {
+        Some((rest, promotion)) => (rest, Some(promotion)),
+        None if piece_type == Type::Pawn && rest.ends_with(['N', 'B', 'R', 'Q']) => {
+            (&rest[..rest.len() - 1], Some(&rest[rest.len() - 1..]))
+        }
+        None => (rest, None),
+    };
+    let promotion = match promotion {
+        Some(promotion) => match promotion.chars().next().and_then(type_from_letter) {
+            Some(piece_type) => Some(piece_type),
+            None => return Err(illegal()),
+        },
+        None => None,
+    };
+
+    let rest = rest.replace(['x', ':', '-'], "");
+    if rest.len() < 2 {
+        return Err(illegal());
+    }
+    let to: Position = rest[rest.len() - 2..].parse().map_err(|_| illegal())?;
+    let disambiguation = &rest[..rest.len() - 2];
+
+    let candidates: Vec<Move> = legal_moves
+        .into_iter()
+        .filter(|movement| {
+            movement.to == to
+                && movement.promotion == promotion
+                && board
+                    .get_piece_at(&movement.from)
+                    .is_some_and(|piece| piece.get_type() == piece_type)
+                && disambiguation.chars().all(|c| {
+                    let square = movement.from.to_string();
+                    square.contains(c)
+                })
+        })
+        .collect();
+
+    match candidates[..] {
+        [movement] => Ok(movement),
+        [] => Err(illegal()),
+        _ => Err(PgnError::AmbiguousMove(san.to_string(), 0)),
+    }
+}
+
+/// Writes a legal move in standard algebraic notation, with the `+` or `#` suffix
+pub fn to_san(board: &Board, movement: Move) -> String {
+    let piece_type = board
+        .get_piece_at(&movement.from)
+        .map_or(Type::Pawn, |piece| piece.get_type());
+    let mut san = if piece_type == Type::King && (movement.to.x - movement.from.x).abs() == 2 {
+        match movement.to.x > movement.from.x {
+            true => "O-O".to_string(),
+            false => "O-O-O".to_string(),
+        }
+    } else {
+        let mut san = String::new();
+        let capture = moves::is_capture(board, movement);
+        let from = movement.from.to_string();
+        if piece_type == Type::Pawn {
+            if capture {
+                san.push_str(&from[..1]);
+            }
+        } else {
+            san.push(type_letter(piece_type));
+            // Other pieces of the same type that can go to the same square
+            let rivals: Vec<Position> = moves::legal_moves(board)
+                .into_iter()
+                .filter(|other| {
+                    other.to == movement.to
+                        && other.from != movement.from
+                        && board
+                            .get_piece_at(&other.from)
+                            .is_some_and(|piece| piece.get_type() == piece_type)
+                })
+                .map(|other| other.from)
+                .collect();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|rival| rival.x != movement.from.x) {
+                    san.push_str(&from[..1]);
+                } else if rivals.iter().all(|rival| rival.y != movement.from.y) {
+                    san.push_str(&from[1..]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&movement.to.to_string());
+        if let Some(promotion) = movement.promotion {
+            san.push('=');
+            san.push(type_letter(promotion));
+        }
+        san
+    };
+
+    if let Some(child) = moves::apply(board, movement) {
+        if child.get_winner() == Some(board.get_turn()) {
+            san.push('#');
+        } else if moves::in_check(&child) {
+            san.push('+');
+        }
+    }
+    san
+}
+
+/// `1. e4 e5 2. Nf3`, `number` is the move number of the first move. The board doesn't keep
+/// the fullmove number of its FEN
+pub fn line_to_san(board: &Board, mut number: u32, line: &[Move]) -> String {
+    let mut board = *board;
+    let mut sans = Vec::with_capacity(line.len());
+    for (index, movement) in line.iter().enumerate() {
+        match board.get_turn() {
+            Color::White => sans.push(format!("{}.", number)),
+            Color::Black if index == 0 => sans.push(format!("{}...", number)),
+            Color::Black => {}
+        }
+        sans.push(to_san(&board, *movement));
+        if board.get_turn() == Color::Black {
+            number += 1;
+        }
+        match moves::apply(&board, *movement) {
+            Some(child) => board = child,
+            None => break,
+        }
+    }
+    sans.join(" ")
+}
+
+fn type_letter(piece_type: Type) -> char {
+    match piece_type {
+        Type::Pawn => 'P',
+        Type::Knight => 'N',
+        Type::Bishop => 'B',
+        Type::Rook => 'R',
+        Type::Queen => 'Q',
+        Type::King => 'K',
+    }
+}
+
+fn type_from_letter(letter: char) -> Option<Type> {
+    match letter {
+        'P' => Some(Type::Pawn),
+        'N' => Some(Type::Knight),
+        'B' => Some(Type::Bishop),
+        'R' => Some(Type::Rook),
+        'Q' => Some(Type::Queen),
+        'K' => Some(Type::King),
+        _ => None,
+    }
+}
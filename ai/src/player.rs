@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+    player::Player,
+};
+use rand::Rng;
+
+use crate::{
+    book::{Book, BookSettings},
+    search::hash,
+    strength::{Level, LimitedSearcher},
+};
+
+/// Plays random legal moves
+pub struct RandomPlayer<R: Rng> {
+    rng: R,
+}
+
+impl<R: Rng> RandomPlayer<R> {
+    pub fn new(rng: R) -> RandomPlayer<R> {
+        RandomPlayer { rng }
+    }
+}
+
+impl<R: Rng> Player for RandomPlayer<R> {
+    fn choose_move(&mut self, board: &Board) -> Option<(Position, Position)> {
+        crate::make_move(board, &mut self.rng).ok()
+    }
+
+    fn choose_promotion(&mut self, board: &Board) -> Type {
+        promotion(board)
+    }
+}
+
+/// Searches its moves at the given level
+pub struct AiPlayer<R: Rng> {
+    searcher: LimitedSearcher,
+    rng: R,
+    /// Promotion of the last searched move
+    promotion: Option<Type>,
+    book: Option<(Book, BookSettings)>,
+    /// Moves this player played, to know when the book depth is reached
+    moves_played: usize,
+}
+
+impl<R: Rng> AiPlayer<R> {
+    pub fn new(level: Level, rng: R) -> AiPlayer<R> {
+        AiPlayer {
+            searcher: LimitedSearcher::new(level),
+            rng,
+            promotion: None,
+            book: None,
+            moves_played: 0,
+        }
+    }
+
+    /// Opens the games with book moves, as long as the book has some
+    pub fn with_book(mut self, book: Book, settings: BookSettings) -> AiPlayer<R> {
+        self.book = Some((book, settings));
+        self
+    }
+
+    pub fn level(&self) -> Level {
+        self.searcher.level()
+    }
+
+    /// Time the next moves can take at most, see [`LimitedSearcher::set_time`]. Book moves are
+    /// played at once
+    pub fn set_time(&mut self, time: Option<Duration>) {
+        self.searcher.set_time(time);
+    }
+}
+
+impl<R: Rng> Player for AiPlayer<R> {
+    fn choose_move(&mut self, board: &Board) -> Option<(Position, Position)> {
+        // Games are assumed to start from the initial position, where White moves first
+        let ply = self.moves_played * 2 + (board.get_turn() == Color::Black) as usize;
+        let book_move = self
+            .book
+            .as_ref()
+            .and_then(|(book, settings)| book.choose_move(board, ply, *settings, &mut self.rng));
+        let movement = match book_move {
+            Some(movement) => movement,
+            None => self.searcher.choose_move(board, &mut self.rng).ok()?,
+        };
+        self.promotion = movement.promotion;
+        Some((movement.from, movement.to))
+    }
+
+    fn choose_promotion(&mut self, board: &Board) -> Type {
+        self.promotion.take().unwrap_or_else(|| promotion(board))
+    }
+
+    /// The search sees the repetitions coming
+    fn set_history(&mut self, history: &[Board]) {
+        self.searcher
+            .set_history(history.iter().map(hash::hash).collect());
+    }
+
+    fn played(&mut self) {
+        self.moves_played += 1;
+    }
+}
+
+fn promotion(board: &Board) -> Type {
+    crate::make_promotion(board).map_or(Type::Queen, |piece| piece.get_type())
+}
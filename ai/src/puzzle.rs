@@ -0,0 +1,346 @@
+use std::{fmt::Display, str::FromStr, sync::atomic::AtomicBool};
+
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+    tactics::Motif,
+};
+
+use crate::{
+    analysis::{Analyser, AnalysisError, MoveClass},
+    moves::{self, Move},
+    pgn::PgnGame,
+    search::{hash, SearchLimits, SearchOptions, Searcher, MATE, MATE_BOUND},
+};
+
+/// Plies searched to score every move of a candidate position
+pub const DEFAULT_PUZZLE_DEPTH: u8 = 8;
+
+/// A move wins decisively when it scores at least this much
+const DECISIVE_SCORE: i32 = 300;
+/// Every other move must score under this for the winning move to be unique
+const ALTERNATIVE_MAX_SCORE: i32 = 150;
+/// Moves of the solving side in the longest solution
+const MAX_SOLUTION_MOVES: usize = 5;
+
+/// Difficulty of a one move solution found at the first ply, a rating like the players'
+const BASE_DIFFICULTY: u32 = 900;
+const MIN_DIFFICULTY: u32 = 600;
+const MAX_DIFFICULTY: u32 = 2800;
+
+/// Tags of a puzzle, written like the big sites write them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Theme {
+    Fork,
+    Pin,
+    Skewer,
+    DiscoveredAttack,
+    HangingPiece,
+    Mate,
+    BackRankMate,
+    SmotheredMate,
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Theme::Fork => "fork",
+            Theme::Pin => "pin",
+            Theme::Skewer => "skewer",
+            Theme::DiscoveredAttack => "discoveredAttack",
+            Theme::HangingPiece => "hangingPiece",
+            Theme::Mate => "mate",
+            Theme::BackRankMate => "backRankMate",
+            Theme::SmotheredMate => "smotheredMate",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fork" => Ok(Theme::Fork),
+            "pin" => Ok(Theme::Pin),
+            "skewer" => Ok(Theme::Skewer),
+            "discoveredAttack" => Ok(Theme::DiscoveredAttack),
+            "hangingPiece" => Ok(Theme::HangingPiece),
+            "mate" => Ok(Theme::Mate),
+            "backRankMate" => Ok(Theme::BackRankMate),
+            "smotheredMate" => Ok(Theme::SmotheredMate),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A position where one move wins, and the forcing line that follows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    /// The position to solve, the side to move plays the first move of the solution
+    pub fen: String,
+    /// Moves of both sides, starting and ending with a move of the solving side
+    pub solution: Vec<Move>,
+    /// Estimated rating of the players who solve it half of the time
+    pub difficulty: u32,
+    pub themes: Vec<Theme>,
+}
+
+/// Header of the CSV written by [`Puzzle`]'s `Display`
+pub const CSV_HEADER: &str = "FEN,Moves,Difficulty,Themes";
+
+impl Puzzle {
+    /// Number of moves of the mate, None when the solution doesn't mate
+    pub fn mate_in(&self) -> Option<usize> {
+        match self.themes.contains(&Theme::Mate) {
+            true => Some(self.solution.len().div_ceil(2)),
+            false => None,
+        }
+    }
+}
+
+/// A CSV line: the FEN, the solution in long algebraic notation, the difficulty and the themes
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let solution: Vec<String> = self.solution.iter().map(|m| m.to_string()).collect();
+        let themes: Vec<String> = self.themes.iter().map(|t| t.to_string()).collect();
+        write!(
+            f,
+            "{},{},{},{}",
+            self.fen,
+            solution.join(" "),
+            self.difficulty,
+            themes.join(" ")
+        )
+    }
+}
+
+/// Finds puzzles in played games. Candidates are the positions right after a mistake or a
+/// blunder, they become puzzles when exactly one move wins decisively. The solution goes on
+/// while the winning side keeps having a single winning move, the defender playing the
+/// engine's best replies
+pub struct PuzzleFinder {
+    searcher: Searcher,
+    analyser: Analyser,
+    depth: u8,
+}
+
+impl Default for PuzzleFinder {
+    fn default() -> Self {
+        PuzzleFinder::new(DEFAULT_PUZZLE_DEPTH)
+    }
+}
+
+impl PuzzleFinder {
+    pub fn new(depth: u8) -> PuzzleFinder {
+        let depth = depth.max(2);
+        PuzzleFinder {
+            searcher: Searcher::default(),
+            // The candidates only need a rough look
+            analyser: Analyser::new(SearchLimits::depth(depth - 1)),
+            depth,
+        }
+    }
+
+    pub fn find_in_pgn(&mut self, game: &PgnGame) -> Result<Vec<Puzzle>, AnalysisError> {
+        self.find_in_game(&game.start, &game.moves)
+    }
+
+    pub fn find_in_game(
+        &mut self,
+        start: &Board,
+        game_moves: &[Move],
+    ) -> Result<Vec<Puzzle>, AnalysisError> {
+        let analysis = self.analyser.analyse(start, game_moves)?;
+        let mut board = *start;
+        let mut puzzles = Vec::new();
+        for analysed in &analysis.moves {
+            board = moves::apply(&board, analysed.movement).unwrap_or(board);
+            if analysed.class < MoveClass::Mistake || board.get_winner().is_some() {
+                continue;
+            }
+            if let Some(puzzle) = self.puzzle_at(&board) {
+                puzzles.push(puzzle);
+            }
+        }
+        Ok(puzzles)
+    }
+
+    /// The puzzle of the position, when the side to move has a single winning move
+    pub fn puzzle_at(&mut self, board: &Board) -> Option<Puzzle> {
+        let solver = board.get_turn();
+        let first = self.unique_winning_move(board)?;
+        let found_at = self.depth_found(board, first);
+
+        let mut solution = Vec::new();
+        let mut position = *board;
+        let mut key = first;
+        loop {
+            solution.push(key);
+            position = moves::apply(&position, key)?;
+            let solver_moves = solution.len().div_ceil(2);
+            if position.get_winner() == Some(solver) || solver_moves >= MAX_SOLUTION_MOVES {
+                break;
+            }
+
+            let limits = SearchLimits::depth(self.depth);
+            let Some(reply) = self.searcher.search(&position, limits).best_move else {
+                break;
+            };
+            let Some(next) = moves::apply(&position, reply) else {
+                break;
+            };
+            let Some(next_key) = self.unique_winning_move(&next) else {
+                break;
+            };
+            solution.push(reply);
+            position = next;
+            key = next_key;
+        }
+
+        let themes = themes(board, &solution);
+        Some(Puzzle {
+            fen: board.to_fen(),
+            difficulty: difficulty(board, &solution, found_at),
+            solution,
+            themes,
+        })
+    }
+
+    /// The only move that wins decisively. When it mates every other move must mate slower
+    fn unique_winning_move(&mut self, board: &Board) -> Option<Move> {
+        let limits = SearchLimits::depth(self.depth);
+        self.searcher.set_history(Vec::new());
+        if self.searcher.search(board, limits).score < DECISIVE_SCORE {
+            return None;
+        }
+
+        // Every move is searched from its own position, the root joins the history
+        self.searcher.set_history(vec![hash::hash(board)]);
+        let child_limits = SearchLimits::depth(self.depth - 1);
+        let mut scores: Vec<(i32, Move)> = moves::legal_moves(board)
+            .into_iter()
+            .filter_map(|movement| {
+                let child = moves::apply(board, movement)?;
+                let score = match child.get_winner() == Some(board.get_turn()) {
+                    true => MATE - 1,
+                    false => -self.searcher.search(&child, child_limits).score,
+                };
+                Some((score, movement))
+            })
+            .collect();
+        scores.sort_by_key(|(score, _)| -score);
+
+        let (best, key) = *scores.first()?;
+        let second = scores.get(1).map_or(i32::MIN, |(score, _)| *score);
+        let unique = match best > MATE_BOUND {
+            true => second < best,
+            false => second < ALTERNATIVE_MAX_SCORE,
+        };
+        match best >= DECISIVE_SCORE && unique {
+            true => Some(key),
+            false => None,
+        }
+    }
+
+    /// First depth from which a new search sticks to the key
+    fn depth_found(&self, board: &Board, key: Move) -> u8 {
+        let mut searcher = Searcher::with_hash_size(SearchOptions::default(), 1);
+        let stop = AtomicBool::new(false);
+        let mut found = None;
+        searcher.search_with(
+            board,
+            SearchLimits::depth(self.depth),
+            &stop,
+            |result| match result.best_move == Some(key) {
+                true => found = found.or(Some(result.depth)),
+                false => found = None,
+            },
+        );
+        found.unwrap_or(self.depth)
+    }
+}
+
+/// Longer solutions, quiet keys and keys the search finds late are harder
+fn difficulty(board: &Board, solution: &[Move], found_at: u8) -> u32 {
+    let key = solution[0];
+    let solver_moves = (solution.len() as u32).div_ceil(2);
+    let quiet = !moves::is_capture(board, key)
+        && !moves::apply(board, key).is_some_and(|child| moves::in_check(&child));
+
+    let mut difficulty = BASE_DIFFICULTY + 250 * (solver_moves - 1) + 120 * (found_at as u32 - 1);
+    if quiet {
+        difficulty += 300;
+    }
+    difficulty.clamp(MIN_DIFFICULTY, MAX_DIFFICULTY)
+}
+
+/// Themes of the moves of the solving side, and the pattern of the final mate
+fn themes(board: &Board, solution: &[Move]) -> Vec<Theme> {
+    let solver = board.get_turn();
+    let mut themes = Vec::new();
+    let mut position = *board;
+    for (index, movement) in solution.iter().enumerate() {
+        let Some(child) = moves::apply(&position, *movement) else {
+            break;
+        };
+        if index % 2 == 0 {
+            let motifs = position.move_motifs(movement.from, movement.to);
+            themes.extend(motifs.iter().filter_map(motif_theme));
+        }
+        if child.get_winner() == Some(solver) {
+            themes.push(Theme::Mate);
+            themes.extend(mate_pattern(&child, movement.to));
+        }
+        position = child;
+    }
+    themes.sort();
+    themes.dedup();
+    themes
+}
+
+/// The themes of the patterns a move plays, overloading and back-rank weaknesses only show in
+/// the mate that follows
+fn motif_theme(motif: &Motif) -> Option<Theme> {
+    match motif {
+        Motif::Fork(_) => Some(Theme::Fork),
+        Motif::Pin(_) => Some(Theme::Pin),
+        Motif::Skewer(_) => Some(Theme::Skewer),
+        Motif::DiscoveredAttack(_) => Some(Theme::DiscoveredAttack),
+        Motif::HangingPiece(_) => Some(Theme::HangingPiece),
+        Motif::OverloadedDefender(_) | Motif::BackRankWeakness(_) => None,
+    }
+}
+
+fn mate_pattern(board: &Board, mating_square: Position) -> Option<Theme> {
+    let mating = board.get_piece_at(&mating_square)?;
+    let color = mating.get_color().opponent();
+    let king = find_king(board, color)?;
+
+    let smothered = board.attacked_squares(king).iter().all(|square| {
+        board
+            .get_piece_at(square)
+            .is_some_and(|piece| piece.get_color() == color)
+    });
+    if mating.get_type() == Type::Knight && smothered {
+        return Some(Theme::SmotheredMate);
+    }
+
+    let heavy = matches!(mating.get_type(), Type::Rook | Type::Queen);
+    let weakness = board.back_rank_weakness(mating.get_color());
+    if heavy && weakness.is_some() && mating_square.y == king.y {
+        return Some(Theme::BackRankMate);
+    }
+    None
+}
+
+fn find_king(board: &Board, color: Color) -> Option<Position> {
+    (0..8)
+        .flat_map(|y| (0..8).map(move |x| Position { x, y }))
+        .find(|square| {
+            board
+                .get_piece_at(square)
+                .is_some_and(|piece| piece.get_type() == Type::King && piece.get_color() == color)
+        })
+}
@@ -0,0 +1,21 @@
+use std::{error::Error, fmt::Display};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AiError {
+    /// The side to move is checkmated, stalemated or has a promotion to choose first
+    NoLegalMoves,
+    /// There is no pawn waiting to be promoted
+    NoPromotion,
+}
+
+impl Display for AiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let error_message = match self {
+            AiError::NoLegalMoves => "No legal moves",
+            AiError::NoPromotion => "No promotion",
+        };
+        write!(f, "{}", error_message)
+    }
+}
+
+impl Error for AiError {}
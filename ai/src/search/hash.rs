@@ -0,0 +1,117 @@
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+    result::OkMovement,
+};
+
+const PIECE_KEYS: usize = 2 * 6 * 64;
+const CASTLING_KEYS: usize = PIECE_KEYS;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const TURN_KEY: usize = EN_PASSANT_KEYS + 8;
+const KEYS_LEN: usize = TURN_KEY + 1;
+
+static KEYS: [u64; KEYS_LEN] = generate_keys();
+
+/// Fills the key table with a splitmix64 sequence, so hashes are the same on every run
+const fn generate_keys() -> [u64; KEYS_LEN] {
+    let mut keys = [0; KEYS_LEN];
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut i = 0;
+    while i < KEYS_LEN {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Zobrist hash of the position: pieces, side to move, castling rights and en passant file
+pub fn hash(board: &Board) -> u64 {
+    let mut hash = 0;
+
+    for (y, row) in board.get_pieces().iter().enumerate() {
+        for (x, piece) in row.iter().enumerate() {
+            if let Some(piece) = piece {
+                let color = match piece.get_color() {
+                    Color::White => 0,
+                    Color::Black => 1,
+                };
+                let piece_type = type_index(piece.get_type());
+                hash ^= KEYS[(color * 6 + piece_type) * 64 + y * 8 + x];
+            }
+        }
+    }
+
+    for (i, castling) in castling_rights(board).into_iter().enumerate() {
+        if castling {
+            hash ^= KEYS[CASTLING_KEYS + i];
+        }
+    }
+
+    if let Some(file) = en_passant_file(board) {
+        hash ^= KEYS[EN_PASSANT_KEYS + file as usize];
+    }
+
+    if board.get_turn() == Color::White {
+        hash ^= KEYS[TURN_KEY];
+    }
+
+    hash
+}
+
+// (king, rook) for white king side, white queen side, black king side, black queen side
+const CASTLING_SQUARES: [(Position, Position); 4] = [
+    (Position { x: 4, y: 0 }, Position { x: 7, y: 0 }),
+    (Position { x: 4, y: 0 }, Position { x: 0, y: 0 }),
+    (Position { x: 4, y: 7 }, Position { x: 7, y: 7 }),
+    (Position { x: 4, y: 7 }, Position { x: 0, y: 7 }),
+];
+
+/// Castling rights in the order white king side, white queen side, black king side, black
+/// queen side
+pub(crate) fn castling_rights(board: &Board) -> [bool; 4] {
+    CASTLING_SQUARES.map(|(king, rook)| !has_moved(board, &king) && !has_moved(board, &rook))
+}
+
+fn has_moved(board: &Board, position: &Position) -> bool {
+    match board.get_piece_at(position) {
+        Some(piece) => piece.moved,
+        None => true,
+    }
+}
+
+/// The file of a pawn that can be captured en passant, only when an enemy pawn is next to it
+pub(crate) fn en_passant_file(board: &Board) -> Option<i32> {
+    let to = match board.get_last_move() {
+        Some(Ok(OkMovement::InitialDoubleAdvance((_, to)))) => to,
+        _ => return None,
+    };
+
+    let capturer = [to.x - 1, to.x + 1].into_iter().any(|x| {
+        let piece = board.get_piece_at(&Position { x, y: to.y });
+        match piece {
+            Some(piece) => piece.get_type() == Type::Pawn && piece.get_color() == board.get_turn(),
+            None => false,
+        }
+    });
+
+    if capturer {
+        Some(to.x)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn type_index(piece_type: Type) -> usize {
+    match piece_type {
+        Type::Pawn => 0,
+        Type::Knight => 1,
+        Type::Bishop => 2,
+        Type::Rook => 3,
+        Type::Queen => 4,
+        Type::King => 5,
+    }
+}
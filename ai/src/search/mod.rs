@@ -0,0 +1,303 @@
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use engine::board::Board;
+
+use crate::{
+    moves::{self, Move},
+    tablebase::{self, Tablebase, Wdl},
+};
+
+use self::{tt::TranspositionTable, worker::Worker};
+
+pub mod hash;
+mod tt;
+mod worker;
+
+#[cfg(test)]
+mod test;
+
+/// Score of a checkmate at the root, mates found deeper are worth `MATE - ply`
+pub const MATE: i32 = 30_000;
+const INFINITY: i32 = 32_000;
+const MAX_PLY: usize = 128;
+const MAX_DEPTH: u8 = 64;
+/// Scores above this value (or under its negation) are mate scores
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+/// Score of a tablebase win at the root, above any evaluation and below every mate
+pub const TB_WIN: i32 = MATE_BOUND - MAX_PLY as i32;
+
+pub const DEFAULT_HASH_SIZE_MB: usize = 16;
+
+/// How often (in nodes) the clock is checked
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+const NULL_MOVE_MIN_DEPTH: i32 = 3;
+const NULL_MOVE_REDUCTION: i32 = 2;
+/// From this depth on a null move cutoff is only trusted after a normal reduced search agrees,
+/// this protects against zugzwang positions where passing would be the best "move"
+const NULL_MOVE_VERIFICATION_DEPTH: i32 = 6;
+
+const LMR_MIN_DEPTH: i32 = 3;
+const LMR_MIN_MOVES: usize = 3;
+
+const FUTILITY_MARGINS: [i32; 3] = [0, 200, 500];
+const REVERSE_FUTILITY_DEPTH: i32 = 3;
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+
+const DELTA_MARGIN: i32 = 200;
+
+/// Selective search techniques, every one of them can be turned off to measure its effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub null_move_pruning: bool,
+    pub late_move_reductions: bool,
+    /// Futility pruning of quiet moves near the leaves and reverse futility pruning
+    pub futility_pruning: bool,
+    pub check_extensions: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            null_move_pruning: true,
+            late_move_reductions: true,
+            futility_pruning: true,
+            check_extensions: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Plain alpha-beta, without any selective technique
+    pub fn plain() -> Self {
+        SearchOptions {
+            null_move_pruning: false,
+            late_move_reductions: false,
+            futility_pruning: false,
+            check_extensions: false,
+        }
+    }
+}
+
+/// When to stop searching, the search stops at the first limit reached.
+/// Without any limit the search goes up to the maximum depth
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        SearchLimits {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+
+    /// Time to spend on a single move given the player's remaining clock and increment
+    pub fn from_clock(remaining: Duration, increment: Duration) -> Self {
+        // Plan for 30 more moves
+        SearchLimits::from_clock_and_moves(remaining, increment, 30)
+    }
+
+    /// Time to spend on a single move when the clock has to last `moves_to_go` more moves
+    pub fn from_clock_and_moves(
+        remaining: Duration,
+        increment: Duration,
+        moves_to_go: u32,
+    ) -> Self {
+        // Never use more than half of what is left
+        let time = remaining / moves_to_go.max(1) + increment * 3 / 4;
+        let time = time.min(remaining / 2);
+        SearchLimits::time(time)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// None only when the side to move has no legal move
+    pub best_move: Option<Move>,
+    /// Centipawns from the point of view of the side to move
+    pub score: i32,
+    /// Last fully searched depth
+    pub depth: u8,
+    pub nodes: u64,
+    /// Principal variation, starting with the best move
+    pub pv: Vec<Move>,
+}
+
+/// Iterative deepening alpha-beta search (principal variation search) with a quiescence search
+/// at the leaves. The transposition table is kept between searches.
+///
+/// With more than one thread the search runs as Lazy SMP: every thread searches the same
+/// position and they only cooperate through the shared transposition table. A single thread
+/// search at a fixed depth is deterministic
+pub struct Searcher {
+    options: SearchOptions,
+    threads: usize,
+    tt: TranspositionTable,
+    history: Vec<u64>,
+    tablebase: Option<Arc<dyn Tablebase>>,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Searcher::new(SearchOptions::default())
+    }
+}
+
+impl Searcher {
+    pub fn new(options: SearchOptions) -> Searcher {
+        Searcher::with_hash_size(options, DEFAULT_HASH_SIZE_MB)
+    }
+
+    pub fn with_hash_size(options: SearchOptions, hash_size_mb: usize) -> Searcher {
+        Searcher {
+            options,
+            threads: 1,
+            tt: TranspositionTable::new(hash_size_mb),
+            history: Vec::new(),
+            tablebase: None,
+        }
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Number of threads used by the next searches, at least one
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Replaces the transposition table with an empty one of roughly `hash_size_mb` megabytes
+    pub fn set_hash_size(&mut self, hash_size_mb: usize) {
+        self.tt = TranspositionTable::new(hash_size_mb);
+    }
+
+    /// Hashes (see [`hash::hash`]) of the positions played before the searched one, oldest
+    /// first. Moves that repeat one of them are scored as draws
+    pub fn set_history(&mut self, history: Vec<u64>) {
+        self.history = history;
+    }
+
+    /// Endgame tables probed in the search. Positions of the tree found in the tables are
+    /// scored from them, and a root position found in them is played from the tables at once
+    pub fn set_tablebase(&mut self, tablebase: Option<Arc<dyn Tablebase>>) {
+        self.tablebase = tablebase;
+        // Scores stored without the tables are not comparable
+        self.tt.clear();
+    }
+
+    /// Forgets everything learned in previous searches
+    pub fn clear(&mut self) {
+        self.tt.clear();
+    }
+
+    pub fn search(&mut self, board: &Board, limits: SearchLimits) -> SearchResult {
+        let stop = AtomicBool::new(false);
+        self.search_with(board, limits, &stop, |_| {})
+    }
+
+    /// Searches until a limit is reached or `stop` is set from another thread, the flag is set
+    /// when the search ends. `on_iteration` receives the result of every completed depth
+    pub fn search_with(
+        &mut self,
+        board: &Board,
+        limits: SearchLimits,
+        stop: &AtomicBool,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        if let Some(result) = self.tablebase_result(board) {
+            on_iteration(&result);
+            stop.store(true, Ordering::Relaxed);
+            return result;
+        }
+
+        let tt = &self.tt;
+        let options = self.options;
+        let history = &self.history[..];
+        let tablebase = self.tablebase.as_deref();
+
+        thread::scope(|scope| {
+            let helpers: Vec<_> = (1..self.threads)
+                .map(|id| {
+                    scope.spawn(move || {
+                        let mut worker =
+                            Worker::new(id, options, tt, stop, limits, history, tablebase);
+                        worker.search(board, &mut |_| {});
+                        worker.nodes()
+                    })
+                })
+                .collect();
+
+            let mut main = Worker::new(0, options, tt, stop, limits, history, tablebase);
+            let mut result = main.search(board, &mut on_iteration);
+
+            // The main thread is done, the helpers must follow
+            stop.store(true, Ordering::Relaxed);
+            for helper in helpers {
+                // A panic in a helper is a bug of the search, not a helper without nodes
+                match helper.join() {
+                    Ok(nodes) => result.nodes += nodes,
+                    Err(panic) => panic::resume_unwind(panic),
+                }
+            }
+
+            result
+        })
+    }
+
+    /// The best move according to the tables, when the root position is in them
+    fn tablebase_result(&self, board: &Board) -> Option<SearchResult> {
+        let tablebase = self.tablebase.as_deref()?;
+        let best_move = *tablebase::root_moves(tablebase, board)?.first()?;
+
+        let child = moves::apply(board, best_move)?;
+        let score = if child.get_winner() == Some(board.get_turn()) {
+            MATE - 1
+        } else {
+            tablebase_score(tablebase.probe_wdl(&child)?.opponent(), 0)
+        };
+
+        Some(SearchResult {
+            best_move: Some(best_move),
+            score,
+            depth: 1,
+            nodes: 1,
+            pv: vec![best_move],
+        })
+    }
+}
+
+/// Score of a tablebase result `ply` plies away from the root, wins found closer are better
+fn tablebase_score(wdl: Wdl, ply: usize) -> i32 {
+    match wdl {
+        Wdl::Win => TB_WIN - ply as i32,
+        Wdl::CursedWin => 1,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -1,
+        Wdl::Loss => -TB_WIN + ply as i32,
+    }
+}
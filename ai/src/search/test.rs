@@ -0,0 +1,299 @@
+use std::str::FromStr;
+
+use engine::{board::Board, piece::position::Position};
+
+use crate::{
+    moves::Move,
+    search::{SearchLimits, SearchOptions, Searcher, MATE},
+};
+
+fn play(moves: &[&str]) -> Board {
+    let mut board = Board::new();
+    for movement in moves {
+        let (from, to) = movement.split_once(' ').unwrap();
+        let from = Position::from_str(from).unwrap();
+        let to = Position::from_str(to).unwrap();
+        board.move_piece(from, to).unwrap();
+    }
+    board
+}
+
+fn italian_game() -> Board {
+    play(&["e2 e4", "e7 e5", "g1 f3", "b8 c6", "f1 c4", "f8 c5"])
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let board = play(&["e2 e4", "e7 e5", "f1 c4", "b8 c6", "d1 h5", "g8 f6"]);
+    let mut searcher = Searcher::default();
+    let result = searcher.search(&board, SearchLimits::depth(3));
+
+    let expected = Move::new(
+        Position::from_str("h5").unwrap(),
+        Position::from_str("f7").unwrap(),
+    );
+    assert_eq!(result.best_move, Some(expected));
+    assert_eq!(result.score, MATE - 1);
+}
+
+#[test]
+fn captures_hanging_queen() {
+    let board = play(&["e2 e4", "d7 d6", "d1 g4"]);
+    let mut searcher = Searcher::default();
+    let result = searcher.search(&board, SearchLimits::depth(3));
+
+    let expected = Move::new(
+        Position::from_str("c8").unwrap(),
+        Position::from_str("g4").unwrap(),
+    );
+    assert_eq!(result.best_move, Some(expected));
+}
+
+#[test]
+fn no_legal_moves() {
+    let board = play(&["f2 f3", "e7 e5", "g2 g4", "d8 h4"]);
+    let mut searcher = Searcher::default();
+    let result = searcher.search(&board, SearchLimits::depth(3));
+
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.nodes, 0);
+}
+
+#[test]
+fn node_limit() {
+    let board = italian_game();
+    let mut searcher = Searcher::default();
+    let limits = SearchLimits {
+        nodes: Some(500),
+        ..Default::default()
+    };
+    let result = searcher.search(&board, limits);
+
+    assert!(result.best_move.is_some());
+    assert!(result.nodes <= 500);
+}
+
+#[cfg(test)]
+mod node_count {
+    use crate::search::{SearchLimits, SearchOptions, Searcher};
+
+    use super::italian_game;
+
+    const DEPTH: u8 = 4;
+
+    fn nodes(options: SearchOptions) -> u64 {
+        let mut searcher = Searcher::new(options);
+        let result = searcher.search(&italian_game(), SearchLimits::depth(DEPTH));
+        assert_eq!(result.depth, DEPTH);
+        result.nodes
+    }
+
+    #[test]
+    fn fixed_depth_is_deterministic() {
+        assert_eq!(
+            nodes(SearchOptions::default()),
+            nodes(SearchOptions::default())
+        );
+    }
+
+    #[test]
+    fn selective_search_visits_fewer_nodes() {
+        let plain = nodes(SearchOptions::plain());
+        let selective = nodes(SearchOptions {
+            check_extensions: false,
+            ..Default::default()
+        });
+        assert!(selective < plain, "{} >= {}", selective, plain);
+    }
+
+    #[test]
+    fn null_move_pruning_visits_fewer_nodes() {
+        let plain = nodes(SearchOptions::plain());
+        let null_move = nodes(SearchOptions {
+            null_move_pruning: true,
+            ..SearchOptions::plain()
+        });
+        assert!(null_move < plain, "{} >= {}", null_move, plain);
+    }
+
+    #[test]
+    fn late_move_reductions_visit_fewer_nodes() {
+        let plain = nodes(SearchOptions::plain());
+        let reductions = nodes(SearchOptions {
+            late_move_reductions: true,
+            ..SearchOptions::plain()
+        });
+        assert!(reductions < plain, "{} >= {}", reductions, plain);
+    }
+
+    #[test]
+    fn futility_pruning_visits_fewer_nodes() {
+        let plain = nodes(SearchOptions::plain());
+        let futility = nodes(SearchOptions {
+            futility_pruning: true,
+            ..SearchOptions::plain()
+        });
+        assert!(futility < plain, "{} >= {}", futility, plain);
+    }
+}
+
+#[test]
+fn plain_and_selective_search_agree_on_tactics() {
+    let board = play(&["e2 e4", "e7 e5", "f1 c4", "b8 c6", "d1 h5", "g8 f6"]);
+    let plain = Searcher::new(SearchOptions::plain()).search(&board, SearchLimits::depth(2));
+    let selective = Searcher::default().search(&board, SearchLimits::depth(2));
+    assert_eq!(plain.best_move, selective.best_move);
+    assert_eq!(plain.score, selective.score);
+}
+
+#[cfg(test)]
+mod threads {
+    use std::str::FromStr;
+
+    use engine::piece::position::Position;
+
+    use crate::{
+        moves::Move,
+        search::{SearchLimits, Searcher, MATE},
+    };
+
+    use super::{italian_game, play};
+
+    #[test]
+    fn multithreaded_search_finds_mate() {
+        let board = play(&["e2 e4", "e7 e5", "f1 c4", "b8 c6", "d1 h5", "g8 f6"]);
+        let mut searcher = Searcher::default();
+        searcher.set_threads(4);
+        let result = searcher.search(&board, SearchLimits::depth(3));
+
+        let expected = Move::new(
+            Position::from_str("h5").unwrap(),
+            Position::from_str("f7").unwrap(),
+        );
+        assert_eq!(result.best_move, Some(expected));
+        assert_eq!(result.score, MATE - 1);
+    }
+
+    #[test]
+    fn multithreaded_search_reaches_depth() {
+        let mut searcher = Searcher::default();
+        searcher.set_threads(3);
+        let result = searcher.search(&italian_game(), SearchLimits::depth(3));
+
+        assert_eq!(result.depth, 3);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn at_least_one_thread() {
+        let mut searcher = Searcher::default();
+        searcher.set_threads(0);
+        assert_eq!(searcher.threads(), 1);
+    }
+}
+
+#[cfg(test)]
+mod transposition_table {
+    use std::str::FromStr;
+
+    use engine::piece::{position::Position, Type};
+
+    use crate::{
+        moves::Move,
+        search::tt::{Bound, Entry, TranspositionTable},
+    };
+
+    #[test]
+    fn store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        let entry = Entry {
+            key: 0xdead_beef_1234_5678,
+            depth: 7,
+            score: -29_990,
+            bound: Bound::Upper,
+            best_move: Some(Move::with_promotion(
+                Position::from_str("b7").unwrap(),
+                Position::from_str("a8").unwrap(),
+                Type::Knight,
+            )),
+        };
+        tt.store(entry);
+
+        assert_eq!(tt.probe(entry.key), Some(entry));
+        assert_eq!(tt.probe(entry.key ^ 1), None);
+
+        tt.clear();
+        assert_eq!(tt.probe(entry.key), None);
+    }
+}
+
+#[cfg(test)]
+mod search_with {
+    use std::{
+        str::FromStr,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use engine::board::Board;
+
+    use crate::{
+        moves::{self, Move},
+        search::{hash, SearchLimits, Searcher},
+    };
+
+    use super::italian_game;
+
+    #[test]
+    fn reports_every_iteration() {
+        let stop = AtomicBool::new(false);
+        let mut depths = Vec::new();
+        let result = Searcher::default().search_with(
+            &italian_game(),
+            SearchLimits::depth(3),
+            &stop,
+            |result| depths.push(result.depth),
+        );
+
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert_eq!(result.depth, 3);
+        assert!(stop.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn stopped_before_starting() {
+        let stop = AtomicBool::new(true);
+        let result = Searcher::default().search_with(
+            &italian_game(),
+            SearchLimits::default(),
+            &stop,
+            |_| {},
+        );
+
+        assert_eq!(result.depth, 0);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn game_history_repetitions_are_draws() {
+        // Black is a queen down, going back to h8 repeats the position after Kh8
+        let mut board = Board::from_fen("6k1/8/8/8/8/8/8/1Q4K1 b - - 0 1").unwrap();
+        let mut history = Vec::new();
+        for movement in ["g8h8", "b1c1", "h8g8", "c1b1"] {
+            history.push(hash::hash(&board));
+            board = moves::apply(&board, Move::from_str(movement).unwrap()).unwrap();
+        }
+
+        let mut searcher = Searcher::default();
+        let without_history = searcher.search(&board, SearchLimits::depth(2));
+        searcher.clear();
+        searcher.set_history(history);
+        let with_history = searcher.search(&board, SearchLimits::depth(2));
+
+        assert!(without_history.score < -500);
+        assert_eq!(with_history.score, 0);
+        assert_eq!(
+            with_history.best_move,
+            Some(Move::from_str("g8h8").unwrap())
+        );
+    }
+}
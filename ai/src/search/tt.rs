@@ -0,0 +1,158 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use engine::piece::{position::Position, Type};
+
+use crate::moves::Move;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The score is at least this value (fail high)
+    Lower,
+    /// The score is at most this value (fail low)
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: i32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+/// A slot stores the entry packed in a single word, plus the key xored with that word.
+/// A slot torn by two threads writing at the same time fails the key check instead of
+/// returning a mixed entry, so no lock is needed
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Fixed size hash table of already searched positions, shared by every search thread.
+/// Always replaces on collision
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    /// Creates a table using roughly `size_mb` megabytes
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let slot_size = std::mem::size_of::<Slot>();
+        let len = (size_mb * 1024 * 1024 / slot_size).max(1);
+        // Power of two so the index is a simple mask
+        let len = 1 << (usize::BITS - 1 - len.leading_zeros());
+        TranspositionTable {
+            slots: (0..len).map(|_| Slot::default()).collect(),
+        }
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = &self.slots[self.index(key)];
+        let data = slot.data.load(Ordering::Relaxed);
+        if data == 0 || slot.key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        Some(unpack(key, data))
+    }
+
+    pub fn store(&self, entry: Entry) {
+        // Keep deeper results of the same position
+        if let Some(old) = self.probe(entry.key) {
+            if old.depth > entry.depth && entry.bound != Bound::Exact {
+                return;
+            }
+        }
+
+        let slot = &self.slots[self.index(entry.key)];
+        let data = pack(&entry);
+        slot.key.store(entry.key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.slots.len() - 1)
+    }
+}
+
+// Layout of the packed entry:
+// bits 0..16 the move (0 when there is none), 16..32 the score,
+// 32..40 the depth and 40..42 the bound. Bit 42 is always set so a used slot is never 0
+const SCORE_SHIFT: u64 = 16;
+const DEPTH_SHIFT: u64 = 32;
+const BOUND_SHIFT: u64 = 40;
+const USED: u64 = 1 << 42;
+
+fn pack(entry: &Entry) -> u64 {
+    let movement = entry.best_move.map_or(0, pack_move);
+    let score = entry.score as i16 as u16 as u64;
+    let depth = entry.depth.clamp(0, u8::MAX as i32) as u64;
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    movement | score << SCORE_SHIFT | depth << DEPTH_SHIFT | bound << BOUND_SHIFT | USED
+}
+
+fn unpack(key: u64, data: u64) -> Entry {
+    let movement = (data & 0xffff) as u16;
+    let bound = match (data >> BOUND_SHIFT) & 0b11 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    Entry {
+        key,
+        depth: ((data >> DEPTH_SHIFT) & 0xff) as i32,
+        score: ((data >> SCORE_SHIFT) & 0xffff) as u16 as i16 as i32,
+        bound,
+        best_move: unpack_move(movement),
+    }
+}
+
+/// from square (6 bits), to square (6 bits), promotion (3 bits) and a presence bit
+fn pack_move(movement: Move) -> u64 {
+    let from = (movement.from.y * 8 + movement.from.x) as u64;
+    let to = (movement.to.y * 8 + movement.to.x) as u64;
+    let promotion = match movement.promotion {
+        None => 0,
+        Some(Type::Queen) => 1,
+        Some(Type::Rook) => 2,
+        Some(Type::Bishop) => 3,
+        Some(Type::Knight) => 4,
+        Some(_) => 0,
+    };
+    1 << 15 | promotion << 12 | to << 6 | from
+}
+
+fn unpack_move(movement: u16) -> Option<Move> {
+    if movement & 1 << 15 == 0 {
+        return None;
+    }
+    let square = |index: u16| Position {
+        x: (index % 8) as i32,
+        y: (index / 8) as i32,
+    };
+    let promotion = match (movement >> 12) & 0b111 {
+        1 => Some(Type::Queen),
+        2 => Some(Type::Rook),
+        3 => Some(Type::Bishop),
+        4 => Some(Type::Knight),
+        _ => None,
+    };
+    Some(Move {
+        from: square(movement & 0x3f),
+        to: square((movement >> 6) & 0x3f),
+        promotion,
+    })
+}
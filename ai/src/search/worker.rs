@@ -0,0 +1,540 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use engine::{board::Board, piece::Type};
+
+use crate::{
+    eval,
+    moves::{self, Move},
+    tablebase::{self, Tablebase},
+};
+
+use super::{
+    hash, tablebase_score,
+    tt::{Bound, Entry, TranspositionTable},
+    SearchLimits, SearchOptions, SearchResult, DELTA_MARGIN, FUTILITY_MARGINS, INFINITY,
+    LMR_MIN_DEPTH, LMR_MIN_MOVES, MATE, MATE_BOUND, MAX_DEPTH, MAX_PLY, NULL_MOVE_MIN_DEPTH,
+    NULL_MOVE_REDUCTION, NULL_MOVE_VERIFICATION_DEPTH, REVERSE_FUTILITY_DEPTH,
+    REVERSE_FUTILITY_MARGIN, TIME_CHECK_INTERVAL,
+};
+
+/// The thread that owns the limits and whose result is reported
+const MAIN_WORKER: usize = 0;
+
+/// State of one search thread. Every thread searches the same root and only shares the
+/// transposition table and the stop flag with the others (Lazy SMP)
+pub(super) struct Worker<'a> {
+    id: usize,
+    options: SearchOptions,
+    tt: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    killers: Vec<[Option<Move>; 2]>,
+    history: Vec<[i32; 64]>,
+    pv: Vec<Vec<Move>>,
+    /// Hashes of the positions played in the game and from the root to the current node,
+    /// used to detect repetitions
+    path: Vec<u64>,
+    tablebase: Option<&'a dyn Tablebase>,
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+}
+
+impl<'a> Worker<'a> {
+    pub(super) fn new(
+        id: usize,
+        options: SearchOptions,
+        tt: &'a TranspositionTable,
+        stop: &'a AtomicBool,
+        limits: SearchLimits,
+        history: &[u64],
+        tablebase: Option<&'a dyn Tablebase>,
+    ) -> Worker<'a> {
+        let mut path = Vec::with_capacity(history.len() + MAX_PLY);
+        path.extend_from_slice(history);
+
+        Worker {
+            id,
+            options,
+            tt,
+            stop,
+            killers: vec![[None; 2]; MAX_PLY],
+            history: vec![[0; 64]; 64],
+            pv: vec![Vec::new(); MAX_PLY + 1],
+            path,
+            tablebase,
+            limits,
+            start: Instant::now(),
+            nodes: 0,
+            stopped: false,
+        }
+    }
+
+    pub(super) fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Iterative deepening from the root, helper threads only stop when the main one does.
+    /// `on_iteration` is called with the result of every completed depth
+    pub(super) fn search(
+        &mut self,
+        board: &Board,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        let root_moves = moves::legal_moves(board);
+        let mut result = SearchResult {
+            best_move: root_moves.first().copied(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+        };
+
+        if root_moves.is_empty() {
+            if moves::in_check(board) {
+                result.score = -MATE;
+            }
+            return result;
+        }
+
+        let max_depth = match self.id {
+            MAIN_WORKER => self.limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH),
+            _ => MAX_DEPTH,
+        };
+        // Half of the helpers start one ply deeper, so they don't walk the tree in lockstep
+        // with the main thread and fill the table with results it will need next
+        let first_depth = 1 + (self.id % 2) as u8;
+
+        for depth in first_depth..=max_depth {
+            let score = self.alpha_beta(board, depth as i32, 0, -INFINITY, INFINITY, true);
+
+            // An interrupted iteration is not trusted
+            if self.stopped {
+                break;
+            }
+
+            result.score = score;
+            result.depth = depth;
+            result.pv = self.pv[0].clone();
+            if let Some(best_move) = result.pv.first() {
+                result.best_move = Some(*best_move);
+            }
+            result.nodes = self.nodes;
+            on_iteration(&result);
+
+            if score.abs() >= MATE_BOUND {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn alpha_beta(
+        &mut self,
+        board: &Board,
+        mut depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        allow_null: bool,
+    ) -> i32 {
+        self.pv[ply].clear();
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let key = hash::hash(board);
+        if ply > 0 && self.is_repetition(key) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return eval::evaluate(board);
+        }
+        if let Some(score) = self.probe_tablebase(board, ply) {
+            return score;
+        }
+
+        let pv_node = beta - alpha > 1;
+        let in_check = moves::in_check(board);
+
+        if in_check && self.options.check_extensions && ply < MAX_PLY / 2 {
+            depth += 1;
+        }
+
+        if depth <= 0 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        let entry = self.tt.probe(key);
+        let tt_move = entry.and_then(|entry| entry.best_move);
+        if let Some(entry) = entry {
+            if !pv_node && entry.depth >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let static_eval = if in_check {
+            -INFINITY
+        } else {
+            eval::evaluate(board)
+        };
+
+        if !pv_node && !in_check {
+            // Reverse futility pruning: we are so far above beta that a shallow search won't change it
+            if self.options.futility_pruning
+                && depth <= REVERSE_FUTILITY_DEPTH
+                && beta.abs() < MATE_BOUND
+                && static_eval - REVERSE_FUTILITY_MARGIN * depth >= beta
+            {
+                return static_eval;
+            }
+
+            // Null move pruning, skipped without pieces (pawn endings are full of zugzwangs)
+            if self.options.null_move_pruning
+                && allow_null
+                && depth >= NULL_MOVE_MIN_DEPTH
+                && static_eval >= beta
+                && eval::non_pawn_material(board, board.get_turn()) > 0
+            {
+                let reduction = NULL_MOVE_REDUCTION + depth / 6;
+                let mut null_board = *board;
+                null_board.make_null_move();
+
+                self.path.push(key);
+                let score = -self.alpha_beta(
+                    &null_board,
+                    depth - 1 - reduction,
+                    ply + 1,
+                    -beta,
+                    -beta + 1,
+                    false,
+                );
+                self.path.pop();
+
+                if self.stopped {
+                    return 0;
+                }
+
+                if score >= beta {
+                    // Mates found after passing are not real
+                    let score = if score >= MATE_BOUND { beta } else { score };
+                    if depth < NULL_MOVE_VERIFICATION_DEPTH {
+                        return score;
+                    }
+
+                    let verified =
+                        self.alpha_beta(board, depth - reduction, ply, beta - 1, beta, false);
+                    if verified >= beta {
+                        return score;
+                    }
+                }
+            }
+        }
+
+        let move_list = moves::legal_moves(board);
+        if move_list.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        let move_list = self.order_moves(board, move_list, tt_move, ply);
+
+        let futility = self.options.futility_pruning
+            && !pv_node
+            && !in_check
+            && (depth as usize) < FUTILITY_MARGINS.len()
+            && alpha.abs() < MATE_BOUND
+            && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha;
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+
+        self.path.push(key);
+        for (i, movement) in move_list.into_iter().enumerate() {
+            let quiet = movement.promotion.is_none() && !moves::is_capture(board, movement);
+            let child = match moves::apply(board, movement) {
+                Some(child) => child,
+                None => continue,
+            };
+
+            let score = if child.get_winner() == Some(board.get_turn()) {
+                self.pv[ply + 1].clear();
+                MATE - (ply as i32 + 1)
+            } else {
+                let gives_check = moves::in_check(&child);
+
+                // Futility pruning: quiet moves can't bring a hopeless node back above alpha
+                if futility && quiet && !gives_check && i > 0 {
+                    continue;
+                }
+
+                if i == 0 {
+                    -self.alpha_beta(&child, depth - 1, ply + 1, -beta, -alpha, true)
+                } else {
+                    // Late move reductions: moves ordered last are unlikely to be good
+                    let reduction = if self.options.late_move_reductions
+                        && depth >= LMR_MIN_DEPTH
+                        && i >= LMR_MIN_MOVES
+                        && quiet
+                        && !in_check
+                        && !gives_check
+                        && !self.is_killer(movement, ply)
+                    {
+                        if depth >= 6 && i >= LMR_MIN_MOVES * 2 {
+                            2
+                        } else {
+                            1
+                        }
+                    } else {
+                        0
+                    };
+
+                    let mut score = -self.alpha_beta(
+                        &child,
+                        depth - 1 - reduction,
+                        ply + 1,
+                        -alpha - 1,
+                        -alpha,
+                        true,
+                    );
+                    if score > alpha && reduction > 0 {
+                        score =
+                            -self.alpha_beta(&child, depth - 1, ply + 1, -alpha - 1, -alpha, true);
+                    }
+                    if score > alpha && score < beta {
+                        score = -self.alpha_beta(&child, depth - 1, ply + 1, -beta, -alpha, true);
+                    }
+                    score
+                }
+            };
+
+            if self.stopped {
+                self.path.pop();
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(movement);
+
+                if score > alpha {
+                    alpha = score;
+                    let mut line = vec![movement];
+                    line.extend_from_slice(&self.pv[ply + 1]);
+                    self.pv[ply] = line;
+
+                    if score >= beta {
+                        if quiet {
+                            self.store_killer(movement, ply);
+                            let from = square_index(movement.from);
+                            let to = square_index(movement.to);
+                            self.history[from][to] += depth * depth;
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        self.path.pop();
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(Entry {
+            key,
+            depth,
+            score: score_to_tt(best_score, ply),
+            bound,
+            best_move,
+        });
+
+        best_score
+    }
+
+    /// Only captures and promotions are searched, so the static evaluation is not taken in the
+    /// middle of an exchange
+    fn quiescence(&mut self, board: &Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        let stand_pat = eval::evaluate(board);
+        if ply >= MAX_PLY - 1 || stand_pat >= beta {
+            return stand_pat;
+        }
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        let tactical_moves: Vec<Move> = moves::legal_moves(board)
+            .into_iter()
+            .filter(|movement| match movement.promotion {
+                Some(promotion) => promotion == Type::Queen,
+                None => moves::is_capture(board, *movement),
+            })
+            .collect();
+        let tactical_moves = self.order_moves(board, tactical_moves, None, ply);
+
+        for movement in tactical_moves {
+            // Delta pruning: even winning the piece for free would not raise alpha
+            let gain = moves::captured_type(board, movement).map_or(0, eval::piece_value);
+            if movement.promotion.is_none() && stand_pat + gain + DELTA_MARGIN < alpha {
+                continue;
+            }
+
+            let child = match moves::apply(board, movement) {
+                Some(child) => child,
+                None => continue,
+            };
+
+            let score = if child.get_winner() == Some(board.get_turn()) {
+                MATE - (ply as i32 + 1)
+            } else {
+                -self.quiescence(&child, ply + 1, -beta, -alpha)
+            };
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        alpha
+    }
+
+    /// Best first: hash move, captures (most valuable victim, least valuable attacker),
+    /// promotions, killer moves and then quiet moves by history
+    fn order_moves(
+        &self,
+        board: &Board,
+        move_list: Vec<Move>,
+        tt_move: Option<Move>,
+        ply: usize,
+    ) -> Vec<Move> {
+        let mut scored: Vec<(i32, Move)> = move_list
+            .into_iter()
+            .map(|movement| {
+                let score = if Some(movement) == tt_move {
+                    1_000_000
+                } else if let Some(victim) = moves::captured_type(board, movement) {
+                    let attacker = board
+                        .get_piece_at(&movement.from)
+                        .map_or(0, |piece| eval::piece_value(piece.get_type()));
+                    100_000 + 10 * eval::piece_value(victim) - attacker / 10
+                } else if let Some(promotion) = movement.promotion {
+                    90_000 + eval::piece_value(promotion)
+                } else if self.killers[ply][0] == Some(movement) {
+                    80_000
+                } else if self.killers[ply][1] == Some(movement) {
+                    79_000
+                } else {
+                    self.history[square_index(movement.from)][square_index(movement.to)].min(70_000)
+                };
+                (score, movement)
+            })
+            .collect();
+
+        // Stable sort, equal moves keep the generation order so searches are reproducible
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, movement)| movement).collect()
+    }
+
+    fn store_killer(&mut self, movement: Move, ply: usize) {
+        if self.killers[ply][0] != Some(movement) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(movement);
+        }
+    }
+
+    fn is_killer(&self, movement: Move, ply: usize) -> bool {
+        self.killers[ply].contains(&Some(movement))
+    }
+
+    /// Score of the position from the tables, the root is never probed here
+    fn probe_tablebase(&self, board: &Board, ply: usize) -> Option<i32> {
+        let tablebase = self.tablebase?;
+        if ply == 0 || tablebase::piece_count(board) > tablebase.max_pieces() {
+            return None;
+        }
+        let wdl = tablebase.probe_wdl(board)?;
+        Some(tablebase_score(wdl, ply))
+    }
+
+    /// A position already seen with the same side to move is scored as a draw
+    fn is_repetition(&self, key: u64) -> bool {
+        self.path
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .any(|old| *old == key)
+    }
+
+    /// Only the main thread looks at the limits, the others follow the shared stop flag
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        if self.id == MAIN_WORKER {
+            let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+            let out_of_time = self.limits.time.is_some_and(|time| {
+                self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) && self.start.elapsed() >= time
+            });
+            if out_of_nodes || out_of_time {
+                self.stop.store(true, Ordering::Relaxed);
+            }
+        }
+
+        self.stopped = self.stop.load(Ordering::Relaxed);
+        self.stopped
+    }
+}
+
+fn square_index(position: engine::piece::position::Position) -> usize {
+    (position.y * 8 + position.x) as usize
+}
+
+/// Mate scores are stored relative to the node, not to the root
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
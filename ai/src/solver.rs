@@ -0,0 +1,249 @@
+use std::{collections::HashMap, fmt::Display};
+
+use engine::board::Board;
+
+use crate::{
+    moves::{self, Move},
+    search::hash,
+};
+
+/// A move of the side that mates, with every defence against it. Mating moves have no defences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackNode {
+    pub movement: Move,
+    pub defences: Vec<DefenceNode>,
+}
+
+/// A defence, with every reply that still mates in time. More than one reply is a dual
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefenceNode {
+    pub movement: Move,
+    pub replies: Vec<AttackNode>,
+}
+
+impl AttackNode {
+    pub fn is_mate(&self) -> bool {
+        self.defences.is_empty()
+    }
+
+    /// Lines from this move to every defence answered by more than one reply
+    pub fn duals(&self) -> Vec<Vec<Move>> {
+        let mut duals = Vec::new();
+        for defence in &self.defences {
+            let line = vec![self.movement, defence.movement];
+            if defence.replies.len() > 1 {
+                duals.push(line.clone());
+            }
+            for reply in &defence.replies {
+                for dual in reply.duals() {
+                    duals.push(line.iter().copied().chain(dual).collect());
+                }
+            }
+        }
+        duals
+    }
+
+    fn write(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        number: u32,
+        indent: usize,
+    ) -> std::fmt::Result {
+        let mate = if self.is_mate() { "#" } else { "" };
+        writeln!(f, "{:indent$}{}. {}{}", "", number, self.movement, mate)?;
+        for defence in &self.defences {
+            let dual = if defence.replies.len() > 1 {
+                " (dual)"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "{:indent$}  {}... {}{}",
+                "", number, defence.movement, dual
+            )?;
+            for reply in &defence.replies {
+                reply.write(f, number + 1, indent + 4)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every defence on its own line, then the replies indented below it
+impl Display for AttackNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, 1, 0)
+    }
+}
+
+/// The answer to "mate in `moves`" for the side to move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MateSolution {
+    pub moves: u32,
+    /// Every first move that mates in time with its solution tree. None means the mate is
+    /// refuted, more than one is a cook
+    pub keys: Vec<AttackNode>,
+    /// The first moves that fail, with a defence that avoids the mate. Moves that stalemate or
+    /// leave no defence that escapes have no refutation
+    pub refutations: Vec<(Move, Option<Move>)>,
+    /// Fewest moves the mate can be forced in, when it is `moves` or less
+    pub shortest: Option<u32>,
+}
+
+impl MateSolution {
+    pub fn is_proven(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// One key, no mate in fewer moves and no duals
+    pub fn is_sound(&self) -> bool {
+        self.keys.len() == 1 && self.shortest == Some(self.moves) && self.keys[0].duals().is_empty()
+    }
+}
+
+/// Proves or refutes mates with an exhaustive AND/OR search: the attacker needs one move that
+/// mates in time, the defender is lost when every reply is. Checks are tried first and every
+/// proven position is cached, so composed problems of a few moves are solved quickly
+#[derive(Debug, Default)]
+pub struct MateSolver {
+    /// Whether the side to move in a position mates within the given moves
+    proofs: HashMap<(u64, u32), bool>,
+    nodes: u64,
+}
+
+impl MateSolver {
+    pub fn new() -> MateSolver {
+        MateSolver::default()
+    }
+
+    /// Positions visited since the solver was created
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn solve(&mut self, board: &Board, moves: u32) -> MateSolution {
+        let mut keys = Vec::new();
+        let mut refutations = Vec::new();
+        for movement in ordered_moves(board).into_iter().filter(|_| moves > 0) {
+            let Some(child) = moves::apply(board, movement) else {
+                continue;
+            };
+            match self.attack_tree(board, movement, &child, moves) {
+                Some(tree) => keys.push(tree),
+                None => refutations.push((movement, self.refutation(&child, moves))),
+            }
+        }
+
+        let shortest = (1..=moves).find(|n| self.mates_in(board, *n));
+        MateSolution {
+            moves,
+            keys,
+            refutations,
+            shortest,
+        }
+    }
+
+    /// True when the side to move mates in `moves` moves or less
+    pub fn mates_in(&mut self, board: &Board, moves: u32) -> bool {
+        if moves == 0 {
+            return false;
+        }
+        let key = (hash::hash(board), moves);
+        if let Some(proof) = self.proofs.get(&key) {
+            return *proof;
+        }
+
+        self.nodes += 1;
+        let attacker = board.get_turn();
+        let proof = ordered_moves(board).into_iter().any(|movement| {
+            let Some(child) = moves::apply(board, movement) else {
+                return false;
+            };
+            child.get_winner() == Some(attacker) || self.is_lost(&child, moves - 1)
+        });
+        self.proofs.insert(key, proof);
+        proof
+    }
+
+    /// True when every move of the side to move lets the opponent mate in `moves` moves or less
+    fn is_lost(&mut self, board: &Board, moves: u32) -> bool {
+        if moves == 0 {
+            return false;
+        }
+        let defences = moves::legal_moves(board);
+        // Stalemate
+        if defences.is_empty() {
+            return false;
+        }
+        defences
+            .into_iter()
+            .all(|movement| match moves::apply(board, movement) {
+                Some(child) => self.mates_in(&child, moves),
+                None => true,
+            })
+    }
+
+    /// The tree of a move that mates in `moves`, None when it doesn't
+    fn attack_tree(
+        &mut self,
+        board: &Board,
+        movement: Move,
+        child: &Board,
+        moves: u32,
+    ) -> Option<AttackNode> {
+        if child.get_winner() == Some(board.get_turn()) {
+            return Some(AttackNode {
+                movement,
+                defences: Vec::new(),
+            });
+        }
+        if !self.is_lost(child, moves - 1) {
+            return None;
+        }
+
+        let mut defences = Vec::new();
+        for defence in moves::legal_moves(child) {
+            let Some(position) = moves::apply(child, defence) else {
+                continue;
+            };
+            let replies = ordered_moves(&position)
+                .into_iter()
+                .filter_map(|reply| {
+                    let after = moves::apply(&position, reply)?;
+                    self.attack_tree(&position, reply, &after, moves - 1)
+                })
+                .collect();
+            defences.push(DefenceNode {
+                movement: defence,
+                replies,
+            });
+        }
+        Some(AttackNode { movement, defences })
+    }
+
+    /// A defence after which the mate can't be forced in time
+    fn refutation(&mut self, child: &Board, moves: u32) -> Option<Move> {
+        moves::legal_moves(child).into_iter().find(|defence| {
+            moves::apply(child, *defence).is_some_and(|after| !self.mates_in(&after, moves - 1))
+        })
+    }
+}
+
+/// Checks first, then captures, then the other moves
+fn ordered_moves(board: &Board) -> Vec<Move> {
+    let mut ordered: Vec<(u8, Move)> = moves::legal_moves(board)
+        .into_iter()
+        .map(|movement| {
+            let check = moves::apply(board, movement).is_some_and(|child| moves::in_check(&child));
+            let rank = match (check, moves::is_capture(board, movement)) {
+                (true, _) => 0,
+                (false, true) => 1,
+                (false, false) => 2,
+            };
+            (rank, movement)
+        })
+        .collect();
+    ordered.sort_by_key(|(rank, _)| *rank);
+    ordered.into_iter().map(|(_, movement)| movement).collect()
+}
@@ -0,0 +1,236 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use engine::board::Board;
+use rand::Rng;
+
+use crate::{
+    eval,
+    moves::{self, Move},
+    result::AiError,
+    search::{hash, SearchLimits, Searcher, MATE},
+};
+
+pub const MIN_LEVEL: u8 = 1;
+pub const MAX_LEVEL: u8 = 10;
+
+/// How a level plays, weaker levels see less, misjudge more and blunder on purpose
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelSettings {
+    /// Plies searched, None for no depth cap
+    pub depth: Option<u8>,
+    /// Nodes searched for the whole move
+    pub nodes: u64,
+    /// Every move score is moved by a random amount in `-noise..=noise` centipawns
+    pub noise: i32,
+    /// Chance of playing a random move instead of the best one
+    pub blunder_probability: f64,
+    /// Approximate playing strength
+    pub elo: u32,
+}
+
+#[rustfmt::skip]
+const LEVELS: [LevelSettings; MAX_LEVEL as usize] = [
+    LevelSettings { depth: Some(1), nodes: 1_000,     noise: 300, blunder_probability: 0.30, elo: 400 },
+    LevelSettings { depth: Some(1), nodes: 2_000,     noise: 200, blunder_probability: 0.20, elo: 600 },
+    LevelSettings { depth: Some(2), nodes: 5_000,     noise: 150, blunder_probability: 0.15, elo: 800 },
+    LevelSettings { depth: Some(2), nodes: 10_000,    noise: 100, blunder_probability: 0.10, elo: 1000 },
+    LevelSettings { depth: Some(3), nodes: 20_000,    noise: 75,  blunder_probability: 0.07, elo: 1200 },
+    LevelSettings { depth: Some(4), nodes: 50_000,    noise: 50,  blunder_probability: 0.05, elo: 1400 },
+    LevelSettings { depth: Some(5), nodes: 100_000,   noise: 30,  blunder_probability: 0.03, elo: 1600 },
+    LevelSettings { depth: Some(6), nodes: 200_000,   noise: 15,  blunder_probability: 0.01, elo: 1800 },
+    LevelSettings { depth: Some(8), nodes: 500_000,   noise: 5,   blunder_probability: 0.0,  elo: 2000 },
+    LevelSettings { depth: None,    nodes: 1_000_000, noise: 0,   blunder_probability: 0.0,  elo: 2200 },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelError {
+    OutOfRange(u8),
+}
+
+impl Display for LevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelError::OutOfRange(level) => write!(
+                f,
+                "Invalid level: {}, levels go from {} to {}",
+                level, MIN_LEVEL, MAX_LEVEL
+            ),
+        }
+    }
+}
+
+impl Error for LevelError {}
+
+/// Difficulty level, from 1 (barely better than random) to 10 (full strength)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Level(u8);
+
+impl Level {
+    pub fn new(level: u8) -> Result<Level, LevelError> {
+        if (MIN_LEVEL..=MAX_LEVEL).contains(&level) {
+            Ok(Level(level))
+        } else {
+            Err(LevelError::OutOfRange(level))
+        }
+    }
+
+    /// The level whose approximate Elo is the closest to `elo`
+    pub fn from_elo(elo: u32) -> Level {
+        let index = LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, settings)| settings.elo.abs_diff(elo))
+            .map_or(0, |(index, _)| index);
+        Level(index as u8 + MIN_LEVEL)
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+
+    pub fn elo(&self) -> u32 {
+        self.settings().elo
+    }
+
+    pub fn settings(&self) -> LevelSettings {
+        LEVELS[(self.0 - MIN_LEVEL) as usize]
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level(MAX_LEVEL)
+    }
+}
+
+/// A search that plays at a given level. Without a time budget, moves only depend on the
+/// position and on the random number generator, so a seeded generator replays the same game
+pub struct LimitedSearcher {
+    level: Level,
+    searcher: Searcher,
+    history: Vec<u64>,
+    /// Most time a move can take, on top of the level's limits
+    time: Option<Duration>,
+}
+
+impl LimitedSearcher {
+    pub fn new(level: Level) -> LimitedSearcher {
+        LimitedSearcher {
+            level,
+            searcher: Searcher::default(),
+            history: Vec::new(),
+            time: None,
+        }
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Hashes of the positions played before the searched one, see [`Searcher::set_history`]
+    pub fn set_history(&mut self, history: Vec<u64>) {
+        self.history = history;
+    }
+
+    /// Time the next moves can take at most, for a player on the clock (see
+    /// [`SearchLimits::from_clock`]). The search stops at the time or at the level's limits,
+    /// whichever comes first
+    pub fn set_time(&mut self, time: Option<Duration>) {
+        self.time = time;
+    }
+
+    pub fn choose_move<R: Rng + ?Sized>(
+        &mut self,
+        board: &Board,
+        rng: &mut R,
+    ) -> Result<Move, AiError> {
+        let settings = self.level.settings();
+        let root_moves = moves::legal_moves(board);
+        if root_moves.is_empty() {
+            return Err(AiError::NoLegalMoves);
+        }
+
+        if settings.noise == 0 && settings.blunder_probability == 0.0 {
+            self.searcher.set_history(self.history.clone());
+            let limits = SearchLimits {
+                depth: settings.depth,
+                time: self.time,
+                nodes: Some(settings.nodes),
+            };
+            let result = self.searcher.search(board, limits);
+            return result.best_move.ok_or(AiError::NoLegalMoves);
+        }
+
+        let scores = self.score_root_moves(board, &root_moves, settings);
+        let best = scores
+            .iter()
+            .map(|(movement, score)| {
+                (
+                    *movement,
+                    score + rng.gen_range(-settings.noise..=settings.noise),
+                )
+            })
+            .max_by_key(|(_, score)| *score)
+            .map(|(movement, _)| movement);
+
+        if root_moves.len() > 1 && rng.gen_bool(settings.blunder_probability) {
+            let others: Vec<Move> = root_moves
+                .iter()
+                .copied()
+                .filter(|movement| Some(*movement) != best)
+                .collect();
+            return Ok(others[rng.gen_range(0..others.len())]);
+        }
+
+        best.ok_or(AiError::NoLegalMoves)
+    }
+
+    /// Scores every root move on its own, splitting the node budget and the time between them.
+    /// Out of time, the moves left are only evaluated
+    fn score_root_moves(
+        &mut self,
+        board: &Board,
+        root_moves: &[Move],
+        settings: LevelSettings,
+    ) -> Vec<(Move, i32)> {
+        let depth = settings.depth.unwrap_or(u8::MAX);
+        let nodes = (settings.nodes / root_moves.len() as u64).max(1);
+        let deadline = self.time.map(|time| Instant::now() + time);
+
+        // Every root move is searched as a new root, the current position joins the history
+        let mut history = self.history.clone();
+        history.push(hash::hash(board));
+        self.searcher.set_history(history);
+
+        root_moves
+            .iter()
+            .enumerate()
+            .filter_map(|(index, movement)| {
+                let child = moves::apply(board, *movement)?;
+                // The time left is shared by the moves still to score
+                let time = deadline.map(|deadline| {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    left / (root_moves.len() - index) as u32
+                });
+                let score = if child.get_winner() == Some(board.get_turn()) {
+                    MATE - 1
+                } else if depth <= 1 || time.is_some_and(|time| time.is_zero()) {
+                    -eval::evaluate(&child)
+                } else {
+                    let limits = SearchLimits {
+                        depth: Some(depth - 1),
+                        time,
+                        nodes: Some(nodes),
+                    };
+                    // A stalemated child has no move and a score of 0
+                    -self.searcher.search(&child, limits).score
+                };
+                Some((*movement, score))
+            })
+            .collect()
+    }
+}
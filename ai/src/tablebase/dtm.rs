@@ -0,0 +1,364 @@
+use std::{collections::BTreeMap, error::Error, fmt::Display, fs, path::Path};
+
+use engine::{
+    board::Board,
+    piece::{Color, Type},
+};
+
+use crate::search::hash;
+
+use super::{generator, huffman, material::Material, piece_count, Tablebase, Wdl};
+
+/// File extension of the tables
+pub const EXTENSION: &str = "dtm";
+
+/// Most pieces, kings included, of the endgames that can be generated
+pub const MAX_PIECES: usize = 4;
+
+const MAGIC: &[u8; 4] = b"RDTM";
+const VERSION: u8 = 1;
+
+/// Squares the white king is moved to in tables without pawns: a1, b1, c1, d1, b2, c2, d2, c3,
+/// d3 and d4. Every other square is reached by mirroring the board and flipping it around the
+/// a1-h8 diagonal
+const TRIANGLE: [u8; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+/// With pawns the board can only be mirrored, the white king stays on the files a to d
+const PAWN_KING_SQUARES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TablebaseError {
+    Io(String),
+    /// Data that is not a table, or a damaged one
+    InvalidTable,
+    /// A table file that could not be read, with its path
+    InvalidFile(String),
+}
+
+impl Display for TablebaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TablebaseError::Io(error) => write!(f, "Could not access the tables: {}", error),
+            TablebaseError::InvalidTable => write!(f, "Invalid table data"),
+            TablebaseError::InvalidFile(path) => write!(f, "Invalid table file: {}", path),
+        }
+    }
+}
+
+impl Error for TablebaseError {}
+
+/// Distance to mate of every position of an endgame, for both sides to move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    material: Material,
+    /// By side to move, 0 for draws and 1 + the plies to mate otherwise
+    values: [Vec<u8>; 2],
+}
+
+impl Table {
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Plies of the longest mate of the endgame, for the side to move that wins it
+    pub fn longest_mate(&self, turn: Color) -> Option<u32> {
+        self.values[side(turn)]
+            .iter()
+            .filter(|value| **value % 2 == 0 && **value > 0)
+            .max()
+            .map(|value| *value as u32 - 1)
+    }
+
+    /// `RDTM`, a version byte, the material and the values of both sides to move, Huffman
+    /// coded
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        let name = self.material.to_string();
+        bytes.push(name.len() as u8);
+        bytes.extend(name.as_bytes());
+        for values in &self.values {
+            huffman::encode(values, &mut bytes);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Table, TablebaseError> {
+        let invalid = TablebaseError::InvalidTable;
+        if bytes.get(..4) != Some(MAGIC) || bytes.get(4) != Some(&VERSION) {
+            return Err(invalid);
+        }
+        let name_length = *bytes.get(5).ok_or(invalid.clone())? as usize;
+        let material: Material = bytes
+            .get(6..6 + name_length)
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .and_then(|name| name.parse().ok())
+            .filter(|material: &Material| {
+                material.is_canonical() && material.can_mate() && material.pieces() <= MAX_PIECES
+            })
+            .ok_or(invalid.clone())?;
+
+        let size = table_size(&material);
+        let mut read = 6 + name_length;
+        let mut decode = || {
+            let (values, length) = huffman::decode(&bytes[read..], size).ok_or(invalid.clone())?;
+            read += length;
+            Ok(values)
+        };
+        let values = [decode()?, decode()?];
+        if read != bytes.len() {
+            return Err(invalid);
+        }
+
+        Ok(Table { material, values })
+    }
+}
+
+/// Distance to mate tables of the endgames with few pieces, built by retrograde analysis.
+/// Positions with castling rights or an en passant capture are not in the tables
+#[derive(Debug, Clone, Default)]
+pub struct DtmTablebase {
+    tables: BTreeMap<Material, Table>,
+}
+
+impl DtmTablebase {
+    pub fn new() -> DtmTablebase {
+        DtmTablebase::default()
+    }
+
+    /// Generates every endgame with up to `max_pieces` pieces, kings included, and at most
+    /// [`MAX_PIECES`]
+    pub fn generate(max_pieces: usize) -> DtmTablebase {
+        let mut tablebase = DtmTablebase::new();
+        for material in Material::all(max_pieces.min(MAX_PIECES)) {
+            tablebase.add(&material);
+        }
+        tablebase
+    }
+
+    /// Generates the table of an endgame, and the ones it can turn into first
+    pub fn add(&mut self, material: &Material) {
+        let (material, _) = material.canonical();
+        if self.tables.contains_key(&material) || !material.can_mate() {
+            return;
+        }
+        for successor in material.successors() {
+            self.add(&successor);
+        }
+
+        let values = generator::generate(&material, |pieces, turn| {
+            self.lookup(pieces, turn).unwrap_or(0)
+        });
+        // Only the positions with the white king in its part of the board are kept
+        let others_size = 1 << (6 * (material.pieces() - 1));
+        let values = values.map(|values| {
+            (0..table_size(&material))
+                .map(|index| {
+                    let king = king_square(&material, index / others_size);
+                    let others = index % others_size;
+                    values[king as usize | others << 6]
+                })
+                .collect()
+        });
+        self.tables
+            .insert(material.clone(), Table { material, values });
+    }
+
+    /// Reads every table of a directory
+    pub fn open(directory: impl AsRef<Path>) -> Result<DtmTablebase, TablebaseError> {
+        let io_error = |error: std::io::Error| TablebaseError::Io(error.to_string());
+        let mut tablebase = DtmTablebase::new();
+        for entry in fs::read_dir(directory).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == EXTENSION)
+            {
+                let table = Table::from_bytes(&fs::read(&path).map_err(io_error)?)
+                    .map_err(|_| TablebaseError::InvalidFile(path.display().to_string()))?;
+                tablebase.tables.insert(table.material.clone(), table);
+            }
+        }
+        Ok(tablebase)
+    }
+
+    /// Writes every table to a directory, one `KQvKR.dtm` file each
+    pub fn save(&self, directory: impl AsRef<Path>) -> Result<(), TablebaseError> {
+        let io_error = |error: std::io::Error| TablebaseError::Io(error.to_string());
+        fs::create_dir_all(&directory).map_err(io_error)?;
+        for table in self.tables.values() {
+            let path = directory
+                .as_ref()
+                .join(format!("{}.{}", table.material, EXTENSION));
+            fs::write(path, table.to_bytes()).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    pub fn table(&self, material: &Material) -> Option<&Table> {
+        self.tables.get(&material.canonical().0)
+    }
+
+    /// Plies to mate with best play, positive when the side to move wins, negative when it
+    /// loses and 0 for draws and mated positions
+    pub fn probe_dtm(&self, board: &Board) -> Option<i32> {
+        if board.get_promotion().is_some()
+            || hash::castling_rights(board).contains(&true)
+            || hash::en_passant_file(board).is_some()
+        {
+            return None;
+        }
+
+        let mut pieces = Vec::new();
+        for (y, row) in board.get_pieces().iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                if let Some(piece) = piece {
+                    pieces.push((piece.get_type(), piece.get_color(), (y * 8 + x) as u8));
+                }
+            }
+        }
+        let value = self.lookup(&pieces, board.get_turn())? as i32;
+        Some(match value {
+            0 => 0,
+            value if value % 2 == 0 => value - 1,
+            value => -(value - 1),
+        })
+    }
+
+    /// Value of a position, see [`generator::generate`]. Endgames where no mate is possible
+    /// are draws without a table
+    fn lookup(&self, pieces: &[(Type, Color, u8)], turn: Color) -> Option<u8> {
+        let side_pieces = |color: Color| {
+            pieces
+                .iter()
+                .filter(|(piece_type, piece_color, _)| {
+                    *piece_color == color && *piece_type != Type::King
+                })
+                .map(|(piece_type, _, _)| *piece_type)
+                .collect()
+        };
+        let material = Material::new(side_pieces(Color::White), side_pieces(Color::Black));
+        if !material.can_mate() {
+            return Some(0);
+        }
+        let (material, flipped) = material.canonical();
+        let table = self.tables.get(&material)?;
+
+        // Colors swapped and the board mirrored so that White is the stronger side
+        let (pieces, turn): (Vec<(Type, Color, u8)>, Color) = match flipped {
+            true => (
+                pieces
+                    .iter()
+                    .map(|(piece_type, color, square)| (*piece_type, color.opponent(), square ^ 56))
+                    .collect(),
+                turn.opponent(),
+            ),
+            false => (pieces.to_vec(), turn),
+        };
+
+        let mut used = vec![false; pieces.len()];
+        let mut squares = Vec::with_capacity(pieces.len());
+        for (piece_type, color) in material.layout() {
+            let found = pieces
+                .iter()
+                .enumerate()
+                .position(|(i, piece)| !used[i] && piece.0 == piece_type && piece.1 == color)?;
+            used[found] = true;
+            squares.push(pieces[found].2);
+        }
+
+        let transform = symmetry(&material, squares[0]);
+        let squares: Vec<u8> = squares.into_iter().map(transform).collect();
+        let king = match material.pawns() {
+            0 => TRIANGLE.iter().position(|square| *square == squares[0])?,
+            _ => (squares[0] / 8 * 4 + squares[0] % 8) as usize,
+        };
+        let others = generator::encode(Color::White, &squares[1..]);
+        table.values[side(turn)]
+            .get(king << (6 * (material.pieces() - 1)) | others)
+            .copied()
+    }
+}
+
+impl Tablebase for DtmTablebase {
+    fn max_pieces(&self) -> usize {
+        self.tables
+            .keys()
+            .map(|material| material.pieces())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if piece_count(board) > self.max_pieces() {
+            return None;
+        }
+        let dtm = self.probe_dtm(board)?;
+        Some(match dtm {
+            0 if board.get_winner().is_some() => Wdl::Loss,
+            0 => Wdl::Draw,
+            dtm if dtm > 0 => Wdl::Win,
+            _ => Wdl::Loss,
+        })
+    }
+
+    fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if piece_count(board) > self.max_pieces() {
+            return None;
+        }
+        self.probe_dtm(board)
+    }
+}
+
+fn side(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn king_squares(material: &Material) -> usize {
+    match material.pawns() {
+        0 => TRIANGLE.len(),
+        _ => PAWN_KING_SQUARES,
+    }
+}
+
+fn king_square(material: &Material, index: usize) -> u8 {
+    match material.pawns() {
+        0 => TRIANGLE[index],
+        _ => (index / 4 * 8 + index % 4) as u8,
+    }
+}
+
+/// Entries of one side to move
+fn table_size(material: &Material) -> usize {
+    king_squares(material) << (6 * (material.pieces() - 1))
+}
+
+/// The symmetry that moves the white king to its part of the board
+fn symmetry(material: &Material, king: u8) -> impl Fn(u8) -> u8 {
+    let (x, y) = (king % 8, king / 8);
+    let mirror_files = x > 3;
+    let pawnless = material.pawns() == 0;
+    let mirror_ranks = pawnless && y > 3;
+    let x = if mirror_files { 7 - x } else { x };
+    let y = if mirror_ranks { 7 - y } else { y };
+    let transpose = pawnless && y > x;
+    move |square| {
+        let mut square = square;
+        if mirror_files {
+            square ^= 7;
+        }
+        if mirror_ranks {
+            square ^= 56;
+        }
+        if transpose {
+            square = (square % 8) * 8 + square / 8;
+        }
+        square
+    }
+}
@@ -0,0 +1,638 @@
+//! Retrograde analysis of a single endgame. Positions are indexed by the squares of their pieces
+//! (`y * 8 + x`, 6 bits each, in the order of [`Material::layout`]), every arrangement of the
+//! pieces has a slot even when it can't happen in a game.
+//!
+//! The tables are built with their own small move generator rather than the engine's, so
+//! comparing both (see the tests) checks the engine on every position of an endgame
+
+use std::mem;
+
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+};
+
+use crate::moves::{self, Move};
+
+use super::{dtm::MAX_PIECES, material::Material};
+
+/// Square of a captured piece
+pub(crate) const NONE: u8 = 64;
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+const KNIGHT_JUMPS: [(i8, i8); 8] = [
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+    (-2, 1),
+    (-1, 2),
+    (1, 2),
+    (2, 1),
+];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+// Generation values, resolved positions are `RESOLVED + plies to mate`
+const UNKNOWN: u8 = 0;
+const DRAW: u8 = 1;
+const RESOLVED: u8 = 2;
+
+// Moves left to examine before a position is lost, and two markers
+const ILLEGAL: u8 = u8::MAX;
+const DONE: u8 = u8::MAX - 1;
+
+/// Marks positions with a capture or a promotion that doesn't lose
+const CANNOT_LOSE: u8 = u8::MAX;
+
+/// A legal move, see [`legal_steps`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Step {
+    /// Index of the moved piece in the layout
+    pub piece: usize,
+    pub to: u8,
+    pub captured: Option<usize>,
+    pub promotion: Option<Type>,
+}
+
+impl Step {
+    /// Captures and promotions leave the endgame
+    fn is_exit(&self) -> bool {
+        self.captured.is_some() || self.promotion.is_some()
+    }
+}
+
+/// Builds the table of both sides to move. Values are 0 for draws and positions that can't
+/// happen, otherwise 1 + the plies to mate with best play: odd distances are won by the side to
+/// move, even ones are lost.
+///
+/// `exit` gives the value of the positions reached by captures and promotions, which are in
+/// other tables. En passant captures are not played, tables are only probed without them
+pub(crate) fn generate(
+    material: &Material,
+    exit: impl Fn(&[(Type, Color, u8)], Color) -> u8,
+) -> [Vec<u8>; 2] {
+    let layout = material.layout();
+    let size = 1usize << (6 * layout.len());
+    let mut values = vec![UNKNOWN; 2 * size];
+    let mut counters = vec![ILLEGAL; 2 * size];
+    // Longest loss through a capture or a promotion, or CANNOT_LOSE
+    let mut exits = vec![0u8; 2 * size];
+    // Positions to resolve, by plies to mate
+    let mut levels: Vec<Vec<u32>> = Vec::new();
+    let mut steps = Vec::new();
+    let mut parents = Vec::new();
+
+    for id in 0..2 * size {
+        let (turn, squares) = decode(id, layout.len());
+        let squares = &squares[..layout.len()];
+        if !is_legal(&layout, squares, turn) {
+            continue;
+        }
+
+        add_legal_steps(&layout, squares, turn, &mut steps);
+        if steps.is_empty() {
+            match in_check(&layout, squares, turn) {
+                true => push(&mut levels, 0, id),
+                false => {
+                    values[id] = DRAW;
+                    counters[id] = DONE;
+                }
+            }
+            continue;
+        }
+
+        let mut internal = 0;
+        let mut best_win: Option<u8> = None;
+        let mut longest_loss = 0;
+        let mut cannot_lose = false;
+        for step in steps.iter().copied() {
+            if !step.is_exit() {
+                internal += 1;
+                continue;
+            }
+            match exit(&after(&layout, squares, step), turn.opponent()) {
+                0 => cannot_lose = true,
+                value if value % 2 == 1 => {
+                    // The opponent is mated in `value - 1` plies
+                    best_win = Some(best_win.map_or(value, |best| best.min(value)));
+                }
+                value => longest_loss = longest_loss.max(value),
+            }
+        }
+
+        counters[id] = internal;
+        if let Some(plies) = best_win {
+            values[id] = RESOLVED + plies;
+            push(&mut levels, plies, id);
+            cannot_lose = true;
+        }
+        exits[id] = if cannot_lose {
+            CANNOT_LOSE
+        } else {
+            longest_loss
+        };
+        if internal == 0 && !cannot_lose {
+            push(&mut levels, longest_loss, id);
+        }
+    }
+
+    let mut plies = 0;
+    while plies < levels.len() {
+        for id in mem::take(&mut levels[plies]) {
+            let id = id as usize;
+            let value = RESOLVED + plies as u8;
+            // Entries left behind by a faster mate
+            if counters[id] == DONE || values[id] != UNKNOWN && values[id] != value {
+                continue;
+            }
+            values[id] = value;
+            counters[id] = DONE;
+
+            let (turn, squares) = decode(id, layout.len());
+            let squares = &squares[..layout.len()];
+            let won = plies % 2 == 0;
+            add_parents(&layout, squares, turn, &mut parents);
+            for parent in parents.iter().copied() {
+                if counters[parent] == DONE {
+                    continue;
+                }
+                let parent_plies = plies as u8 + 1;
+                if won {
+                    // Mates the opponent, unless a faster mate is already known
+                    if values[parent] == UNKNOWN || values[parent] > RESOLVED + parent_plies {
+                        values[parent] = RESOLVED + parent_plies;
+                        push(&mut levels, parent_plies, parent);
+                    }
+                } else if values[parent] == UNKNOWN {
+                    counters[parent] -= 1;
+                    if counters[parent] == 0 && exits[parent] != CANNOT_LOSE {
+                        push(&mut levels, parent_plies.max(exits[parent]), parent);
+                    }
+                }
+            }
+        }
+        plies += 1;
+    }
+
+    let final_value = |value: u8| match value >= RESOLVED {
+        true => value - RESOLVED + 1,
+        false => 0,
+    };
+    let (white, black) = values.split_at(size);
+    [
+        white.iter().map(|value| final_value(*value)).collect(),
+        black.iter().map(|value| final_value(*value)).collect(),
+    ]
+}
+
+/// Compares the legal moves of every `stride`th position of an endgame, for both sides to move,
+/// with the ones of the engine. Returns the FEN of the positions where they differ
+pub fn engine_mismatches(material: &Material, stride: usize) -> Vec<String> {
+    let layout = material.layout();
+    let size = 1usize << (6 * layout.len());
+    let mut mismatches = Vec::new();
+    for id in (0..2 * size).step_by(stride.max(1)) {
+        let (turn, squares) = decode(id, layout.len());
+        let squares = &squares[..layout.len()];
+        if !is_legal(&layout, squares, turn) {
+            continue;
+        }
+
+        let fen = fen(&layout, squares, turn);
+        let mut expected: Vec<String> = legal_steps(&layout, squares, turn)
+            .into_iter()
+            .map(|step| {
+                let from = position(squares[step.piece]);
+                let to = position(step.to);
+                match step.promotion {
+                    Some(promotion) => Move::with_promotion(from, to, promotion),
+                    None => Move::new(from, to),
+                }
+                .to_string()
+            })
+            .collect();
+        let mut actual: Vec<String> = match Board::from_fen(&fen) {
+            Ok(board) => moves::legal_moves(&board)
+                .into_iter()
+                .map(|movement| movement.to_string())
+                .collect(),
+            Err(_) => vec!["invalid FEN".to_string()],
+        };
+        expected.sort();
+        actual.sort();
+        if expected != actual {
+            mismatches.push(fen);
+        }
+    }
+    mismatches
+}
+
+fn position(square: u8) -> Position {
+    Position {
+        x: (square % 8) as i32,
+        y: (square / 8) as i32,
+    }
+}
+
+/// FEN of a position, without castling rights or en passant square
+pub(crate) fn fen(layout: &[(Type, Color)], squares: &[u8], turn: Color) -> String {
+    let mut rows = Vec::new();
+    for y in (0..8).rev() {
+        let mut row = String::new();
+        let mut empty = 0;
+        for x in 0..8 {
+            match occupant(squares, y * 8 + x) {
+                Some(piece) => {
+                    if empty > 0 {
+                        row.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    let (piece_type, color) = layout[piece];
+                    let letter = match piece_type {
+                        Type::Pawn => 'p',
+                        Type::Knight => 'n',
+                        Type::Bishop => 'b',
+                        Type::Rook => 'r',
+                        Type::Queen => 'q',
+                        Type::King => 'k',
+                    };
+                    row.push(match color {
+                        Color::White => letter.to_ascii_uppercase(),
+                        Color::Black => letter,
+                    });
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            row.push_str(&empty.to_string());
+        }
+        rows.push(row);
+    }
+    let turn = match turn {
+        Color::White => "w",
+        Color::Black => "b",
+    };
+    format!("{} {} - - 0 1", rows.join("/"), turn)
+}
+
+fn push(levels: &mut Vec<Vec<u32>>, plies: u8, id: usize) {
+    let plies = plies as usize;
+    if levels.len() <= plies {
+        levels.resize(plies + 1, Vec::new());
+    }
+    levels[plies].push(id as u32);
+}
+
+/// Side to move and squares of the position `id`, unused squares are [`NONE`]
+fn decode(id: usize, pieces: usize) -> (Color, [u8; MAX_PIECES]) {
+    let turn = match id >> (6 * pieces) {
+        0 => Color::White,
+        _ => Color::Black,
+    };
+    let mut squares = [NONE; MAX_PIECES];
+    for (i, square) in squares.iter_mut().enumerate().take(pieces) {
+        *square = (id >> (6 * i)) as u8 & 63;
+    }
+    (turn, squares)
+}
+
+/// A copy of the squares that can be changed
+fn copy(squares: &[u8]) -> [u8; MAX_PIECES] {
+    let mut copy = [NONE; MAX_PIECES];
+    copy[..squares.len()].copy_from_slice(squares);
+    copy
+}
+
+/// Index of a position, captured pieces can't be indexed
+pub(crate) fn encode(turn: Color, squares: &[u8]) -> usize {
+    let index = squares.iter().enumerate().fold(0, |index, (i, square)| {
+        index | (*square as usize) << (6 * i)
+    });
+    match turn {
+        Color::White => index,
+        Color::Black => index | 1 << (6 * squares.len()),
+    }
+}
+
+fn king(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn offset(square: u8, (dx, dy): (i8, i8)) -> Option<u8> {
+    let x = (square % 8) as i8 + dx;
+    let y = (square / 8) as i8 + dy;
+    match (0..8).contains(&x) && (0..8).contains(&y) {
+        true => Some((y * 8 + x) as u8),
+        false => None,
+    }
+}
+
+fn occupant(squares: &[u8], square: u8) -> Option<usize> {
+    squares.iter().position(|s| *s == square)
+}
+
+/// Rank where pawns of this color are promoted
+fn last_rank(color: Color) -> u8 {
+    match color {
+        Color::White => 7,
+        Color::Black => 0,
+    }
+}
+
+fn forward(color: Color) -> i8 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
+    }
+}
+
+fn directions(piece_type: Type) -> &'static [(i8, i8)] {
+    match piece_type {
+        Type::Rook => &ROOK_DIRECTIONS,
+        Type::Bishop => &BISHOP_DIRECTIONS,
+        Type::Queen => &KING_STEPS,
+        _ => &[],
+    }
+}
+
+fn attacks(piece_type: Type, color: Color, from: u8, target: u8, squares: &[u8]) -> bool {
+    let dx = (target % 8) as i8 - (from % 8) as i8;
+    let dy = (target / 8) as i8 - (from / 8) as i8;
+    let straight = dx == 0 || dy == 0;
+    let diagonal = dx.abs() == dy.abs();
+    match piece_type {
+        Type::King => dx.abs().max(dy.abs()) == 1,
+        Type::Knight => dx.abs() * dy.abs() == 2,
+        Type::Pawn => dx.abs() == 1 && dy == forward(color),
+        Type::Rook if !straight => false,
+        Type::Bishop if !diagonal => false,
+        Type::Queen if !straight && !diagonal => false,
+        _ if from == target => false,
+        _ => {
+            // Every square between them must be empty
+            let direction = (dx.signum(), dy.signum());
+            let mut square = from;
+            while let Some(next) = offset(square, direction) {
+                if next == target {
+                    return true;
+                }
+                if occupant(squares, next).is_some() {
+                    return false;
+                }
+                square = next;
+            }
+            false
+        }
+    }
+}
+
+pub(crate) fn in_check(layout: &[(Type, Color)], squares: &[u8], color: Color) -> bool {
+    let king_square = squares[king(color)];
+    layout
+        .iter()
+        .zip(squares)
+        .any(|((piece_type, piece_color), square)| {
+            *piece_color != color
+                && *square != NONE
+                && attacks(*piece_type, *piece_color, *square, king_square, squares)
+        })
+}
+
+/// Pieces on different squares, no pawn on the first or last rank and the side that just
+/// moved not in check
+pub(crate) fn is_legal(layout: &[(Type, Color)], squares: &[u8], turn: Color) -> bool {
+    let distinct = squares
+        .iter()
+        .enumerate()
+        .all(|(i, square)| !squares[..i].contains(square));
+    let pawns = layout.iter().zip(squares).all(|((piece_type, _), square)| {
+        *piece_type != Type::Pawn || (1..7).contains(&(square / 8))
+    });
+    distinct && pawns && !in_check(layout, squares, turn.opponent())
+}
+
+/// Squares a piece can move to or come from, without allocating. A queen has 27 at most
+struct Targets {
+    list: [(u8, Option<usize>); 32],
+    len: usize,
+}
+
+impl Targets {
+    fn new() -> Targets {
+        Targets {
+            list: [(NONE, None); 32],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, target: (u8, Option<usize>)) {
+        self.list[self.len] = target;
+        self.len += 1;
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (u8, Option<usize>)> + '_ {
+        self.list[..self.len].iter().copied()
+    }
+}
+
+/// Every legal move of the side to move, captures of the king excluded
+pub(crate) fn legal_steps(layout: &[(Type, Color)], squares: &[u8], turn: Color) -> Vec<Step> {
+    let mut steps = Vec::new();
+    add_legal_steps(layout, squares, turn, &mut steps);
+    steps
+}
+
+fn add_legal_steps(layout: &[(Type, Color)], squares: &[u8], turn: Color, steps: &mut Vec<Step>) {
+    steps.clear();
+    for (piece, (piece_type, color)) in layout.iter().enumerate() {
+        let from = squares[piece];
+        if *color != turn || from == NONE {
+            continue;
+        }
+
+        let mut targets = Targets::new();
+        let capture_or_quiet = |to: u8, targets: &mut Targets| -> bool {
+            match occupant(squares, to) {
+                None => {
+                    targets.push((to, None));
+                    true
+                }
+                Some(other) => {
+                    if layout[other].1 != turn && layout[other].0 != Type::King {
+                        targets.push((to, Some(other)));
+                    }
+                    false
+                }
+            }
+        };
+        match piece_type {
+            Type::King | Type::Knight => {
+                let jumps = match piece_type {
+                    Type::King => &KING_STEPS,
+                    _ => &KNIGHT_JUMPS,
+                };
+                for to in jumps.iter().filter_map(|jump| offset(from, *jump)) {
+                    capture_or_quiet(to, &mut targets);
+                }
+            }
+            Type::Pawn => {
+                let dy = forward(turn);
+                if let Some(to) =
+                    offset(from, (0, dy)).filter(|to| occupant(squares, *to).is_none())
+                {
+                    targets.push((to, None));
+                    let start = last_rank(turn.opponent()) as i8 + dy;
+                    if from / 8 == start as u8 {
+                        if let Some(to) =
+                            offset(to, (0, dy)).filter(|to| occupant(squares, *to).is_none())
+                        {
+                            targets.push((to, None));
+                        }
+                    }
+                }
+                for to in [-1, 1].iter().filter_map(|dx| offset(from, (*dx, dy))) {
+                    if let Some(other) = occupant(squares, to) {
+                        if layout[other].1 != turn && layout[other].0 != Type::King {
+                            targets.push((to, Some(other)));
+                        }
+                    }
+                }
+            }
+            slider => {
+                for direction in directions(*slider) {
+                    let mut square = from;
+                    while let Some(to) = offset(square, *direction) {
+                        if !capture_or_quiet(to, &mut targets) {
+                            break;
+                        }
+                        square = to;
+                    }
+                }
+            }
+        }
+
+        for (to, captured) in targets.iter() {
+            let mut child = copy(squares);
+            child[piece] = to;
+            if let Some(captured) = captured {
+                child[captured] = NONE;
+            }
+            if in_check(layout, &child[..squares.len()], turn) {
+                continue;
+            }
+
+            let promotions: &[Option<Type>] =
+                match *piece_type == Type::Pawn && to / 8 == last_rank(turn) {
+                    true => &[
+                        Some(Type::Queen),
+                        Some(Type::Rook),
+                        Some(Type::Bishop),
+                        Some(Type::Knight),
+                    ],
+                    false => &[None],
+                };
+            for promotion in promotions {
+                steps.push(Step {
+                    piece,
+                    to,
+                    captured,
+                    promotion: *promotion,
+                });
+            }
+        }
+    }
+}
+
+/// The pieces left after a move
+pub(crate) fn after(
+    layout: &[(Type, Color)],
+    squares: &[u8],
+    step: Step,
+) -> Vec<(Type, Color, u8)> {
+    layout
+        .iter()
+        .zip(squares)
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != step.captured)
+        .map(|(i, ((piece_type, color), square))| match i == step.piece {
+            true => (step.promotion.unwrap_or(*piece_type), *color, step.to),
+            false => (*piece_type, *color, *square),
+        })
+        .collect()
+}
+
+/// Positions that reach this one with a move that is not a capture or a promotion
+fn add_parents(layout: &[(Type, Color)], squares: &[u8], turn: Color, parents: &mut Vec<usize>) {
+    parents.clear();
+    let mover = turn.opponent();
+    for (piece, (piece_type, color)) in layout.iter().enumerate() {
+        if *color != mover {
+            continue;
+        }
+        let to = squares[piece];
+        let empty = |square: &u8| occupant(squares, *square).is_none();
+
+        let mut origins = Targets::new();
+        match piece_type {
+            Type::King | Type::Knight => {
+                let jumps = match piece_type {
+                    Type::King => &KING_STEPS,
+                    _ => &KNIGHT_JUMPS,
+                };
+                for from in jumps.iter().filter_map(|jump| offset(to, *jump)) {
+                    origins.push((from, None));
+                }
+            }
+            Type::Pawn => {
+                let back = -forward(mover);
+                // Not from the first rank, and only double steps back to the starting rank
+                if let Some(from) = offset(to, (0, back))
+                    .filter(|from| empty(from) && from / 8 != last_rank(mover.opponent()))
+                {
+                    origins.push((from, None));
+                    let start = last_rank(mover.opponent()) as i8 - back;
+                    if let Some(from) =
+                        offset(from, (0, back)).filter(|from| *from / 8 == start as u8)
+                    {
+                        origins.push((from, None));
+                    }
+                }
+            }
+            slider => {
+                for direction in directions(*slider) {
+                    let mut square = to;
+                    while let Some(from) = offset(square, *direction) {
+                        if !empty(&from) {
+                            break;
+                        }
+                        origins.push((from, None));
+                        square = from;
+                    }
+                }
+            }
+        }
+
+        for (from, _) in origins.iter().filter(|(from, _)| empty(from)) {
+            let mut parent = copy(squares);
+            parent[piece] = from;
+            let parent = &parent[..squares.len()];
+            if !in_check(layout, parent, turn) {
+                parents.push(encode(mover, parent));
+            }
+        }
+    }
+}
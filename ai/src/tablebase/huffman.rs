@@ -0,0 +1,147 @@
+//! Canonical Huffman coding of table values. A block is the code length of the 256 byte values,
+//! the length of the bit stream in bytes (4 bytes, little endian) then the codes, most
+//! significant bit first
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Longer codes are avoided by flattening the frequencies
+const MAX_CODE_LENGTH: usize = 24;
+
+pub(crate) fn encode(values: &[u8], bytes: &mut Vec<u8>) {
+    let mut frequencies = [0u64; 256];
+    for value in values {
+        frequencies[*value as usize] += 1;
+    }
+    let lengths = code_lengths(frequencies);
+    let codes = canonical_codes(&lengths);
+
+    let mut stream = Vec::new();
+    let (mut buffer, mut bits) = (0u64, 0);
+    for value in values {
+        let length = lengths[*value as usize] as u32;
+        buffer = buffer << length | codes[*value as usize] as u64;
+        bits += length;
+        while bits >= 8 {
+            bits -= 8;
+            stream.push((buffer >> bits) as u8);
+        }
+    }
+    if bits > 0 {
+        stream.push((buffer << (8 - bits)) as u8);
+    }
+
+    bytes.extend(lengths);
+    bytes.extend((stream.len() as u32).to_le_bytes());
+    bytes.extend(stream);
+}
+
+/// Reads `count` values, returns them with the number of bytes read
+pub(crate) fn decode(bytes: &[u8], count: usize) -> Option<(Vec<u8>, usize)> {
+    let lengths: [u8; 256] = bytes.get(..256)?.try_into().ok()?;
+    let size = u32::from_le_bytes(bytes.get(256..260)?.try_into().ok()?) as usize;
+    let stream = bytes.get(260..260 + size)?;
+
+    let mut counts = [0usize; MAX_CODE_LENGTH + 1];
+    for length in lengths {
+        if length as usize > MAX_CODE_LENGTH {
+            return None;
+        }
+        counts[length as usize] += 1;
+    }
+    counts[0] = 0;
+    let mut symbols: Vec<u8> = (0..=255).filter(|s| lengths[*s as usize] > 0).collect();
+    symbols.sort_by_key(|symbol| lengths[*symbol as usize]);
+
+    let mut values = Vec::with_capacity(count);
+    let mut bits = stream
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) as usize & 1));
+    while values.len() < count {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        let mut found = None;
+        for count in &counts[1..] {
+            code |= bits.next()?;
+            if code < first + count {
+                found = Some(symbols[index + code - first]);
+                break;
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        values.push(found?);
+    }
+    Some((values, 260 + size))
+}
+
+fn code_lengths(mut frequencies: [u64; 256]) -> [u8; 256] {
+    loop {
+        let lengths = tree_depths(&frequencies);
+        if lengths
+            .iter()
+            .all(|length| *length as usize <= MAX_CODE_LENGTH)
+        {
+            return lengths;
+        }
+        for frequency in frequencies.iter_mut().filter(|f| **f > 0) {
+            *frequency = *frequency / 2 + 1;
+        }
+    }
+}
+
+/// Depth of every symbol in a Huffman tree, 0 for unused symbols
+fn tree_depths(frequencies: &[u64; 256]) -> [u8; 256] {
+    // Leaves are 0 to 255, inner nodes come after them
+    let mut parents: Vec<usize> = vec![usize::MAX; 256];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, frequency)| **frequency > 0)
+        .map(|(symbol, frequency)| Reverse((*frequency, symbol)))
+        .collect();
+
+    let mut lengths = [0u8; 256];
+    if heap.len() == 1 {
+        let Reverse((_, symbol)) = heap.pop().unwrap_or_default();
+        lengths[symbol] = 1;
+        return lengths;
+    }
+    while heap.len() > 1 {
+        let (Some(Reverse((a, left))), Some(Reverse((b, right)))) = (heap.pop(), heap.pop()) else {
+            break;
+        };
+        let node = parents.len();
+        parents.push(usize::MAX);
+        parents[left] = node;
+        parents[right] = node;
+        heap.push(Reverse((a + b, node)));
+    }
+
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        if frequencies[symbol] == 0 {
+            continue;
+        }
+        let mut node = symbol;
+        while parents[node] != usize::MAX {
+            node = parents[node];
+            *length = length.saturating_add(1);
+        }
+    }
+    lengths
+}
+
+/// Codes of the same length are consecutive, shorter ones first, by symbol
+fn canonical_codes(lengths: &[u8; 256]) -> [u32; 256] {
+    let mut codes = [0u32; 256];
+    let mut code = 0u32;
+    for length in 1..=MAX_CODE_LENGTH as u8 {
+        for symbol in 0..256 {
+            if lengths[symbol] == length {
+                codes[symbol] = code;
+                code += 1;
+            }
+        }
+        code <<= 1;
+    }
+    codes
+}
@@ -0,0 +1,202 @@
+use std::{cmp::Reverse, collections::BTreeSet, fmt::Display, str::FromStr};
+
+use engine::piece::{Color, Type};
+
+use crate::moves::PROMOTION_TYPES;
+
+/// Pieces besides the kings, in the order tables index them
+const PIECE_TYPES: [Type; 5] = [
+    Type::Queen,
+    Type::Rook,
+    Type::Bishop,
+    Type::Knight,
+    Type::Pawn,
+];
+
+/// The pieces of an endgame besides the kings, most valuable first. Tables are only stored
+/// for the orientation where White is the stronger side, see [`Material::is_canonical`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Material {
+    pub white: Vec<Type>,
+    pub black: Vec<Type>,
+}
+
+impl Material {
+    pub fn new(mut white: Vec<Type>, mut black: Vec<Type>) -> Material {
+        white.sort_by_key(|piece_type| Reverse(*piece_type));
+        black.sort_by_key(|piece_type| Reverse(*piece_type));
+        Material { white, black }
+    }
+
+    /// Every endgame with up to `max_pieces` pieces (kings included) that can end in a mate,
+    /// in an order where the endgames a table depends on come before it
+    pub fn all(max_pieces: usize) -> Vec<Material> {
+        let mut materials = BTreeSet::new();
+        for extra in 1..=max_pieces.saturating_sub(2) {
+            for white in 0..=extra {
+                for white_pieces in combinations(white) {
+                    for black_pieces in combinations(extra - white) {
+                        let material = Material::new(white_pieces.clone(), black_pieces);
+                        if material.can_mate() {
+                            materials.insert(material.canonical().0);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut materials: Vec<Material> = materials.into_iter().collect();
+        materials.sort_by_key(|material| (material.pieces(), material.pawns()));
+        materials
+    }
+
+    /// Pieces on the board, kings included
+    pub fn pieces(&self) -> usize {
+        2 + self.white.len() + self.black.len()
+    }
+
+    pub fn pawns(&self) -> usize {
+        self.white
+            .iter()
+            .chain(&self.black)
+            .filter(|piece_type| **piece_type == Type::Pawn)
+            .count()
+    }
+
+    pub fn side(&self, color: Color) -> &[Type] {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    /// Colors swapped
+    pub fn flipped(&self) -> Material {
+        Material {
+            white: self.black.clone(),
+            black: self.white.clone(),
+        }
+    }
+
+    /// White has more pieces, or as many and the most valuable ones
+    pub fn is_canonical(&self) -> bool {
+        // Pieces are sorted, comparing them compares the most valuable first
+        (self.white.len(), &self.white) >= (self.black.len(), &self.black)
+    }
+
+    /// The orientation tables are stored in, and whether the colors had to be swapped
+    pub fn canonical(&self) -> (Material, bool) {
+        match self.is_canonical() {
+            true => (self.clone(), false),
+            false => (self.flipped(), true),
+        }
+    }
+
+    /// False when no sequence of moves, even the most cooperative one, ends in a mate: a lone
+    /// king against a king with nothing or a single minor piece
+    pub fn can_mate(&self) -> bool {
+        let minor = |pieces: &[Type]| match pieces {
+            [] => true,
+            [piece_type] => matches!(piece_type, Type::Bishop | Type::Knight),
+            _ => false,
+        };
+        !(self.white.is_empty() && minor(&self.black)
+            || self.black.is_empty() && minor(&self.white))
+    }
+
+    /// The kings then the white and the black pieces, the order squares are indexed in
+    pub(crate) fn layout(&self) -> Vec<(Type, Color)> {
+        let mut layout = vec![(Type::King, Color::White), (Type::King, Color::Black)];
+        layout.extend(self.white.iter().map(|t| (*t, Color::White)));
+        layout.extend(self.black.iter().map(|t| (*t, Color::Black)));
+        layout
+    }
+
+    /// Endgames reached by a capture or a promotion
+    pub(crate) fn successors(&self) -> Vec<Material> {
+        let mut successors = Vec::new();
+        for color in [Color::White, Color::Black] {
+            let pieces = self.side(color);
+            for (i, piece_type) in pieces.iter().enumerate() {
+                let mut captured = pieces.to_vec();
+                captured.remove(i);
+                successors.push(self.with_side(color, captured));
+
+                if *piece_type == Type::Pawn {
+                    for promotion in PROMOTION_TYPES {
+                        let mut promoted = pieces.to_vec();
+                        promoted[i] = promotion;
+                        successors.push(self.with_side(color, promoted));
+                    }
+                }
+            }
+        }
+        successors
+    }
+
+    fn with_side(&self, color: Color, pieces: Vec<Type>) -> Material {
+        match color {
+            Color::White => Material::new(pieces, self.black.clone()),
+            Color::Black => Material::new(self.white.clone(), pieces),
+        }
+    }
+}
+
+/// Every multiset of `count` pieces, most valuable first
+fn combinations(count: usize) -> Vec<Vec<Type>> {
+    if count == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result = Vec::new();
+    for smaller in combinations(count - 1) {
+        for piece_type in PIECE_TYPES {
+            // Only non increasing sequences, so every multiset appears once
+            if smaller.last().is_none_or(|last| *last >= piece_type) {
+                let mut pieces = smaller.clone();
+                pieces.push(piece_type);
+                result.push(pieces);
+            }
+        }
+    }
+    result
+}
+
+fn letter(piece_type: Type) -> char {
+    match piece_type {
+        Type::Pawn => 'P',
+        Type::Knight => 'N',
+        Type::Bishop => 'B',
+        Type::Rook => 'R',
+        Type::Queen => 'Q',
+        Type::King => 'K',
+    }
+}
+
+/// `KQvKR`
+impl Display for Material {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = |pieces: &[Type]| -> String { pieces.iter().map(|t| letter(*t)).collect() };
+        write!(f, "K{}vK{}", side(&self.white), side(&self.black))
+    }
+}
+
+impl FromStr for Material {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (white, black) = s.split_once('v').ok_or(())?;
+        let side = |pieces: &str| -> Result<Vec<Type>, ()> {
+            let pieces = pieces.strip_prefix('K').ok_or(())?;
+            pieces
+                .chars()
+                .map(|c| {
+                    PIECE_TYPES
+                        .into_iter()
+                        .find(|piece_type| letter(*piece_type) == c)
+                        .ok_or(())
+                })
+                .collect()
+        };
+        Ok(Material::new(side(white)?, side(black)?))
+    }
+}
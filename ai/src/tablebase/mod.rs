@@ -0,0 +1,91 @@
+use std::cmp::Reverse;
+
+use engine::board::Board;
+
+use crate::moves::{self, Move};
+
+pub mod dtm;
+pub mod generator;
+mod huffman;
+pub mod material;
+pub mod syzygy;
+
+#[cfg(test)]
+mod test;
+
+/// Result of a position with best play, for the side to move. Blessed losses and cursed wins
+/// are only drawn because of the fifty-move rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    /// The same result seen by the other player
+    pub fn opponent(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+/// Perfect knowledge of the endgames with few pieces left
+pub trait Tablebase: Send + Sync {
+    /// Most pieces, kings included, a position can have to be in the tables
+    fn max_pieces(&self) -> usize;
+
+    /// None when the position is not in the tables
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl>;
+
+    /// Plies to the next capture, pawn move or mate with best play, positive when the side to
+    /// move wins, negative when it loses and 0 for draws. Tables that only know the distance
+    /// to mate can return it, it is never shorter
+    fn probe_dtz(&self, board: &Board) -> Option<i32>;
+}
+
+/// Pieces on the board, kings included
+pub fn piece_count(board: &Board) -> usize {
+    board.get_pieces().iter().flatten().flatten().count()
+}
+
+/// The moves that keep the best result of the position, the ones that win the fastest (or lose
+/// the slowest) first. None when the position or one of its children is not in the tables
+pub fn root_moves(tablebase: &dyn Tablebase, board: &Board) -> Option<Vec<Move>> {
+    if piece_count(board) > tablebase.max_pieces() {
+        return None;
+    }
+
+    let mut ranked = Vec::new();
+    for movement in moves::legal_moves(board) {
+        let child = moves::apply(board, movement)?;
+        let (wdl, plies) = if child.get_winner() == Some(board.get_turn()) {
+            (Wdl::Win, 1)
+        } else {
+            let wdl = tablebase.probe_wdl(&child)?.opponent();
+            let dtz = tablebase.probe_dtz(&child)?;
+            (wdl, 1 + dtz.abs())
+        };
+        // Wins as fast as possible, losses as slow as possible
+        let distance = if wdl > Wdl::Draw { plies } else { -plies };
+        ranked.push((Reverse(wdl), distance, movement));
+    }
+
+    let best = ranked.iter().map(|(wdl, _, _)| *wdl).min()?;
+    ranked.retain(|(wdl, _, _)| *wdl == best);
+    // Stable, equal moves keep the generation order
+    ranked.sort_by_key(|(_, distance, _)| *distance);
+    Some(
+        ranked
+            .into_iter()
+            .map(|(_, _, movement)| movement)
+            .collect(),
+    )
+}
@@ -0,0 +1,1232 @@
+use std::{
+    cmp::max,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use engine::{
+    board::Board,
+    piece::{Color, Type},
+};
+
+use crate::{
+    moves::{self, Move},
+    search::hash,
+};
+
+use super::{dtm::TablebaseError, material::Material, piece_count, Tablebase, Wdl};
+
+/// File extension of the win/draw/loss tables
+pub const WDL_EXTENSION: &str = "rtbw";
+/// File extension of the distance to zeroing tables
+pub const DTZ_EXTENSION: &str = "rtbz";
+
+/// Most pieces, kings included, of the endgames Syzygy tables exist for
+pub const MAX_PIECES: usize = 7;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+/// Layout flags of a table file
+const SPLIT: u8 = 1;
+const HAS_PAWNS: u8 = 2;
+
+/// Flags of a subtable
+const BLACK_TO_MOVE: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE_DTZ: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+const MAX_BLOCK_SIZE: usize = 1024;
+
+/// Index of the squares of the a1-d1-d4 triangle, the ones below the diagonal first
+#[rustfmt::skip]
+const TRIANGLE: [u64; 64] = [
+    6, 0, 1, 2, 2, 1, 0, 6,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    6, 0, 1, 2, 2, 1, 0, 6,
+];
+
+/// Index of the squares below the a1-h8 diagonal, the diagonal itself comes last
+#[rustfmt::skip]
+const LOWER: [u64; 64] = [
+    28,  0,  1,  2,  3,  4,  5,  6,
+     0, 29,  7,  8,  9, 10, 11, 12,
+     1,  7, 30, 13, 14, 15, 16, 17,
+     2,  8, 13, 31, 18, 19, 20, 21,
+     3,  9, 14, 18, 32, 22, 23, 24,
+     4, 10, 15, 19, 22, 33, 25, 26,
+     5, 11, 16, 20, 23, 25, 34, 27,
+     6, 12, 17, 21, 24, 26, 27, 35,
+];
+
+/// Positions of the two kings in tables without a third unique piece
+const KING_PAIRS: u64 = 462;
+/// Positions of the three leading pieces in tables with at least three unique pieces
+const UNIQUE_TRIPLES: u64 = 31_332;
+
+const KING_INDEX: [[u64; 64]; 10] = king_index();
+const PAWNS: PawnIndex = PawnIndex::new();
+
+/// Index of the two kings, by the triangle index of the first one and the square of the
+/// second. Positions with both kings on the diagonal come last, `u64::MAX` when the kings touch
+const fn king_index() -> [[u64; 64]; 10] {
+    let mut index = [[u64::MAX; 64]; 10];
+    let mut code = 0;
+    let mut triangle = 0;
+    while triangle < 10 {
+        let mut first = 0;
+        while first < 64 {
+            if is_in_triangle(first) && TRIANGLE[first] as usize == triangle {
+                let mut second = 0;
+                while second < 64 {
+                    let skipped = kings_touch(first, second)
+                        || on_diagonal(first) && (above_diagonal(second) || on_diagonal(second));
+                    if !skipped {
+                        index[triangle][second] = code;
+                        code += 1;
+                    }
+                    second += 1;
+                }
+            }
+            first += 1;
+        }
+        triangle += 1;
+    }
+
+    let mut triangle = 0;
+    while triangle < 10 {
+        let mut first = 0;
+        while first < 64 {
+            if is_in_triangle(first) && TRIANGLE[first] as usize == triangle && on_diagonal(first) {
+                let mut second = 0;
+                while second < 64 {
+                    if !kings_touch(first, second) && on_diagonal(second) {
+                        index[triangle][second] = code;
+                        code += 1;
+                    }
+                    second += 1;
+                }
+            }
+            first += 1;
+        }
+        triangle += 1;
+    }
+    index
+}
+
+/// The a1-d1-d4 triangle
+const fn is_in_triangle(square: usize) -> bool {
+    square % 8 < 4 && square / 8 <= square % 8
+}
+
+const fn on_diagonal(square: usize) -> bool {
+    square % 8 == square / 8
+}
+
+const fn above_diagonal(square: usize) -> bool {
+    square / 8 > square % 8
+}
+
+const fn kings_touch(first: usize, second: usize) -> bool {
+    let files = (first % 8).abs_diff(second % 8);
+    let ranks = (first / 8).abs_diff(second / 8);
+    files <= 1 && ranks <= 1
+}
+
+/// How the leading pawns are indexed, they are kept on the files a to d
+struct PawnIndex {
+    /// Order of the pawn squares, the files next to the edge come first
+    squares: [u64; 64],
+    /// By count of leading pawns and square of the first one
+    lead: [[u64; 64]; 6],
+    /// By count of leading pawns and file of the first one
+    lead_size: [[u64; 4]; 6],
+}
+
+impl PawnIndex {
+    const fn new() -> PawnIndex {
+        let mut squares = [0; 64];
+        let mut lead = [[0; 64]; 6];
+        let mut lead_size = [[0; 4]; 6];
+        let mut available = 48;
+
+        let mut count = 1;
+        while count <= 5 {
+            let mut file = 0;
+            while file < 4 {
+                let mut index = 0;
+                let mut rank = 1;
+                while rank < 7 {
+                    let square = file + 8 * rank;
+                    if count == 1 {
+                        available -= 1;
+                        squares[square] = available;
+                        available -= 1;
+                        squares[square ^ 7] = available;
+                    }
+                    lead[count][square] = index;
+                    index += binomial(squares[square], count as u64 - 1);
+                    rank += 1;
+                }
+                lead_size[count][file] = index;
+                file += 1;
+            }
+            count += 1;
+        }
+
+        PawnIndex {
+            squares,
+            lead,
+            lead_size,
+        }
+    }
+}
+
+const fn binomial(mut n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    if k > n - k {
+        return binomial(n, n - k);
+    }
+    let mut result = 1;
+    let mut d = 1;
+    while d <= k {
+        result = result * n / d;
+        n -= 1;
+        d += 1;
+    }
+    result
+}
+
+fn flip_vertical(square: usize) -> usize {
+    square ^ 56
+}
+
+fn flip_horizontal(square: usize) -> usize {
+    square ^ 7
+}
+
+fn flip_diagonal(square: usize) -> usize {
+    (square % 8) * 8 + square / 8
+}
+
+fn rank(square: usize) -> u64 {
+    (square / 8) as u64
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Children of a node of the symbol tree, 12 bits each
+fn read_children(data: &[u8], at: usize) -> Option<(u16, u16)> {
+    let bytes = data.get(at..at + 3)?;
+    let left = (u16::from(bytes[1] & 0xf) << 8) | u16::from(bytes[0]);
+    let right = (u16::from(bytes[2]) << 4) | (u16::from(bytes[1]) >> 4);
+    Some((left, right))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Metric {
+    Wdl,
+    Dtz,
+}
+
+type Piece = (Type, Color);
+
+/// A piece as the table headers write it, the black ones have the 8 bit set
+fn piece_from_nibble(nibble: u8) -> Option<Piece> {
+    let color = match nibble & 8 {
+        0 => Color::White,
+        _ => Color::Black,
+    };
+    let piece_type = match nibble & 7 {
+        1 => Type::Pawn,
+        2 => Type::Knight,
+        3 => Type::Bishop,
+        4 => Type::Rook,
+        5 => Type::Queen,
+        6 => Type::King,
+        _ => return None,
+    };
+    Some((piece_type, color))
+}
+
+fn material_of(pieces: impl Iterator<Item = Piece>) -> Material {
+    let mut white = Vec::new();
+    let mut black = Vec::new();
+    for (piece_type, color) in pieces.filter(|(piece_type, _)| *piece_type != Type::King) {
+        match color {
+            Color::White => white.push(piece_type),
+            Color::Black => black.push(piece_type),
+        }
+    }
+    Material::new(white, black)
+}
+
+/// How the squares of the pieces are turned into an index: the pieces are split in groups
+/// indexed together, and the index of every group is multiplied by its factor
+#[derive(Debug)]
+struct Groups {
+    pieces: Vec<Piece>,
+    lengths: Vec<usize>,
+    factors: Vec<u64>,
+}
+
+impl Groups {
+    fn new(pieces: Vec<Piece>, order: [u8; 2], file: usize) -> Option<Groups> {
+        let material = material_of(pieces.iter().copied());
+        let has_pawns = material.pawns() > 0;
+        let both_pawns =
+            material.white.contains(&Type::Pawn) && material.black.contains(&Type::Pawn);
+        let unique = unique_pieces(&pieces);
+
+        // The leading pawns, the three leading unique pieces or the two kings come first, then
+        // every run of identical pieces
+        let first = match (has_pawns, unique >= 3) {
+            (true, _) => 0,
+            (false, true) => 3,
+            (false, false) => 2,
+        };
+        let mut lengths = if first > 0 { vec![first] } else { Vec::new() };
+        let mut start = first;
+        while start < pieces.len() {
+            let end = (start..pieces.len())
+                .find(|i| pieces[*i] != pieces[start])
+                .unwrap_or(pieces.len());
+            lengths.push(end - start);
+            start = end;
+        }
+        if (has_pawns && lengths[0] >= PAWNS.lead.len()) || (both_pawns && lengths.len() < 2) {
+            return None;
+        }
+
+        let mut factors = vec![0; lengths.len() + 1];
+        let mut free_squares = 64 - lengths[0] - if both_pawns { lengths[1] } else { 0 };
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut index = 1u64;
+        let mut k = 0;
+        while next < lengths.len() || k == order[0] || k == order[1] {
+            if k == order[0] {
+                factors[0] = index;
+                index = index.checked_mul(match (has_pawns, unique >= 3) {
+                    (true, _) => PAWNS.lead_size[lengths[0]][file],
+                    (false, true) => UNIQUE_TRIPLES,
+                    (false, false) => KING_PAIRS,
+                })?;
+            } else if k == order[1] {
+                factors[1] = index;
+                index = index.checked_mul(binomial(48 - lengths[0] as u64, lengths[1] as u64))?;
+            } else {
+                factors[next] = index;
+                index = index.checked_mul(binomial(free_squares as u64, lengths[next] as u64))?;
+                free_squares = free_squares.checked_sub(lengths[next])?;
+                next += 1;
+            }
+            k += 1;
+            if k > 15 {
+                return None;
+            }
+        }
+        factors[lengths.len()] = index;
+
+        Some(Groups {
+            pieces,
+            lengths,
+            factors,
+        })
+    }
+
+    /// Positions of the subtable
+    fn size(&self) -> u64 {
+        self.factors[self.lengths.len()]
+    }
+}
+
+/// Kinds of pieces there is exactly one of, the kings included
+fn unique_pieces(pieces: &[Piece]) -> usize {
+    pieces
+        .iter()
+        .filter(|piece| pieces.iter().filter(|other| other == piece).count() == 1)
+        .count()
+}
+
+/// Turns the stored values of a distance to zeroing subtable back into distances, by result
+#[derive(Debug)]
+struct DtzMap {
+    start: usize,
+    wide: bool,
+    /// Offsets of the wins, losses, cursed wins and blessed losses
+    offsets: [usize; 4],
+}
+
+impl DtzMap {
+    fn read(&self, data: &[u8], wdl: Wdl, value: u16) -> Option<u16> {
+        let offset = self.offsets[match wdl {
+            Wdl::Win => 0,
+            Wdl::Loss => 1,
+            Wdl::CursedWin => 2,
+            Wdl::BlessedLoss | Wdl::Draw => 3,
+        }] + usize::from(value);
+        match self.wide {
+            true => read_u16(data, self.start + 2 * offset),
+            false => data.get(self.start + offset).map(|value| u16::from(*value)),
+        }
+    }
+}
+
+/// The values of one side to move, Huffman coded in blocks. Every symbol stands for a value or
+/// a pair of other symbols
+#[derive(Debug)]
+struct Subtable {
+    flags: u8,
+    groups: Groups,
+    /// The value of every position when the subtable only has one
+    single_value: u16,
+    block_size: u32,
+    /// There is an entry of the sparse index about every `span` values
+    span: u32,
+    blocks: u32,
+    /// Offset of the symbol tree
+    tree: usize,
+    min_length: u8,
+    /// Offset of the first symbol of every code length
+    lowest_symbols: usize,
+    /// First code of every length, aligned to the left of 64 bits
+    base: Vec<u64>,
+    /// Values each symbol stands for, minus one
+    symbol_lengths: Vec<u8>,
+    sparse_index: usize,
+    sparse_index_size: u32,
+    block_lengths: usize,
+    block_lengths_size: u32,
+    data: usize,
+    dtz_map: Option<DtzMap>,
+}
+
+impl Subtable {
+    /// The header at `at`, returns the subtable and the offset after it
+    fn parse(data: &[u8], at: usize, groups: Groups, metric: Metric) -> Option<(Subtable, usize)> {
+        let flags = *data.get(at)?;
+        let mut subtable = Subtable {
+            flags,
+            groups,
+            single_value: 0,
+            block_size: 0,
+            span: 0,
+            blocks: 0,
+            tree: 0,
+            min_length: 0,
+            lowest_symbols: 0,
+            base: Vec::new(),
+            symbol_lengths: Vec::new(),
+            sparse_index: 0,
+            sparse_index_size: 0,
+            block_lengths: 0,
+            block_lengths_size: 0,
+            data: 0,
+            dtz_map: None,
+        };
+        if flags & SINGLE_VALUE != 0 {
+            if metric == Metric::Wdl {
+                subtable.single_value = u16::from(*data.get(at + 1)?);
+            }
+            return Some((subtable, at + 2));
+        }
+
+        let header = data.get(at..at + 10)?;
+        subtable.block_size = 1u32.checked_shl(u32::from(header[1]))?;
+        if subtable.block_size as usize > MAX_BLOCK_SIZE {
+            return None;
+        }
+        subtable.span = 1u32.checked_shl(u32::from(header[2]))?;
+        subtable.sparse_index_size = subtable
+            .groups
+            .size()
+            .div_ceil(u64::from(subtable.span))
+            .try_into()
+            .ok()?;
+        subtable.blocks = read_u32(header, 4)?;
+        subtable.block_lengths_size = subtable.blocks.checked_add(u32::from(header[3]))?;
+        let (max_length, min_length) = (header[8], header[9]);
+        if max_length > 32 || min_length > max_length {
+            return None;
+        }
+        subtable.min_length = min_length;
+
+        let lengths = usize::from(max_length - min_length + 1);
+        subtable.lowest_symbols = at + 10;
+        let mut base = vec![0u64; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = |i: usize| read_u16(data, subtable.lowest_symbols + 2 * i).map(u64::from);
+            base[i] = base[i + 1]
+                .checked_add(lowest(i)?)?
+                .checked_sub(lowest(i + 1)?)?
+                / 2;
+            if base[i] * 2 < base[i + 1] {
+                return None;
+            }
+        }
+        for (i, base) in base.iter_mut().enumerate() {
+            *base = base.checked_shl(64 - (u32::from(min_length) + i as u32))?;
+        }
+        subtable.base = base;
+
+        let mut at = at + 10 + 2 * lengths;
+        let symbols = usize::from(read_u16(data, at)?);
+        at += 2;
+        subtable.tree = at;
+        let mut symbol_lengths = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            symbol_length(data, at, &mut symbol_lengths, &mut visited, symbol, 16)?;
+        }
+        subtable.symbol_lengths = symbol_lengths;
+        at += 3 * symbols + (symbols & 1);
+
+        Some((subtable, at))
+    }
+
+    /// The value stored at `index`, from the block it is coded in
+    fn value(&self, data: &[u8], index: u64, metric: Metric) -> Option<u16> {
+        if self.flags & SINGLE_VALUE != 0 {
+            return Some(self.single_value);
+        }
+
+        // The sparse index points near the block, which is then found with the block lengths
+        let entry = self.sparse_index + 6 * usize::try_from(index / u64::from(self.span)).ok()?;
+        let mut block = read_u32(data, entry)?;
+        let offset = i64::from(read_u16(data, entry + 4)?);
+        let mut position =
+            (index % u64::from(self.span)) as i64 - i64::from(self.span) / 2 + offset;
+        let block_length = |block: u32| -> Option<i64> {
+            Some(i64::from(read_u16(data, self.block_lengths + 2 * block as usize)?) + 1)
+        };
+        while position < 0 {
+            block = block.checked_sub(1)?;
+            position += block_length(block)?;
+        }
+        while position >= block_length(block)? {
+            position -= block_length(block)?;
+            block = block.checked_add(1)?;
+        }
+
+        let start = self.data + block as usize * self.block_size as usize;
+        let mut bits = BitReader::new(data.get(start..)?);
+        let mut buffer = bits.next_u64();
+        let mut buffered = 64;
+        let mut symbol;
+        loop {
+            let mut length = 0;
+            while buffer < *self.base.get(length)? {
+                length += 1;
+            }
+            symbol = ((buffer - self.base[length]) >> (64 - length - usize::from(self.min_length)))
+                as usize;
+            symbol += usize::from(read_u16(data, self.lowest_symbols + 2 * length)?);
+            let values = i64::from(*self.symbol_lengths.get(symbol)?) + 1;
+            if position < values {
+                break;
+            }
+            position -= values;
+            length += usize::from(self.min_length);
+            buffer <<= length;
+            buffered -= length;
+            if buffered <= 32 {
+                buffered += 32;
+                buffer |= u64::from(bits.next_u32()) << (64 - buffered);
+            }
+        }
+
+        // Down the pairs to the value
+        while self.symbol_lengths[symbol] != 0 {
+            let (left, right) = read_children(data, self.tree + 3 * symbol)?;
+            let left_values = i64::from(*self.symbol_lengths.get(usize::from(left))?) + 1;
+            if position < left_values {
+                symbol = usize::from(left);
+            } else {
+                position -= left_values;
+                symbol = usize::from(right);
+            }
+        }
+        let value = self.tree + 3 * symbol;
+        match metric {
+            Metric::Wdl => data.get(value).map(|value| u16::from(*value)),
+            Metric::Dtz => read_u16(data, value).map(|value| value & 0xfff),
+        }
+    }
+}
+
+/// Values a symbol of the tree stands for, minus one
+fn symbol_length(
+    data: &[u8],
+    tree: usize,
+    lengths: &mut [u8],
+    visited: &mut [bool],
+    symbol: usize,
+    depth: u8,
+) -> Option<()> {
+    if *visited.get(symbol)? {
+        return Some(());
+    }
+    let (left, right) = read_children(data, tree + 3 * symbol)?;
+    if right == 0xfff {
+        lengths[symbol] = 0;
+    } else {
+        let (left, right) = (usize::from(left), usize::from(right));
+        let depth = depth.checked_sub(1)?;
+        symbol_length(data, tree, lengths, visited, left, depth)?;
+        symbol_length(data, tree, lengths, visited, right, depth)?;
+        lengths[symbol] = lengths[left].checked_add(lengths[right])?.checked_add(1)?;
+    }
+    visited[symbol] = true;
+    Some(())
+}
+
+/// Big endian reads of a block, zeros past the end of the file
+struct BitReader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data, at: 0 }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.data.get(self.at).copied().unwrap_or(0);
+        self.at += 1;
+        byte
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        (0..4).fold(0, |value, _| value << 8 | u32::from(self.next_byte()))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        (0..8).fold(0, |value, _| value << 8 | u64::from(self.next_byte()))
+    }
+}
+
+/// Where the value of a position is in a table
+enum Lookup<'a> {
+    Found(&'a Subtable, u64),
+    /// Distance to zeroing tables only keep one side to move
+    OtherSide,
+}
+
+/// A table file read in memory
+struct Table {
+    data: Vec<u8>,
+    metric: Metric,
+    /// The endgame as the first subtable sees it
+    material: Material,
+    symmetric: bool,
+    has_pawns: bool,
+    /// One entry for every file of the leading pawn, a to d, or a single one without pawns.
+    /// Each has a subtable for every side to move the table keeps
+    files: Vec<Vec<Subtable>>,
+}
+
+impl Table {
+    fn parse(data: Vec<u8>, material: &Material, metric: Metric) -> Option<Table> {
+        let magic = match metric {
+            Metric::Wdl => WDL_MAGIC,
+            Metric::Dtz => DTZ_MAGIC,
+        };
+        if data.get(..4)? != magic {
+            return None;
+        }
+        let layout = *data.get(4)?;
+        let has_pawns = layout & HAS_PAWNS != 0;
+        let symmetric = material.white == material.black;
+        if has_pawns != (material.pawns() > 0) || (layout & SPLIT != 0) == symmetric {
+            return None;
+        }
+
+        let both_pawns =
+            material.white.contains(&Type::Pawn) && material.black.contains(&Type::Pawn);
+        let files = if has_pawns { 4 } else { 1 };
+        let sides = match metric {
+            Metric::Wdl if !symmetric => 2,
+            _ => 1,
+        };
+        let count = material.pieces();
+
+        let mut at = 5;
+        let mut groups = Vec::new();
+        for file in 0..files {
+            let first = *data.get(at)?;
+            let second = match both_pawns {
+                true => *data.get(at + 1)?,
+                false => 0xff,
+            };
+            let orders = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+            at += 1 + usize::from(both_pawns);
+
+            let mut file_groups = Vec::new();
+            for (side, order) in orders.into_iter().enumerate().take(sides) {
+                let pieces = data
+                    .get(at..at + count)?
+                    .iter()
+                    .map(|byte| piece_from_nibble(if side == 0 { byte & 0xf } else { byte >> 4 }))
+                    .collect::<Option<Vec<Piece>>>()?;
+                let key = material_of(pieces.iter().copied());
+                if key != *material && key != material.flipped() {
+                    return None;
+                }
+                file_groups.push(Groups::new(pieces, order, file)?);
+            }
+            at += count;
+            groups.push(file_groups);
+        }
+        at += at & 1;
+
+        let first_pieces = groups.first()?.first()?.pieces.clone();
+        if (first_pieces[0].0 == Type::Pawn) != has_pawns {
+            return None;
+        }
+        let table_material = material_of(first_pieces.into_iter());
+
+        let mut files = Vec::new();
+        for file_groups in groups {
+            let mut subtables = Vec::new();
+            for groups in file_groups {
+                let (subtable, next) = Subtable::parse(&data, at, groups, metric)?;
+                subtables.push(subtable);
+                at = next;
+            }
+            files.push(subtables);
+        }
+
+        if metric == Metric::Dtz {
+            let start = at;
+            for subtables in &mut files {
+                let subtable = &mut subtables[0];
+                if subtable.flags & MAPPED == 0 {
+                    continue;
+                }
+                let wide = subtable.flags & WIDE_DTZ != 0;
+                let mut offsets = [0; 4];
+                for offset in &mut offsets {
+                    if wide {
+                        *offset = (at - start + 2) / 2;
+                        at += 2 * usize::from(read_u16(&data, at)?) + 2;
+                    } else {
+                        *offset = at - start + 1;
+                        at += usize::from(*data.get(at)?) + 1;
+                    }
+                }
+                subtable.dtz_map = Some(DtzMap {
+                    start,
+                    wide,
+                    offsets,
+                });
+            }
+            at += at & 1;
+        }
+
+        for subtable in files.iter_mut().flatten() {
+            subtable.sparse_index = at;
+            at += 6 * subtable.sparse_index_size as usize;
+        }
+        for subtable in files.iter_mut().flatten() {
+            subtable.block_lengths = at;
+            at += 2 * subtable.block_lengths_size as usize;
+        }
+        for subtable in files.iter_mut().flatten() {
+            // Blocks start on 64 bytes boundaries
+            at = (at + 0x3f) & !0x3f;
+            subtable.data = at;
+            at += subtable.blocks as usize * subtable.block_size as usize;
+        }
+        if at > data.len() {
+            return None;
+        }
+
+        Some(Table {
+            data,
+            metric,
+            material: table_material,
+            symmetric,
+            has_pawns,
+            files,
+        })
+    }
+
+    /// Finds the subtable of the position and its index in it
+    fn lookup(&self, pieces: &[Option<Piece>; 64], turn: Color) -> Option<Lookup<'_>> {
+        let key = material_of(pieces.iter().flatten().copied());
+        // The colors are swapped and the board mirrored when the table sees the position from
+        // the other side
+        let flip = (self.symmetric && turn == Color::Black) || key != self.material;
+        let black_side = (turn == Color::Black) != flip;
+        let flip_color = |color: Color| if flip { color.opponent() } else { color };
+        let flip_square = |square: usize| if flip { flip_vertical(square) } else { square };
+
+        let mut squares = Vec::new();
+        let mut used = [false; 64];
+        let file = match self.has_pawns {
+            true => {
+                let (_, color) = self.files[0][0].groups.pieces[0];
+                let lead = Some((Type::Pawn, flip_color(color)));
+                for square in (0..64).filter(|square| pieces[*square] == lead) {
+                    used[square] = true;
+                    squares.push(flip_square(square));
+                }
+                // The leading pawn is the one the furthest from the edge files
+                for i in 1..squares.len() {
+                    if PAWNS.squares[squares[0]] < PAWNS.squares[squares[i]] {
+                        squares.swap(0, i);
+                    }
+                }
+                let file = squares.first()? % 8;
+                file.min(7 - file)
+            }
+            false => 0,
+        };
+
+        let subtables = self.files.get(file)?;
+        let subtable = &subtables[if black_side { subtables.len() - 1 } else { 0 }];
+        if self.metric == Metric::Dtz
+            && (subtable.flags & BLACK_TO_MOVE != 0) != black_side
+            && (!self.symmetric || self.has_pawns)
+        {
+            return Some(Lookup::OtherSide);
+        }
+
+        let leading_pawns = squares.len();
+        for (piece_type, color) in subtable.groups.pieces.iter().skip(leading_pawns) {
+            let piece = Some((*piece_type, flip_color(*color)));
+            let square = (0..64).find(|square| !used[*square] && pieces[*square] == piece)?;
+            used[square] = true;
+            squares.push(flip_square(square));
+        }
+        if squares.len() != subtable.groups.pieces.len() {
+            return None;
+        }
+
+        if squares[0] % 8 >= 4 {
+            squares
+                .iter_mut()
+                .for_each(|square| *square = flip_horizontal(*square));
+        }
+
+        let groups = &subtable.groups;
+        let mut index = match self.has_pawns {
+            true => {
+                let mut index = PAWNS.lead[leading_pawns][squares[0]];
+                squares[1..leading_pawns].sort_by_key(|square| PAWNS.squares[*square]);
+                for (i, square) in squares.iter().enumerate().take(leading_pawns).skip(1) {
+                    index += binomial(PAWNS.squares[*square], i as u64);
+                }
+                index
+            }
+            false => {
+                if squares[0] / 8 >= 4 {
+                    squares
+                        .iter_mut()
+                        .for_each(|square| *square = flip_vertical(*square));
+                }
+                // The first leading piece off the diagonal goes below it
+                if let Some(square) = squares[..groups.lengths[0]]
+                    .iter()
+                    .find(|square| !on_diagonal(**square))
+                {
+                    if above_diagonal(*square) {
+                        squares
+                            .iter_mut()
+                            .for_each(|square| *square = flip_diagonal(*square));
+                    }
+                }
+                leading_index(&squares, groups.lengths[0])?
+            }
+        };
+        index *= groups.factors[0];
+
+        // The other groups, each as a combination of the squares left
+        let mut remaining_pawns =
+            self.material.white.contains(&Type::Pawn) && self.material.black.contains(&Type::Pawn);
+        let mut start = groups.lengths[0];
+        for (next, length) in groups.lengths.iter().enumerate().skip(1) {
+            let (previous, group) = squares.split_at_mut(start);
+            let group = &mut group[..*length];
+            group.sort_unstable();
+            let mut group_index = 0;
+            for (i, square) in group.iter().enumerate() {
+                let below = previous.iter().filter(|other| *square > **other).count();
+                let pawn_ranks = if remaining_pawns { 8 } else { 0 };
+                let free = (*square as u64).checked_sub((below + pawn_ranks) as u64)?;
+                group_index += binomial(free, i as u64 + 1);
+            }
+            remaining_pawns = false;
+            index += group_index * groups.factors[next];
+            start += length;
+        }
+
+        Some(Lookup::Found(subtable, index))
+    }
+
+    fn probe_wdl(&self, pieces: &[Option<Piece>; 64], turn: Color) -> Option<Wdl> {
+        let Lookup::Found(subtable, index) = self.lookup(pieces, turn)? else {
+            return None;
+        };
+        match subtable.value(&self.data, index, self.metric)? {
+            0 => Some(Wdl::Loss),
+            1 => Some(Wdl::BlessedLoss),
+            2 => Some(Wdl::Draw),
+            3 => Some(Wdl::CursedWin),
+            4 => Some(Wdl::Win),
+            _ => None,
+        }
+    }
+
+    /// Plies from the first move to the next zeroing move, None when the table doesn't keep
+    /// this side to move
+    fn probe_dtz(
+        &self,
+        pieces: &[Option<Piece>; 64],
+        turn: Color,
+        wdl: Wdl,
+    ) -> Option<Option<i32>> {
+        let (subtable, index) = match self.lookup(pieces, turn)? {
+            Lookup::Found(subtable, index) => (subtable, index),
+            Lookup::OtherSide => return Some(None),
+        };
+        let mut value = subtable.value(&self.data, index, self.metric)?;
+        if let Some(map) = &subtable.dtz_map {
+            value = map.read(&self.data, wdl, value)?;
+        }
+        // Most tables round the distance to full moves
+        let exact = match wdl {
+            Wdl::Win => subtable.flags & WIN_PLIES != 0,
+            Wdl::Loss => subtable.flags & LOSS_PLIES != 0,
+            _ => false,
+        };
+        let plies = i32::from(value);
+        Some(Some(if exact { plies } else { 2 * plies }))
+    }
+}
+
+/// Index of the leading group of a table without pawns, the first square is in the a1-d1-d4
+/// triangle and the first square off the diagonal is below it
+fn leading_index(squares: &[usize], length: usize) -> Option<u64> {
+    let square = |i: usize| squares[i] as u64;
+    match length {
+        3 => {
+            let adjust1 = u64::from(squares[1] > squares[0]);
+            let adjust2 = u64::from(squares[2] > squares[0]) + u64::from(squares[2] > squares[1]);
+            Some(if !on_diagonal(squares[0]) {
+                TRIANGLE[squares[0]] * 63 * 62 + (square(1) - adjust1) * 62 + square(2) - adjust2
+            } else if !on_diagonal(squares[1]) {
+                6 * 63 * 62 + rank(squares[0]) * 28 * 62 + LOWER[squares[1]] * 62 + square(2)
+                    - adjust2
+            } else if !on_diagonal(squares[2]) {
+                6 * 63 * 62
+                    + 4 * 28 * 62
+                    + rank(squares[0]) * 7 * 28
+                    + (rank(squares[1]) - adjust1) * 28
+                    + LOWER[squares[2]]
+            } else {
+                6 * 63 * 62
+                    + 4 * 28 * 62
+                    + 4 * 7 * 28
+                    + rank(squares[0]) * 7 * 6
+                    + (rank(squares[1]) - adjust1) * 6
+                    + (rank(squares[2]) - adjust2)
+            })
+        }
+        2 => {
+            let index = KING_INDEX[TRIANGLE[squares[0]] as usize][squares[1]];
+            (index != u64::MAX).then_some(index)
+        }
+        _ => None,
+    }
+}
+
+/// A table file of the directory, read the first time it is probed
+struct TableFile {
+    path: PathBuf,
+    table: OnceLock<Option<Table>>,
+}
+
+/// Syzygy endgame tables: win/draw/loss `.rtbw` files for the search and distance to zeroing
+/// `.rtbz` files for the root moves. Distances are in plies to the next capture, pawn move or
+/// mate and most tables round them to full moves, a distance can be one ply longer than the
+/// real one. Results take the fifty-move rule into account from a position just after a
+/// capture or a pawn move
+pub struct SyzygyTablebase {
+    wdl: BTreeMap<Material, TableFile>,
+    dtz: BTreeMap<Material, TableFile>,
+    max_pieces: usize,
+}
+
+impl SyzygyTablebase {
+    /// Finds the tables of a directory, named like `KQvKR.rtbw`. They are read when probed
+    pub fn open(directory: impl AsRef<Path>) -> Result<SyzygyTablebase, TablebaseError> {
+        let io_error = |error: std::io::Error| TablebaseError::Io(error.to_string());
+        let mut tablebase = SyzygyTablebase {
+            wdl: BTreeMap::new(),
+            dtz: BTreeMap::new(),
+            max_pieces: 0,
+        };
+        for entry in fs::read_dir(directory).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            let tables = match path.extension().and_then(|extension| extension.to_str()) {
+                Some(WDL_EXTENSION) => &mut tablebase.wdl,
+                Some(DTZ_EXTENSION) => &mut tablebase.dtz,
+                _ => continue,
+            };
+            let invalid = || TablebaseError::InvalidFile(path.display().to_string());
+            let material: Material = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse().ok())
+                .filter(|material: &Material| material.pieces() <= MAX_PIECES)
+                .ok_or_else(invalid)?;
+            // Tables are made of 64 bytes blocks and a 16 bytes checksum
+            if fs::metadata(&path).map_err(io_error)?.len() % 64 != 16 {
+                return Err(invalid());
+            }
+
+            tablebase.max_pieces = tablebase.max_pieces.max(material.pieces());
+            tables.insert(
+                material,
+                TableFile {
+                    path,
+                    table: OnceLock::new(),
+                },
+            );
+        }
+        Ok(tablebase)
+    }
+
+    /// Endgames with a win/draw/loss table
+    pub fn materials(&self) -> impl Iterator<Item = &Material> {
+        self.wdl.keys()
+    }
+
+    fn table(&self, material: &Material, metric: Metric) -> Option<&Table> {
+        let tables = match metric {
+            Metric::Wdl => &self.wdl,
+            Metric::Dtz => &self.dtz,
+        };
+        let file = tables
+            .get(material)
+            .or_else(|| tables.get(&material.flipped()))?;
+        file.table
+            .get_or_init(|| {
+                let data = fs::read(&file.path).ok()?;
+                let material = material_of_file(&file.path)?;
+                Table::parse(data, &material, metric)
+            })
+            .as_ref()
+    }
+
+    /// The result stored in the tables, captures aside
+    fn probe_table_wdl(&self, board: &Board) -> Option<Wdl> {
+        let pieces = pieces(board);
+        let material = material_of(pieces.iter().flatten().copied());
+        if !material.can_mate() {
+            return Some(Wdl::Draw);
+        }
+        self.table(&material, Metric::Wdl)?
+            .probe_wdl(&pieces, board.get_turn())
+    }
+
+    fn probe_table_dtz(&self, board: &Board, wdl: Wdl) -> Option<Option<i32>> {
+        let pieces = pieces(board);
+        let material = material_of(pieces.iter().flatten().copied());
+        self.table(&material, Metric::Dtz)?
+            .probe_dtz(&pieces, board.get_turn(), wdl)
+    }
+
+    /// The result of the position, and whether the best move is a capture or a pawn move. The
+    /// tables can store any result lower than the one of a capture, so captures are searched
+    fn probe(&self, board: &Board) -> Option<(Wdl, bool)> {
+        if piece_count(board) > self.max_pieces
+            || board.get_promotion().is_some()
+            || hash::castling_rights(board).contains(&true)
+        {
+            return None;
+        }
+        // A mate keeps the turn of the side who mated
+        if board.get_winner().is_some() {
+            return Some((Wdl::Loss, false));
+        }
+
+        let legal_moves = moves::legal_moves(board);
+        let mut best_capture = Wdl::Loss;
+        let mut best_en_passant = Wdl::Loss;
+        for movement in &legal_moves {
+            if !moves::is_capture(board, *movement) {
+                continue;
+            }
+            let child = moves::apply(board, *movement)?;
+            let wdl = self
+                .probe_captures(&child, Wdl::Loss, best_capture.opponent())?
+                .opponent();
+            if wdl == Wdl::Win {
+                return Some((wdl, true));
+            }
+            match is_en_passant(board, *movement) {
+                true => best_en_passant = max(best_en_passant, wdl),
+                false => best_capture = max(best_capture, wdl),
+            }
+        }
+
+        let wdl = self.probe_table_wdl(board)?;
+        // The tables don't know about en passant
+        if best_en_passant > max(wdl, best_capture) {
+            return Some((best_en_passant, true));
+        }
+        best_capture = max(best_capture, best_en_passant);
+        if best_capture >= wdl {
+            return Some((best_capture, best_capture > Wdl::Draw));
+        }
+        // Stalemate without the en passant captures
+        let only_en_passant = !legal_moves.is_empty()
+            && legal_moves
+                .iter()
+                .all(|movement| is_en_passant(board, *movement));
+        if wdl == Wdl::Draw && only_en_passant {
+            return Some((best_en_passant, true));
+        }
+        Some((wdl, false))
+    }
+
+    /// Alpha-beta search of the captures, positions after a capture have no en passant
+    fn probe_captures(&self, board: &Board, mut alpha: Wdl, beta: Wdl) -> Option<Wdl> {
+        if board.get_winner().is_some() {
+            return Some(Wdl::Loss);
+        }
+        for movement in moves::legal_moves(board) {
+            if !moves::is_capture(board, movement) {
+                continue;
+            }
+            let child = moves::apply(board, movement)?;
+            let wdl = self
+                .probe_captures(&child, beta.opponent(), alpha.opponent())?
+                .opponent();
+            if wdl >= beta {
+                return Some(wdl);
+            }
+            alpha = max(alpha, wdl);
+        }
+        Some(max(alpha, self.probe_table_wdl(board)?))
+    }
+
+    /// Plies to the next zeroing move, the tables only keep one side to move and the other
+    /// one is found with a search of one ply
+    fn dtz(&self, board: &Board, wdl: Wdl, zeroing: bool) -> Option<i32> {
+        let before_zeroing = match wdl {
+            Wdl::Draw => return Some(0),
+            Wdl::Loss => -1,
+            Wdl::BlessedLoss => -101,
+            Wdl::CursedWin => 101,
+            Wdl::Win => 1,
+        };
+        let add_plies = |dtz: i32, plies: i32| dtz + dtz.signum() * plies;
+        if zeroing || board.get_winner().is_some() {
+            return Some(before_zeroing);
+        }
+
+        let legal_moves = moves::legal_moves(board);
+        let is_pawn_move = |movement: &Move| {
+            board
+                .get_piece_at(&movement.from)
+                .is_some_and(|piece| piece.get_type() == Type::Pawn)
+        };
+        // Captures were already searched, a winning pawn move is the best move
+        if wdl > Wdl::Draw {
+            for movement in legal_moves.iter().filter(|movement| is_pawn_move(movement)) {
+                if moves::is_capture(board, *movement) {
+                    continue;
+                }
+                let child = moves::apply(board, *movement)?;
+                if self.probe(&child)?.0.opponent() == wdl {
+                    return Some(before_zeroing);
+                }
+            }
+        }
+
+        if let Some(plies) = self.probe_table_dtz(board, wdl)? {
+            return Some(add_plies(before_zeroing, plies));
+        }
+
+        let mut best = match wdl > Wdl::Draw {
+            true => None,
+            false => Some(before_zeroing),
+        };
+        for movement in legal_moves {
+            if is_pawn_move(&movement) || moves::is_capture(board, movement) {
+                continue;
+            }
+            let child = moves::apply(board, movement)?;
+            if child.get_winner() == Some(board.get_turn()) {
+                best = Some(1);
+                continue;
+            }
+            let (child_wdl, child_zeroing) = self.probe(&child)?;
+            let dtz = -self.dtz(&child, child_wdl, child_zeroing)?;
+            if dtz.signum() == before_zeroing.signum() {
+                let dtz = add_plies(dtz, 1);
+                best = match best {
+                    Some(best) if best <= dtz => Some(best),
+                    _ => Some(dtz),
+                };
+            }
+        }
+        best
+    }
+}
+
+impl Tablebase for SyzygyTablebase {
+    fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        self.probe(board).map(|(wdl, _)| wdl)
+    }
+
+    fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        let (wdl, zeroing) = self.probe(board)?;
+        self.dtz(board, wdl, zeroing)
+    }
+}
+
+fn material_of_file(path: &Path) -> Option<Material> {
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+/// The pieces by square, a1 first
+fn pieces(board: &Board) -> [Option<Piece>; 64] {
+    let mut pieces = [None; 64];
+    for (y, row) in board.get_pieces().iter().enumerate() {
+        for (x, piece) in row.iter().enumerate() {
+            pieces[y * 8 + x] = piece.map(|piece| (piece.get_type(), piece.get_color()));
+        }
+    }
+    pieces
+}
+
+fn is_en_passant(board: &Board, movement: Move) -> bool {
+    moves::is_capture(board, movement) && board.get_piece_at(&movement.to).is_none()
+}
@@ -0,0 +1,446 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+};
+use rand::Rng;
+
+use crate::{
+    moves::{self, Move},
+    search::{SearchLimits, Searcher, MATE, TB_WIN},
+    seeded_rng,
+    tablebase::{
+        self,
+        dtm::{DtmTablebase, Table, TablebaseError},
+        generator,
+        material::Material,
+        syzygy::SyzygyTablebase,
+        Tablebase, Wdl,
+    },
+};
+
+/// Answers every position with up to `max_pieces` pieces with the result of `probe`
+struct FnTablebase {
+    max_pieces: usize,
+    probe: fn(&Board) -> (Wdl, i32),
+}
+
+impl Tablebase for FnTablebase {
+    fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        Some((self.probe)(board).0)
+    }
+
+    fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        Some((self.probe)(board).1)
+    }
+}
+
+fn queen_on_c1(board: &Board) -> bool {
+    let c1 = Position::from_str("c1").unwrap();
+    board
+        .get_piece_at(&c1)
+        .is_some_and(|piece| piece.get_type() == Type::Queen)
+}
+
+#[test]
+fn test_wdl_opponent() {
+    assert_eq!(Wdl::Win.opponent(), Wdl::Loss);
+    assert_eq!(Wdl::CursedWin.opponent(), Wdl::BlessedLoss);
+    assert_eq!(Wdl::Draw.opponent(), Wdl::Draw);
+}
+
+#[test]
+fn test_root_moves() {
+    let board = Board::from_fen("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1").unwrap();
+    // Black loses everywhere, except when the queen goes to c1
+    let tablebase = FnTablebase {
+        max_pieces: 3,
+        probe: |board| match queen_on_c1(board) {
+            true => (Wdl::Draw, 0),
+            false => (Wdl::Loss, -10),
+        },
+    };
+
+    let moves = tablebase::root_moves(&tablebase, &board).unwrap();
+    // Mate first, then every other winning move
+    assert_eq!(moves[0], Move::from_str("b1b8").unwrap());
+    assert!(!moves.contains(&Move::from_str("b1c1").unwrap()));
+    assert_eq!(moves.len(), crate::moves::legal_moves(&board).len() - 1);
+
+    let crowded = Board::new();
+    assert_eq!(tablebase::root_moves(&tablebase, &crowded), None);
+}
+
+#[test]
+fn test_search_plays_from_the_tables() {
+    let board = Board::from_fen("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1").unwrap();
+    let mut searcher = Searcher::default();
+    searcher.set_tablebase(Some(Arc::new(FnTablebase {
+        max_pieces: 3,
+        probe: |_| (Wdl::Loss, -10),
+    })));
+
+    let result = searcher.search(&board, SearchLimits::depth(5));
+    assert_eq!(result.best_move, Some(Move::from_str("b1b8").unwrap()));
+    assert_eq!(result.score, MATE - 1);
+    assert_eq!(result.nodes, 1);
+}
+
+#[test]
+fn test_search_probes_the_tree() {
+    let board = Board::from_fen("4k3/8/8/8/8/8/r7/Q3K3 w - - 0 1").unwrap();
+    let mut searcher = Searcher::default();
+    // Whoever has to move in a three pieces ending loses
+    searcher.set_tablebase(Some(Arc::new(FnTablebase {
+        max_pieces: 3,
+        probe: |_| (Wdl::Loss, -20),
+    })));
+
+    let result = searcher.search(&board, SearchLimits::depth(2));
+    assert_eq!(result.best_move, Some(Move::from_str("a1a2").unwrap()));
+    assert_eq!(result.score, TB_WIN - 1);
+}
+
+/// Every endgame up to three pieces, generated once for all the tests
+fn three_pieces() -> &'static DtmTablebase {
+    static TABLES: OnceLock<DtmTablebase> = OnceLock::new();
+    TABLES.get_or_init(|| DtmTablebase::generate(3))
+}
+
+/// The four pieces endgames there are Syzygy tables of, and the ones they lead to
+fn four_pieces() -> &'static DtmTablebase {
+    static TABLES: OnceLock<DtmTablebase> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tablebase = DtmTablebase::new();
+        tablebase.add(&material("KRvKR"));
+        tablebase.add(&material("KRvKB"));
+        tablebase
+    })
+}
+
+fn material(name: &str) -> Material {
+    Material::from_str(name).unwrap()
+}
+
+/// The distance to mate found by playing every move with the engine and probing the children
+fn one_ply_dtm(tablebase: &DtmTablebase, board: &Board) -> i32 {
+    let mut best_win: Option<i32> = None;
+    let mut draw = false;
+    let mut longest_loss = 0;
+    for movement in moves::legal_moves(board) {
+        let child = moves::apply(board, movement).unwrap();
+        if child.get_winner() == Some(board.get_turn()) {
+            best_win = Some(1);
+            continue;
+        }
+        match tablebase.probe_dtm(&child).unwrap() {
+            0 => draw = true,
+            dtm if dtm < 0 => best_win = Some(best_win.map_or(1 - dtm, |win| win.min(1 - dtm))),
+            dtm => longest_loss = longest_loss.max(dtm + 1),
+        }
+    }
+    match (best_win, draw) {
+        (Some(win), _) => win,
+        (None, true) => 0,
+        (None, false) => -longest_loss,
+    }
+}
+
+#[test]
+fn test_material() {
+    let krvkq = material("KRvKQ");
+    assert_eq!(krvkq.to_string(), "KRvKQ");
+    assert_eq!(krvkq.pieces(), 4);
+    assert!(!krvkq.is_canonical());
+    assert_eq!(krvkq.canonical(), (material("KQvKR"), true));
+    assert_eq!(material("KNBvK").to_string(), "KBNvK");
+    assert!(!material("KvKB").can_mate());
+    assert!(material("KNvKN").can_mate());
+    assert!(Material::from_str("QvK").is_err());
+
+    let names: Vec<String> = Material::all(3).iter().map(|m| m.to_string()).collect();
+    assert_eq!(names, vec!["KRvK", "KQvK", "KPvK"]);
+    let four = Material::all(4);
+    // Promotions lead to tables that come first
+    let kpvkp = four.iter().position(|m| *m == material("KPvKP")).unwrap();
+    let kqvkp = four.iter().position(|m| *m == material("KQvKP")).unwrap();
+    assert!(kqvkp < kpvkp);
+}
+
+#[test]
+fn test_longest_mates() {
+    let tablebase = three_pieces();
+    let longest = |name: &str| {
+        let table = tablebase.table(&material(name)).unwrap();
+        (
+            table.longest_mate(Color::White),
+            table.longest_mate(Color::Black),
+        )
+    };
+    // Mates in 10, 16 and 28 moves
+    assert_eq!(longest("KQvK"), (Some(19), None));
+    assert_eq!(longest("KRvK"), (Some(31), None));
+    assert_eq!(longest("KPvK"), (Some(55), None));
+}
+
+#[test]
+fn test_probes() {
+    let tablebase = three_pieces();
+    assert_eq!(tablebase.max_pieces(), 3);
+
+    let mate_in_one = Board::from_fen("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1").unwrap();
+    assert_eq!(tablebase.probe_dtm(&mate_in_one), Some(1));
+    assert_eq!(tablebase.probe_wdl(&mate_in_one), Some(Wdl::Win));
+    // The same position with the colors swapped
+    let mirrored = Board::from_fen("1q6/8/8/8/8/6k1/8/7K b - - 0 1").unwrap();
+    assert_eq!(tablebase.probe_dtm(&mirrored), Some(1));
+
+    let mated = Board::from_fen("1Q5k/8/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(tablebase.probe_dtm(&mated), Some(0));
+    assert_eq!(tablebase.probe_wdl(&mated), Some(Wdl::Loss));
+
+    let stalemate = Board::from_fen("7k/8/6Q1/8/8/8/8/K7 b - - 0 1").unwrap();
+    assert_eq!(tablebase.probe_wdl(&stalemate), Some(Wdl::Draw));
+    let lone_kings = Board::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+    assert_eq!(tablebase.probe_wdl(&lone_kings), Some(Wdl::Draw));
+
+    // The king can take the rook
+    let hanging_rook = Board::from_fen("8/8/8/8/8/2k5/3R4/K7 b - - 0 1").unwrap();
+    assert_eq!(tablebase.probe_wdl(&hanging_rook), Some(Wdl::Draw));
+    let castling = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    assert_eq!(tablebase.probe_wdl(&castling), None);
+    assert_eq!(tablebase.probe_wdl(&Board::new()), None);
+}
+
+/// Every sampled position is checked against its children, with the engine playing the moves
+#[test]
+fn test_tables_agree_with_engine() {
+    let tablebase = three_pieces();
+    let mut rng = seeded_rng(7);
+    for name in ["KQvK", "KRvK", "KPvK", "KvKQ", "KvKP"] {
+        let layout = material(name).layout();
+        let mut checked = 0;
+        while checked < 150 {
+            let squares: Vec<u8> = layout.iter().map(|_| rng.gen_range(0..64)).collect();
+            let turn = if rng.gen() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            if !generator::is_legal(&layout, &squares, turn) {
+                continue;
+            }
+            let board = Board::from_fen(&generator::fen(&layout, &squares, turn)).unwrap();
+            let dtm = tablebase.probe_dtm(&board).unwrap();
+            assert_eq!(dtm, one_ply_dtm(tablebase, &board), "{}", board.to_fen());
+            checked += 1;
+        }
+    }
+}
+
+#[test]
+fn test_move_generator_matches_engine() {
+    assert_eq!(
+        generator::engine_mismatches(&material("KPvK"), 1),
+        Vec::<String>::new()
+    );
+    for name in ["KQvKR", "KBNvK", "KPvKP", "KRvKP", "KNvKB"] {
+        let mismatches = generator::engine_mismatches(&material(name), 4099);
+        assert!(mismatches.is_empty(), "{}: {:?}", name, mismatches);
+    }
+}
+
+#[test]
+fn test_table_bytes() {
+    let table = three_pieces().table(&material("KRvK")).unwrap();
+    let bytes = table.to_bytes();
+    // Runs of draws and illegal positions compress well
+    assert!(bytes.len() < 2 * 10 * 64 * 64 / 2);
+    assert_eq!(Table::from_bytes(&bytes).as_ref(), Ok(table));
+
+    assert_eq!(
+        Table::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(TablebaseError::InvalidTable)
+    );
+    assert_eq!(
+        Table::from_bytes(b"RDTM"),
+        Err(TablebaseError::InvalidTable)
+    );
+}
+
+#[test]
+fn test_save_and_open() {
+    let directory = std::env::temp_dir().join(format!("tablebase-{}", std::process::id()));
+    three_pieces().save(&directory).unwrap();
+    let opened = DtmTablebase::open(&directory).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(opened.tables().count(), 3);
+    let board = Board::from_fen("8/8/8/4k3/8/8/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(opened.probe_dtm(&board), three_pieces().probe_dtm(&board));
+    assert!(matches!(
+        DtmTablebase::open(&directory),
+        Err(TablebaseError::Io(_))
+    ));
+}
+
+#[test]
+fn test_search_with_generated_tables() {
+    // Too far from the mate for the search alone
+    let board = Board::from_fen("8/8/3k4/8/8/8/8/R6K w - - 0 1").unwrap();
+    let dtm = three_pieces().probe_dtm(&board).unwrap();
+    assert!(dtm > 0);
+
+    let mut searcher = Searcher::default();
+    searcher.set_tablebase(Some(Arc::new(three_pieces().clone())));
+    let result = searcher.search(&board, SearchLimits::depth(3));
+    let child = moves::apply(&board, result.best_move.unwrap()).unwrap();
+    assert_eq!(three_pieces().probe_dtm(&child), Some(1 - dtm));
+}
+
+/// Syzygy tables of KQvK, KRvK, KPvK, KRvKR and KRvKB, as the generator of the Syzygy format wrote them
+fn syzygy() -> &'static SyzygyTablebase {
+    static TABLES: OnceLock<SyzygyTablebase> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        SyzygyTablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tables/syzygy")).unwrap()
+    })
+}
+
+#[test]
+fn test_syzygy_probes() {
+    let tablebase = syzygy();
+    assert_eq!(tablebase.max_pieces(), 4);
+    assert_eq!(tablebase.materials().count(), 5);
+    let probe = |fen: &str| {
+        let board = Board::from_fen(fen).unwrap();
+        (tablebase.probe_wdl(&board), tablebase.probe_dtz(&board))
+    };
+
+    // Distances to zeroing of the Syzygy test suite
+    assert_eq!(
+        probe("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1"),
+        (Some(Wdl::Win), Some(21))
+    );
+    assert_eq!(
+        probe("8/5p2/6k1/K7/8/8/8/8 w - - 0 1"),
+        (Some(Wdl::Loss), Some(-2))
+    );
+    assert_eq!(
+        probe("8/8/8/2K5/5kp1/8/8/8 b - - 0 1"),
+        (Some(Wdl::Win), Some(1))
+    );
+    assert_eq!(
+        probe("8/3k4/8/8/8/8/4P3/3K4 w - - 0 1"),
+        (Some(Wdl::Draw), Some(0))
+    );
+
+    assert_eq!(
+        probe("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1"),
+        (Some(Wdl::Win), Some(1))
+    );
+    assert_eq!(
+        probe("1Q5k/8/6K1/8/8/8/8/8 b - - 0 1"),
+        (Some(Wdl::Loss), Some(-1))
+    );
+    // The king can take the rook
+    assert_eq!(
+        probe("8/8/8/8/8/2k5/3R4/K7 b - - 0 1"),
+        (Some(Wdl::Draw), Some(0))
+    );
+    assert_eq!(
+        probe("7k/8/8/8/8/8/8/K7 w - - 0 1"),
+        (Some(Wdl::Draw), Some(0))
+    );
+
+    assert_eq!(probe("4k3/8/8/8/8/8/8/4K2R w K - 0 1"), (None, None));
+    // No KQvKR table, unless a capture wins
+    assert_eq!(probe("4k3/8/8/8/8/8/r7/3QK3 w - - 0 1"), (None, None));
+    assert_eq!(
+        probe("4k3/8/8/8/8/8/r7/Q3K3 w - - 0 1"),
+        (Some(Wdl::Win), Some(1))
+    );
+    // Mate in one, the probe of the child sees the mate
+    assert_eq!(
+        probe("4b3/8/8/8/2R5/8/5K2/7k w - - 0 1"),
+        (Some(Wdl::Win), Some(1))
+    );
+    assert_eq!(tablebase.probe_wdl(&Board::new()), None);
+}
+
+/// The Syzygy results match the generated tables. With three pieces and no pawns the next
+/// zeroing move is the mate, so the distances match too, up to the rounding to full moves
+#[test]
+fn test_syzygy_agrees_with_generated_tables() {
+    let mut rng = seeded_rng(11);
+    let endgames = [
+        ("KQvK", three_pieces()),
+        ("KRvK", three_pieces()),
+        ("KPvK", three_pieces()),
+        ("KvKQ", three_pieces()),
+        ("KvKR", three_pieces()),
+        ("KvKP", three_pieces()),
+        ("KRvKR", four_pieces()),
+        ("KRvKB", four_pieces()),
+        ("KBvKR", four_pieces()),
+    ];
+    for (name, tablebase) in endgames {
+        let layout = material(name).layout();
+        let mut checked = 0;
+        while checked < 300 {
+            let squares: Vec<u8> = layout.iter().map(|_| rng.gen_range(0..64)).collect();
+            let turn = if rng.gen() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            if !generator::is_legal(&layout, &squares, turn) {
+                continue;
+            }
+            let board = Board::from_fen(&generator::fen(&layout, &squares, turn)).unwrap();
+            let wdl = syzygy().probe_wdl(&board);
+            assert_eq!(wdl, tablebase.probe_wdl(&board), "{}", board.to_fen());
+
+            let dtz = syzygy().probe_dtz(&board).unwrap();
+            let dtm = tablebase.probe_dtm(&board).unwrap();
+            let sign = match wdl.unwrap() {
+                Wdl::Win => 1,
+                Wdl::Loss => -1,
+                _ => 0,
+            };
+            assert_eq!(dtz.signum(), sign, "{}", board.to_fen());
+            if layout.len() == 3 && !name.contains('P') {
+                let plies = dtz.abs() - dtm.abs();
+                assert!(plies == 0 || plies == 1, "{}", board.to_fen());
+            }
+            checked += 1;
+        }
+    }
+}
+
+#[test]
+fn test_search_with_syzygy_tables() {
+    // Only the double push keeps the pawn out of the king's reach
+    let board = Board::from_fen("8/8/8/8/1K6/8/5Pk1/8 w - - 0 1").unwrap();
+    assert_eq!(
+        tablebase::root_moves(syzygy(), &board),
+        Some(vec![Move::from_str("f2f4").unwrap()])
+    );
+
+    let board = Board::from_fen("8/8/3k4/8/8/8/8/R6K w - - 0 1").unwrap();
+    let mut searcher = Searcher::default();
+    searcher.set_tablebase(Some(Arc::new(
+        SyzygyTablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tables/syzygy")).unwrap(),
+    )));
+    let result = searcher.search(&board, SearchLimits::depth(3));
+    let child = moves::apply(&board, result.best_move.unwrap()).unwrap();
+    assert_eq!(syzygy().probe_wdl(&child), Some(Wdl::Loss));
+    assert!(syzygy().probe_dtz(&child).unwrap().abs() < syzygy().probe_dtz(&board).unwrap());
+}
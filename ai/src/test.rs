@@ -90,3 +90,689 @@ mod new_board {
         //assert_eq!(num_positions, 119060324);
     }
 }
+
+#[cfg(test)]
+mod fen_positions {
+    use engine::board::Board;
+
+    use crate::test::move_generation;
+
+    #[test]
+    fn kiwipete() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(move_generation(1, &mut board), 48);
+        assert_eq!(move_generation(2, &mut board), 2039);
+    }
+
+    #[test]
+    fn rook_endgame() {
+        let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_eq!(move_generation(1, &mut board), 14);
+        assert_eq!(move_generation(2, &mut board), 191);
+        assert_eq!(move_generation(3, &mut board), 2812);
+    }
+}
+
+#[cfg(test)]
+mod moves {
+    use std::str::FromStr;
+
+    use engine::piece::{position::Position, Type};
+
+    use crate::moves::Move;
+
+    #[test]
+    fn long_algebraic_notation() {
+        let movement = Move::from_str("e7e8n").unwrap();
+        assert_eq!(
+            movement,
+            Move::with_promotion(
+                Position::from_str("e7").unwrap(),
+                Position::from_str("e8").unwrap(),
+                Type::Knight
+            )
+        );
+        assert_eq!(movement.to_string(), "e7e8n");
+        assert_eq!(Move::from_str("g1f3").unwrap().to_string(), "g1f3");
+
+        assert!(Move::from_str("e7e8k").is_err());
+        assert!(Move::from_str("e7").is_err());
+        assert!(Move::from_str("i2i4").is_err());
+    }
+}
+
+#[cfg(test)]
+mod strength {
+    use std::{
+        str::FromStr,
+        time::{Duration, Instant},
+    };
+
+    use engine::{board::Board, piece::position::Position};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        moves::Move,
+        result::AiError,
+        strength::{Level, LevelError, LimitedSearcher},
+    };
+
+    fn play(moves: &[&str]) -> Board {
+        let mut board = Board::new();
+        for movement in moves {
+            let movement = Move::from_str(movement).unwrap();
+            board.move_piece(movement.from, movement.to).unwrap();
+        }
+        board
+    }
+
+    fn choose_move(level: u8, board: &Board, seed: u64) -> Result<Move, AiError> {
+        let mut searcher = LimitedSearcher::new(Level::new(level).unwrap());
+        let mut rng = StdRng::seed_from_u64(seed);
+        searcher.choose_move(board, &mut rng)
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(Level::new(0), Err(LevelError::OutOfRange(0)));
+        assert_eq!(Level::new(11), Err(LevelError::OutOfRange(11)));
+        assert_eq!(Level::new(5).unwrap().get(), 5);
+
+        assert_eq!(Level::from_elo(1250).get(), 5);
+        assert_eq!(Level::from_elo(0).get(), 1);
+        assert_eq!(Level::from_elo(3000).get(), 10);
+        assert!(Level::new(3).unwrap().elo() < Level::new(4).unwrap().elo());
+    }
+
+    #[test]
+    fn same_seed_same_move() {
+        let board = Board::new();
+        for seed in 0..5 {
+            assert_eq!(choose_move(3, &board, seed), choose_move(3, &board, seed));
+        }
+    }
+
+    #[test]
+    fn weak_levels_vary() {
+        let board = Board::new();
+        let mut chosen: Vec<Move> = (0..20)
+            .filter_map(|seed| choose_move(1, &board, seed).ok())
+            .collect();
+        chosen.sort_by_key(|movement| (movement.from, movement.to));
+        chosen.dedup();
+        assert!(chosen.len() > 1);
+    }
+
+    #[test]
+    fn stronger_levels_take_the_queen() {
+        let board = play(&["e2e4", "d7d6", "d1g4"]);
+        let capture = Move::new(
+            Position::from_str("c8").unwrap(),
+            Position::from_str("g4").unwrap(),
+        );
+        assert_eq!(choose_move(6, &board, 1), Ok(capture));
+    }
+
+    #[test]
+    fn full_strength_finds_mate() {
+        let board = play(&["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6"]);
+        let mate = Move::new(
+            Position::from_str("h5").unwrap(),
+            Position::from_str("f7").unwrap(),
+        );
+        assert_eq!(choose_move(10, &board, 0), Ok(mate));
+    }
+
+    #[test]
+    fn no_legal_moves() {
+        let board = play(&["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert_eq!(choose_move(1, &board, 0), Err(AiError::NoLegalMoves));
+    }
+
+    #[test]
+    fn time_budget() {
+        let board = play(&["e2e4", "e7e5"]);
+        for level in [5, 10] {
+            let mut searcher = LimitedSearcher::new(Level::new(level).unwrap());
+            searcher.set_time(Some(Duration::from_millis(20)));
+            let start = Instant::now();
+            let movement = searcher.choose_move(&board, &mut StdRng::seed_from_u64(0));
+            assert!(movement.is_ok());
+            // The level alone searches far longer
+            assert!(start.elapsed() < Duration::from_millis(500));
+        }
+
+        // With time to spare the level's limits stop the search
+        let board = play(&["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6"]);
+        let mate = Move::new(
+            Position::from_str("h5").unwrap(),
+            Position::from_str("f7").unwrap(),
+        );
+        let mut searcher = LimitedSearcher::new(Level::new(10).unwrap());
+        searcher.set_time(Some(Duration::from_secs(3600)));
+        let movement = searcher.choose_move(&board, &mut StdRng::seed_from_u64(0));
+        assert_eq!(movement, Ok(mate));
+    }
+}
+
+#[cfg(test)]
+mod random_mover {
+    use engine::board::Board;
+
+    use crate::{make_move, result::AiError, seeded_rng};
+
+    #[test]
+    fn same_seed_same_game() {
+        let play = |seed: u64| {
+            let mut rng = seeded_rng(seed);
+            let mut board = Board::new();
+            let mut moves = Vec::new();
+            for _ in 0..20 {
+                let (from, to) = match make_move(&board, &mut rng) {
+                    Ok(movement) => movement,
+                    Err(_) => break,
+                };
+                board.move_piece(from, to).unwrap();
+                moves.push((from, to));
+            }
+            moves
+        };
+
+        assert_eq!(play(7), play(7));
+        assert_ne!(play(7), play(8));
+    }
+
+    #[test]
+    fn no_legal_moves() {
+        let mut board = Board::new();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            board
+                .move_piece(from.parse().unwrap(), to.parse().unwrap())
+                .unwrap();
+        }
+        assert_eq!(
+            make_move(&board, &mut seeded_rng(0)),
+            Err(AiError::NoLegalMoves)
+        );
+    }
+}
+
+#[cfg(test)]
+mod promotion {
+    use std::str::FromStr;
+
+    use engine::{
+        board::Board,
+        piece::{position::Position, Type},
+    };
+
+    use crate::{make_promotion, result::AiError};
+
+    fn promote_from(fen: &str, from: &str, to: &str) -> Board {
+        let mut board = Board::from_fen(fen).unwrap();
+        board
+            .move_piece(
+                Position::from_str(from).unwrap(),
+                Position::from_str(to).unwrap(),
+            )
+            .unwrap();
+        board
+    }
+
+    #[test]
+    fn queen_by_default() {
+        let board = promote_from("8/P7/8/8/8/2k5/8/4K3 w - - 0 1", "a7", "a8");
+        assert_eq!(make_promotion(&board).unwrap().get_type(), Type::Queen);
+    }
+
+    #[test]
+    fn avoids_stalemate() {
+        // A queen or a bishop on b8 would take h2, the last square of the black king
+        let board = promote_from("8/1P6/8/8/8/8/8/5K1k w - - 0 1", "b7", "b8");
+        assert_eq!(make_promotion(&board).unwrap().get_type(), Type::Rook);
+    }
+
+    #[test]
+    fn knight_mate() {
+        // The black king is walled in by its own pieces, only a knight gives check
+        let board = promote_from("5bnr/4Ppkp/5ppp/8/8/8/8/K7 w - - 0 1", "e7", "e8");
+        assert_eq!(make_promotion(&board).unwrap().get_type(), Type::Knight);
+    }
+
+    #[test]
+    fn no_promotion() {
+        assert_eq!(
+            make_promotion(&Board::new()).err(),
+            Some(AiError::NoPromotion)
+        );
+    }
+}
+
+#[cfg(test)]
+mod player {
+    use std::str::FromStr;
+
+    use engine::{
+        board::Board,
+        game::Game,
+        piece::{position::Position, Color},
+        player::{
+            runner::{GameRunner, Outcome},
+            Player,
+        },
+    };
+
+    use crate::{
+        book::{builder::BookBuilder, BookSettings},
+        player::{AiPlayer, RandomPlayer},
+        seeded_rng,
+        strength::Level,
+    };
+
+    #[test]
+    fn ai_mates_random_player() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        let mut white = AiPlayer::new(Level::default(), seeded_rng(0));
+        let mut black = RandomPlayer::new(seeded_rng(0));
+
+        let outcome = GameRunner::from_game(game).run(&mut white, &mut black, |_| {});
+        assert_eq!(
+            outcome,
+            Outcome::Checkmate {
+                winner: Color::White
+            }
+        );
+    }
+
+    #[test]
+    fn ai_plays_book_moves() {
+        let mut builder = BookBuilder::new();
+        builder.add_pgn("1. a3 a6 2. h3 *").unwrap();
+        let settings = BookSettings {
+            depth: None,
+            variety: 0.0,
+        };
+        let mut player = AiPlayer::new(Level::new(1).unwrap(), seeded_rng(0))
+            .with_book(builder.build(), settings);
+
+        let board = Board::new();
+        let (from, to) = player.choose_move(&board).unwrap();
+        assert_eq!(
+            (from.to_string(), to.to_string()),
+            ("a2".into(), "a3".into())
+        );
+    }
+
+    #[test]
+    fn ai_counts_played_moves() {
+        let mut builder = BookBuilder::new();
+        builder.add_pgn("1. a3 a6 2. h3 *").unwrap();
+        let settings = BookSettings {
+            depth: Some(3),
+            variety: 0.0,
+        };
+        let mut player = AiPlayer::new(Level::new(1).unwrap(), seeded_rng(0))
+            .with_book(builder.build(), settings);
+
+        // A move asked again, as after a rejection, is still the first one
+        let board = Board::new();
+        let first = player.choose_move(&board).unwrap();
+        assert_eq!(player.choose_move(&board), Some(first));
+        player.played();
+
+        let mut game = Game::new();
+        game.move_piece(first.0, first.1).unwrap();
+        let (from, to) = (
+            Position::from_str("a7").unwrap(),
+            Position::from_str("a6").unwrap(),
+        );
+        game.move_piece(from, to).unwrap();
+        let (from, to) = player.choose_move(game.get_board()).unwrap();
+        assert_eq!(
+            (from.to_string(), to.to_string()),
+            ("h2".into(), "h3".into())
+        );
+    }
+
+    #[test]
+    fn random_players_finish_the_game() {
+        let play = |seed: u64| {
+            let mut white = RandomPlayer::new(seeded_rng(seed));
+            let mut black = RandomPlayer::new(seeded_rng(seed + 1));
+            let mut runner = GameRunner::new();
+            let outcome = runner.run(&mut white, &mut black, |_| {});
+            (outcome, runner.get_game().history().count())
+        };
+
+        assert_eq!(play(3), play(3));
+    }
+}
+
+#[cfg(test)]
+mod pgn {
+    use std::str::FromStr;
+
+    use engine::board::Board;
+
+    use crate::{
+        moves::{self, Move},
+        pgn::{self, GameResult, PgnError},
+    };
+
+    #[test]
+    fn games_and_tags() {
+        let text = r#"[Event "Casual"]
+[White "Morphy"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4) d6 $1 3. d4 Bg4?! 4.dxe5 1-0
+
+[Event "Second"]
+1. d4 d5 2. c4 *
+"#;
+        let games = pgn::parse(text).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("White"), Some("Morphy"));
+        assert_eq!(games[0].result, GameResult::WhiteWins);
+        assert_eq!(games[0].moves.len(), 7);
+        assert_eq!(games[0].moves[6], Move::from_str("d4e5").unwrap());
+        assert_eq!(games[1].tag("Event"), Some("Second"));
+        assert_eq!(games[1].result, GameResult::Unknown);
+        assert_eq!(games[1].positions().count(), 3);
+    }
+
+    #[test]
+    fn san() {
+        let board = Board::from_fen("r3k2r/1P6/8/8/3N4/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let parse = |san: &str| pgn::parse_san(&board, san);
+
+        assert_eq!(parse("O-O"), Ok(Move::from_str("e1g1").unwrap()));
+        assert_eq!(parse("O-O-O"), Ok(Move::from_str("e1c1").unwrap()));
+        assert_eq!(parse("bxa8=N+"), Ok(Move::from_str("b7a8n").unwrap()));
+        assert_eq!(parse("b8Q"), Ok(Move::from_str("b7b8q").unwrap()));
+        assert_eq!(parse("Rxa8"), Ok(Move::from_str("a1a8").unwrap()));
+        assert_eq!(parse("Nf3"), Ok(Move::from_str("d4f3").unwrap()));
+        assert_eq!(parse("Rhh2"), Ok(Move::from_str("h1h2").unwrap()));
+        assert_eq!(parse("b8"), Err(PgnError::IllegalMove("b8".to_string(), 0)));
+        assert_eq!(
+            parse("Nc3"),
+            Err(PgnError::IllegalMove("Nc3".to_string(), 0))
+        );
+    }
+
+    #[test]
+    fn ambiguous_and_illegal_moves() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(
+            pgn::parse_san(&board, "Rd1"),
+            Err(PgnError::AmbiguousMove("Rd1".to_string(), 0))
+        );
+        assert_eq!(
+            pgn::parse_san(&board, "Rad1"),
+            Ok(Move::from_str("a1d1").unwrap())
+        );
+
+        assert_eq!(
+            pgn::parse("1. e4 e5 2. Ke3 *").err(),
+            Some(PgnError::IllegalMove("Ke3".to_string(), 2))
+        );
+    }
+
+    #[test]
+    fn write_san() {
+        let board = Board::from_fen("r3k2r/1P6/8/8/3N4/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let san = |movement: &str| pgn::to_san(&board, Move::from_str(movement).unwrap());
+        assert_eq!(san("e1g1"), "O-O");
+        assert_eq!(san("e1c1"), "O-O-O");
+        assert_eq!(san("b7a8n"), "bxa8=N");
+        assert_eq!(san("b7b8q"), "b8=Q+");
+        assert_eq!(san("a1a8"), "Rxa8+");
+        assert_eq!(san("h1h2"), "Rh2");
+        let rooks = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(pgn::to_san(&rooks, Move::from_str("a1d1").unwrap()), "Rad1");
+
+        // Every legal move reads back as itself
+        for fen in [
+            "r3k2r/1P6/8/8/3N4/8/8/R3K2R w KQkq - 0 1",
+            "4k3/8/8/2N1N3/8/2N1N3/8/4K3 w - - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            for movement in moves::legal_moves(&board) {
+                let san = pgn::to_san(&board, movement);
+                assert_eq!(pgn::parse_san(&board, &san), Ok(movement), "{}", san);
+            }
+        }
+        assert_eq!(
+            pgn::to_san(
+                &Board::from_fen(
+                    "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4"
+                )
+                .unwrap(),
+                Move::from_str("h5f7").unwrap()
+            ),
+            "Qxf7#"
+        );
+    }
+
+    #[test]
+    fn write_games() {
+        let text =
+            "[Event \"Casual \\\"blitz\\\"\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n";
+        let games = pgn::parse(text).unwrap();
+        assert_eq!(games[0].to_string(), text);
+        assert_eq!(
+            pgn::parse(&games[0].to_string()).unwrap()[0].moves,
+            games[0].moves
+        );
+    }
+
+    #[test]
+    fn fen_tag() {
+        let games = pgn::parse("[FEN \"4k3/8/8/8/8/8/8/R3K3 w - - 0 1\"]\n1. Ra8# 1-0").unwrap();
+        assert_eq!(games[0].moves, vec![Move::from_str("a1a8").unwrap()]);
+    }
+}
+
+#[cfg(test)]
+mod solver {
+    use std::str::FromStr;
+
+    use engine::board::Board;
+
+    use crate::{moves, moves::Move, solver::MateSolver};
+
+    const LEGAL_MATE: &str = "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1";
+
+    #[test]
+    fn sound_problem() {
+        let board = Board::from_fen(LEGAL_MATE).unwrap();
+        let solution = MateSolver::new().solve(&board, 2);
+        assert!(solution.is_sound());
+
+        let key = &solution.keys[0];
+        assert_eq!(key.movement, Move::from_str("d5f6").unwrap());
+        assert_eq!(key.defences.len(), 1);
+        assert_eq!(key.defences[0].movement, Move::from_str("g7f6").unwrap());
+        assert_eq!(key.defences[0].replies.len(), 1);
+        assert_eq!(
+            key.defences[0].replies[0].movement,
+            Move::from_str("c4f7").unwrap()
+        );
+        assert!(key.defences[0].replies[0].is_mate());
+        assert_eq!(
+            solution.refutations.len(),
+            moves::legal_moves(&board).len() - 1
+        );
+    }
+
+    #[test]
+    fn refuted_and_short_solutions() {
+        let board = Board::from_fen(LEGAL_MATE).unwrap();
+        let mut solver = MateSolver::new();
+        let refuted = solver.solve(&board, 1);
+        assert!(!refuted.is_proven());
+        assert_eq!(refuted.refutations.len(), moves::legal_moves(&board).len());
+        assert!(refuted
+            .refutations
+            .iter()
+            .all(|(_, refutation)| refutation.is_some()));
+
+        let longer = solver.solve(&board, 3);
+        assert_eq!(longer.shortest, Some(2));
+        assert!(!longer.is_sound());
+    }
+
+    #[test]
+    fn cooks_and_duals() {
+        let board = Board::from_fen("k7/8/1K6/8/8/8/6R1/7R w - - 0 1").unwrap();
+        let mut solver = MateSolver::new();
+        let cooked = solver.solve(&board, 1);
+        let keys: Vec<Move> = cooked.keys.iter().map(|key| key.movement).collect();
+        assert!(keys.contains(&Move::from_str("h1h8").unwrap()));
+        assert!(keys.contains(&Move::from_str("g2g8").unwrap()));
+        assert!(!cooked.is_sound());
+
+        let doubled = solver.solve(&board, 2);
+        let dual = doubled
+            .keys
+            .iter()
+            .find(|key| !key.duals().is_empty())
+            .unwrap();
+        assert!(dual.to_string().contains("(dual)"));
+    }
+}
+
+#[cfg(test)]
+mod analysis {
+    use engine::{board::Board, piece::Color};
+
+    use crate::{
+        analysis::{Analyser, AnalysisError, MoveClass},
+        pgn,
+        search::SearchLimits,
+    };
+
+    #[test]
+    fn move_classes() {
+        assert_eq!(MoveClass::from_loss(0), MoveClass::Best);
+        assert_eq!(MoveClass::from_loss(30), MoveClass::Good);
+        assert_eq!(MoveClass::from_loss(50), MoveClass::Inaccuracy);
+        assert_eq!(MoveClass::from_loss(150), MoveClass::Mistake);
+        assert_eq!(MoveClass::from_loss(300), MoveClass::Blunder);
+        assert!(!MoveClass::Good.is_error());
+        assert_eq!(MoveClass::Blunder.annotation(), "??");
+    }
+
+    #[test]
+    fn scholars_mate() {
+        let game = &pgn::parse("1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0").unwrap()[0];
+        let analysis = Analyser::new(SearchLimits::depth(4))
+            .analyse_pgn(game)
+            .unwrap();
+        assert_eq!(analysis.moves.len(), 7);
+
+        let blunder = &analysis.moves[5];
+        assert_eq!(blunder.color, Color::Black);
+        assert_eq!(blunder.class, MoveClass::Blunder);
+        assert!(!blunder.better_line.is_empty());
+        assert_ne!(blunder.better_line[0], blunder.movement);
+
+        let mate = &analysis.moves[6];
+        assert_eq!(mate.class, MoveClass::Best);
+        assert!(mate.accuracy() > 99.9);
+        assert_eq!(analysis.count(Color::Black, MoveClass::Blunder), 1);
+        assert!(analysis.accuracy(Color::White) > analysis.accuracy(Color::Black));
+        assert!(analysis.to_string().contains("3... Nf6?? blunder"));
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let game = &pgn::parse("1. e4 e5 *").unwrap()[0];
+        let mut moves = game.moves.clone();
+        moves.push(moves[0]);
+        let error = Analyser::new(SearchLimits::depth(1)).analyse(&Board::new(), &moves);
+        assert_eq!(error.err(), Some(AnalysisError::IllegalMove(2)));
+    }
+}
+
+#[cfg(test)]
+mod puzzle {
+    use std::str::FromStr;
+
+    use engine::board::Board;
+
+    use crate::{
+        moves::Move,
+        pgn,
+        puzzle::{PuzzleFinder, Theme},
+    };
+
+    fn puzzle_at(fen: &str) -> Option<crate::puzzle::Puzzle> {
+        PuzzleFinder::new(4).puzzle_at(&Board::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn fork() {
+        let puzzle = puzzle_at("q3k3/5ppp/8/1N6/8/8/5PPP/3R2K1 w - - 0 1").unwrap();
+        assert_eq!(puzzle.solution[0], Move::from_str("b5c7").unwrap());
+        assert!(puzzle.themes.contains(&Theme::Fork));
+        assert_eq!(puzzle.mate_in(), None);
+    }
+
+    #[test]
+    fn skewer() {
+        let puzzle = puzzle_at("8/8/8/2k4q/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(puzzle.solution[0], Move::from_str("a1a5").unwrap());
+        assert!(puzzle.themes.contains(&Theme::Skewer));
+    }
+
+    #[test]
+    fn hanging_piece() {
+        let puzzle = puzzle_at("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        assert_eq!(puzzle.solution[0], Move::from_str("d1d5").unwrap());
+        assert!(puzzle.themes.contains(&Theme::HangingPiece));
+        assert_eq!(Theme::from_str("hangingPiece"), Ok(Theme::HangingPiece));
+    }
+
+    #[test]
+    fn back_rank_mate() {
+        let puzzle = puzzle_at("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        assert_eq!(puzzle.solution, vec![Move::from_str("a1a8").unwrap()]);
+        assert_eq!(puzzle.themes, vec![Theme::Mate, Theme::BackRankMate]);
+        assert_eq!(puzzle.mate_in(), Some(1));
+        assert_eq!(
+            puzzle.to_string(),
+            format!(
+                "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1,a1a8,{},mate backRankMate",
+                puzzle.difficulty
+            )
+        );
+    }
+
+    #[test]
+    fn no_unique_win() {
+        assert_eq!(
+            puzzle_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            None
+        );
+        // Both rooks mate
+        assert_eq!(puzzle_at("k7/8/1K6/8/8/8/6R1/7R w - - 0 1"), None);
+    }
+
+    #[test]
+    fn games() {
+        let game = &pgn::parse(
+            "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nd4 4. Nxe5 Qg5 5. Nxf7 Qxg2 6. Rf1 Qxe4+ 7. Be2 Nf3# 0-1",
+        )
+        .unwrap()[0];
+        let puzzles = PuzzleFinder::new(4).find_in_pgn(game).unwrap();
+        let smothered = puzzles
+            .iter()
+            .find(|puzzle| puzzle.themes.contains(&Theme::SmotheredMate))
+            .unwrap();
+        assert_eq!(smothered.solution, vec![Move::from_str("d4f3").unwrap()]);
+        assert!(smothered.fen.ends_with(" b Qkq - 0 1"));
+    }
+}
@@ -0,0 +1,37 @@
+use std::fs;
+
+use ai::{
+    analysis::{Analyser, DEFAULT_ANALYSIS_DEPTH},
+    pgn,
+    search::SearchLimits,
+};
+
+/// `analyse <pgn file> [depth]`, prints the report of every game of the file
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (path, depth) = match args {
+        [path] => (path, DEFAULT_ANALYSIS_DEPTH),
+        [path, depth] => (
+            path,
+            depth
+                .parse()
+                .map_err(|_| format!("Invalid depth: {}", depth))?,
+        ),
+        _ => return Err("Usage: cli analyse <pgn file> [depth]".to_string()),
+    };
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let games = pgn::parse(&text).map_err(|error| error.to_string())?;
+
+    let mut analyser = Analyser::new(SearchLimits::depth(depth));
+    for game in &games {
+        println!(
+            "{} - {}",
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?")
+        );
+        let analysis = analyser
+            .analyse_pgn(game)
+            .map_err(|error| error.to_string())?;
+        println!("{}", analysis);
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use engine::{
+    board::Board,
+    piece::{position::Position, Type},
+    player::Player,
+    tactics::Motif,
+};
+
+/// Reads moves from the terminal, in the format 'a2 a3'. 'hint' lists the tactical patterns of
+/// the position and 'resign' gives up
+pub struct HumanPlayer;
+
+impl Player for HumanPlayer {
+    fn choose_move(&mut self, board: &Board) -> Option<(Position, Position)> {
+        loop {
+            let input = get_input()?;
+            match input.trim() {
+                "resign" => return None,
+                "hint" => {
+                    print_hints(board);
+                    continue;
+                }
+                _ => {}
+            }
+            match input_to_moves(input.trim()) {
+                Some(moves) => return Some(moves),
+                None => println!("Invalid input"),
+            }
+        }
+    }
+
+    fn choose_promotion(&mut self, _board: &Board) -> Type {
+        loop {
+            println!("Promote a piece");
+            println!("Options: Q, R, B, K");
+            // A closed input can't choose, the queen is the usual choice
+            let input = match get_input() {
+                Some(input) => input,
+                None => return Type::Queen,
+            };
+            match input.trim() {
+                "Q" => return Type::Queen,
+                "R" => return Type::Rook,
+                "B" => return Type::Bishop,
+                "K" => return Type::Knight,
+                _ => println!("Invalid input"),
+            }
+        }
+    }
+
+    fn rejected(&mut self, error: &dyn Error) {
+        println!("{}", error);
+    }
+}
+
+/// Reads a line, None once the input is closed
+fn get_input() -> Option<String> {
+    print!("> ");
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input),
+    }
+}
+
+fn input_to_moves(input: &str) -> Option<(Position, Position)> {
+    let (from, to) = input.split_once(' ')?;
+    match (Position::from_str(from), Position::from_str(to.trim())) {
+        (Ok(from), Ok(to)) => Some((from, to)),
+        _ => None,
+    }
+}
+
+/// The patterns the side to move can use, then the ones the opponent threatens
+fn print_hints(board: &Board) {
+    let turn = board.get_turn();
+    for (title, color) in [("Your chances", turn), ("Threats", turn.opponent())] {
+        let motifs = board.motifs(color);
+        if motifs.is_empty() {
+            println!("{}: none", title);
+            continue;
+        }
+        println!("{}:", title);
+        for motif in motifs {
+            println!("  {}", describe(&motif));
+        }
+    }
+}
+
+fn describe(motif: &Motif) -> String {
+    let join = |squares: &[Position]| {
+        let names: Vec<String> = squares.iter().map(|square| square.to_string()).collect();
+        names.join(", ")
+    };
+    match motif {
+        Motif::Pin(pin) => format!(
+            "{} pins {} to {}{}",
+            pin.pinner,
+            pin.pinned,
+            pin.target,
+            if pin.absolute { " (absolute)" } else { "" }
+        ),
+        Motif::Skewer(skewer) => format!(
+            "{} skewers {} and {}",
+            skewer.attacker, skewer.front, skewer.behind
+        ),
+        Motif::Fork(fork) => format!("{} forks {}", fork.attacker, join(&fork.targets)),
+        Motif::DiscoveredAttack(discovered) => format!(
+            "moving {} uncovers {} on {}",
+            discovered.blocker, discovered.attacker, discovered.target
+        ),
+        Motif::HangingPiece(hanging) => format!(
+            "{} hangs, attacked from {}",
+            hanging.square,
+            join(&hanging.attackers)
+        ),
+        Motif::OverloadedDefender(overloaded) => format!(
+            "{} alone defends {}",
+            overloaded.defender,
+            join(&overloaded.defended)
+        ),
+        Motif::BackRankWeakness(weakness) => {
+            format!("the king on {} is weak on its back rank", weakness.king)
+        }
+    }
+}
@@ -1,103 +1,96 @@
-use std::{
-    io::{self, Write},
-    str::FromStr,
-};
+use std::{env, process};
 
-use engine::{
-    board::Board,
-    piece::{position::Position, ChessPiece, Color},
+use ai::{
+    player::{AiPlayer, RandomPlayer},
+    strength::Level,
+};
+use engine::player::{
+    runner::{GameRunner, Outcome},
+    Player,
 };
+use human::HumanPlayer;
+
+mod analyse;
+mod human;
+mod solve;
 
 fn main() {
-    println!("Welcome to Rust Chess!");
-    println!("Please enter a move in the format: 'a2 a3'");
-    let mut board = Board::new();
-    println!("{}", board);
-    while board.get_winner().is_none() {
-        let turn = board.get_turn();
-        let promotion = board.get_promotion();
-        if promotion.is_some() {
-            match turn {
-                Color::White => promote_piece(&mut board),
-                Color::Black => {
-                    let choice = ai::make_promotion(&board);
-                    board.promote(choice).unwrap();
-                }
-            }
-        } else {
-            match turn {
-                Color::White => move_piece(&mut board),
-                Color::Black => {
-                    let (from, to) = ai::make_move(&board);
-                    board.move_piece(from, to).unwrap();
-                }
-            }
+    let args: Vec<String> = env::args().collect();
+    let result = match args.get(1).map(String::as_str) {
+        Some("solve") => Some(solve::run(&args[2..])),
+        Some("analyse") => Some(analyse::run(&args[2..])),
+        _ => None,
+    };
+    if let Some(result) = result {
+        if let Err(error) = result {
+            println!("{}", error);
         }
-
-        println!("{}", board);
+        return;
     }
 
-    let winner = board.get_winner().unwrap();
-
-    println!("{} wins!", winner);
-}
-
-fn promote_piece(board: &mut Board) {
-    println!("Promote a piece");
-    println!("Options: Q, R, B, K");
-    let input = get_input();
-    let turn = board.get_turn();
-
-    let piece = match input.trim() {
-        "Q" => ChessPiece::create_queen(turn),
-        "R" => ChessPiece::create_rook(turn),
-        "B" => ChessPiece::create_bishop(turn),
-        "K" => ChessPiece::create_knight(turn),
-        _ => {
-            println!("Invalid input");
-            return;
+    let seed = match seed_from_args() {
+        Ok(seed) => seed,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
         }
     };
-    board.promote(piece).unwrap();
-}
-
-fn move_piece(board: &mut Board) {
-    let input = get_input();
-    let moves: Vec<&str> = input.trim().split(" ").collect();
-    if moves.len() != 2 {
-        println!("Invalid input");
-        return;
-    }
-    let moves = input_to_moves(moves);
-    let (from, to) = match moves {
-        Some(moves) => moves,
-        None => {
-            println!("Invalid input");
+    let white = player_from_args("--white", "human", seed);
+    let black = player_from_args("--black", "random", seed.wrapping_add(1));
+    let (mut white, mut black) = match (white, black) {
+        (Ok(white), Ok(black)) => (white, black),
+        (Err(error), _) | (_, Err(error)) => {
+            println!("{}", error);
+            println!("Players are 'human', 'random' or an AI level from 1 to 10");
             return;
         }
     };
-    let moved = board.move_piece(from, to);
-    match moved {
-        Ok(_) => {}
-        Err(e) => {
-            println!("{}", e);
-        }
+
+    println!("Welcome to Rust Chess!");
+    println!("AI seed: {} (replay this game with --seed {})", seed, seed);
+    println!("Please enter a move in the format: 'a2 a3', 'hint' or 'resign'");
+    let mut runner = GameRunner::new();
+    println!("{}", runner.get_game().get_board());
+    let outcome = runner.run(white.as_mut(), black.as_mut(), |game| {
+        println!("{}", game.get_board())
+    });
+
+    match outcome {
+        Outcome::Checkmate { winner } => println!("{} wins!", winner),
+        Outcome::Resignation { winner } => println!("{} wins by resignation!", winner),
+        Outcome::Draw(reason) => println!("{}, it's a draw!", reason),
     }
 }
 
-fn get_input() -> String {
-    print!("> ");
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    input
+/// Reads the AI seed from `--seed <number>`, a random one without the flag
+fn seed_from_args() -> Result<u64, String> {
+    match arg_value("--seed") {
+        Some(seed) => seed
+            .parse()
+            .map_err(|_| format!("Invalid seed: {}, use a number", seed)),
+        None if env::args().any(|arg| arg == "--seed") => Err("Missing seed after --seed".into()),
+        None => Ok(rand::random()),
+    }
 }
 
-fn input_to_moves(input: Vec<&str>) -> Option<(Position, Position)> {
-    let from = Position::from_str(input[0]);
-    let to = Position::from_str(input[1]);
-    match (from, to) {
-        (Ok(from), Ok(to)) => Some((from, to)),
-        _ => None,
+/// Reads a player from `--white <player>` or `--black <player>`
+fn player_from_args(flag: &str, default: &str, seed: u64) -> Result<Box<dyn Player>, String> {
+    let player = arg_value(flag).unwrap_or_else(|| default.to_string());
+    match player.as_str() {
+        "human" => Ok(Box::new(HumanPlayer)),
+        "random" => Ok(Box::new(RandomPlayer::new(ai::seeded_rng(seed)))),
+        level => {
+            let level = level
+                .parse()
+                .map_err(|_| format!("Invalid player: {}", level))
+                .and_then(|level| Level::new(level).map_err(|error| error.to_string()))?;
+            Ok(Box::new(AiPlayer::new(level, ai::seeded_rng(seed))))
+        }
     }
 }
+
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.get(index + 1).cloned()
+}
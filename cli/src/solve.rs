@@ -0,0 +1,48 @@
+use ai::solver::MateSolver;
+use engine::board::Board;
+
+/// `solve <moves> <fen>`, checks that the side to move mates in that many moves and prints the
+/// solution with its cooks, duals and short solutions
+pub fn run(args: &[String]) -> Result<(), String> {
+    let [moves, fen] = args else {
+        return Err("Usage: cli solve <moves> \"<fen>\"".to_string());
+    };
+    let moves: u32 = moves
+        .parse()
+        .map_err(|_| format!("Invalid number of moves: {}", moves))?;
+    let board = Board::from_fen(fen).map_err(|error| error.to_string())?;
+
+    let mut solver = MateSolver::new();
+    let solution = solver.solve(&board, moves);
+    if !solution.is_proven() {
+        println!("No mate in {}", moves);
+        for (movement, refutation) in &solution.refutations {
+            match refutation {
+                Some(refutation) => println!("  {} is refuted by {}", movement, refutation),
+                None => println!("  {} doesn't mate", movement),
+            }
+        }
+        return Ok(());
+    }
+
+    println!("Mate in {} ({} positions searched)", moves, solver.nodes());
+    if solution.keys.len() > 1 {
+        println!("Cooked: {} keys", solution.keys.len());
+    }
+    if let Some(shortest) = solution.shortest.filter(|shortest| *shortest < moves) {
+        println!("Short solution: mate in {}", shortest);
+    }
+    for key in &solution.keys {
+        println!();
+        print!("{}", key);
+        for dual in key.duals() {
+            let line: Vec<String> = dual.iter().map(|movement| movement.to_string()).collect();
+            println!("Dual after {}", line.join(" "));
+        }
+    }
+    if solution.is_sound() {
+        println!();
+        println!("Sound: one key, no duals and no shorter mate");
+    }
+    Ok(())
+}
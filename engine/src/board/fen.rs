@@ -0,0 +1,314 @@
+use std::str::FromStr;
+
+use crate::{
+    piece::{position::Position, ChessPiece, Color, Type},
+    result::{FenError, OkMovement},
+};
+
+use super::Board;
+
+/// Forsyth-Edwards Notation of the initial position
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// (castling right, king square, rook square)
+const CASTLING_RIGHTS: [(char, Position, Position); 4] = [
+    ('K', Position { x: 4, y: 0 }, Position { x: 7, y: 0 }),
+    ('Q', Position { x: 4, y: 0 }, Position { x: 0, y: 0 }),
+    ('k', Position { x: 4, y: 7 }, Position { x: 7, y: 7 }),
+    ('q', Position { x: 4, y: 7 }, Position { x: 0, y: 7 }),
+];
+
+impl Board {
+    /// Creates a board from a FEN string.
+    /// The halfmove clock and fullmove number are validated but not stored in the board
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(FenError::MissingField)?;
+        let turn = fields.next().ok_or(FenError::MissingField)?;
+        let castling = fields.next().unwrap_or("-");
+        let en_passant = fields.next().unwrap_or("-");
+        for clock in fields.take(2) {
+            clock.parse::<u32>().map_err(|_| FenError::InvalidClock)?;
+        }
+
+        let mut pieces = parse_placement(placement)?;
+
+        let turn = match turn {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidTurn),
+        };
+
+        apply_castling_rights(&mut pieces, castling)?;
+
+        let last_move = parse_en_passant(&pieces, en_passant, turn)?;
+
+        let white_king_position = find_king(&pieces, Color::White)?;
+        let black_king_position = find_king(&pieces, Color::Black)?;
+
+        let mut board = Board {
+            turn,
+            pieces,
+            winner: None,
+            check: None,
+            last_move,
+            promotion: None,
+            white_king_position,
+            black_king_position,
+        };
+
+        let enemy = board.next_turn();
+        // The side that just moved can't be in check
+        if board.is_king_in_check(enemy) {
+            return Err(FenError::InvalidPlacement);
+        }
+
+        if board.is_king_in_check(turn) {
+            board.check = Some(turn);
+            if board.is_checkmate(turn) {
+                board.winner = Some(enemy);
+            }
+        }
+
+        Ok(board)
+    }
+
+    /// Returns the FEN of the board, the halfmove clock and the fullmove number are always `0 1`
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for y in (0..8).rev() {
+            let mut empty = 0;
+            for x in 0..8 {
+                match self.get_piece_at(&Position { x, y }) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece_to_char(piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if y > 0 {
+                placement.push('/');
+            }
+        }
+
+        let turn = match self.turn {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let castling: String = CASTLING_RIGHTS
+            .iter()
+            .zip(self.castling_rights())
+            .filter(|(_, right)| *right)
+            .map(|((right, _, _), _)| *right)
+            .collect();
+        let castling = if castling.is_empty() {
+            "-".to_string()
+        } else {
+            castling
+        };
+
+        let en_passant = match self.last_move {
+            Some(Ok(OkMovement::InitialDoubleAdvance((from, to)))) => Position {
+                x: from.x,
+                y: (from.y + to.y) / 2,
+            }
+            .to_string(),
+            _ => "-".to_string(),
+        };
+
+        format!("{} {} {} {} 0 1", placement, turn, castling, en_passant)
+    }
+
+    /// Castling rights in FEN order: white king side, white queen side, black king side
+    /// and black queen side
+    pub(crate) fn castling_rights(&self) -> [bool; 4] {
+        CASTLING_RIGHTS.map(|(_, king, rook)| self.has_castling_right(&king, &rook))
+    }
+
+    fn has_castling_right(&self, king: &Position, rook: &Position) -> bool {
+        let king = self.get_piece_at(king);
+        let rook = self.get_piece_at(rook);
+        match (king, rook) {
+            (Some(king), Some(rook)) => {
+                king.get_type() == Type::King
+                    && rook.get_type() == Type::Rook
+                    && king.get_color() == rook.get_color()
+                    && !king.moved
+                    && !rook.moved
+            }
+            _ => false,
+        }
+    }
+}
+
+fn parse_placement(placement: &str) -> Result<[[Option<ChessPiece>; 8]; 8], FenError> {
+    let mut pieces = [[None; 8]; 8];
+    let rows: Vec<&str> = placement.split('/').collect();
+    if rows.len() != 8 {
+        return Err(FenError::InvalidPlacement);
+    }
+
+    // The first row of the FEN is the 8th rank
+    for (row, y) in rows.iter().zip((0..8).rev()) {
+        let mut x = 0;
+        for c in row.chars() {
+            if let Some(empty) = c.to_digit(10) {
+                x += empty as usize;
+                continue;
+            }
+            if x >= 8 {
+                return Err(FenError::InvalidPlacement);
+            }
+            let mut piece = char_to_piece(c).ok_or(FenError::InvalidPlacement)?;
+            // A pawn out of its initial rank has moved, kings and rooks are handled by the castling rights
+            piece.moved = match (piece.get_type(), piece.get_color()) {
+                (Type::Pawn, Color::White) => y != 1,
+                (Type::Pawn, Color::Black) => y != 6,
+                (Type::King, _) | (Type::Rook, _) => true,
+                _ => false,
+            };
+            pieces[y][x] = Some(piece);
+            x += 1;
+        }
+        if x != 8 {
+            return Err(FenError::InvalidPlacement);
+        }
+    }
+
+    Ok(pieces)
+}
+
+/// Kings and rooks are created as moved, castling rights mark them as unmoved
+fn apply_castling_rights(
+    pieces: &mut [[Option<ChessPiece>; 8]; 8],
+    castling: &str,
+) -> Result<(), FenError> {
+    if castling == "-" {
+        return Ok(());
+    }
+
+    for right in castling.chars() {
+        let (_, king, rook) = CASTLING_RIGHTS
+            .iter()
+            .find(|(c, _, _)| *c == right)
+            .ok_or(FenError::InvalidCastling)?;
+        let color = if right.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        for (position, piece_type) in [(king, Type::King), (rook, Type::Rook)] {
+            let piece = &mut pieces[position.y as usize][position.x as usize];
+            match piece {
+                Some(piece) if piece.get_type() == piece_type && piece.get_color() == color => {
+                    piece.moved = false;
+                }
+                _ => return Err(FenError::InvalidCastling),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The board knows about en passant through the last move, so a double advance is recreated
+fn parse_en_passant(
+    pieces: &[[Option<ChessPiece>; 8]; 8],
+    en_passant: &str,
+    turn: Color,
+) -> Result<Option<crate::result::Movement>, FenError> {
+    if en_passant == "-" {
+        return Ok(None);
+    }
+
+    let target = Position::from_str(en_passant).map_err(|_| FenError::InvalidEnPassant)?;
+    let (from_y, to_y, expected_y) = match turn {
+        Color::White => (6, 4, 5),
+        Color::Black => (1, 3, 2),
+    };
+    if target.y != expected_y {
+        return Err(FenError::InvalidEnPassant);
+    }
+
+    let pawn = pieces[to_y as usize][target.x as usize];
+    let is_enemy_pawn = match pawn {
+        Some(pawn) => pawn.get_type() == Type::Pawn && pawn.get_color() != turn,
+        None => false,
+    };
+    if !is_enemy_pawn {
+        return Err(FenError::InvalidEnPassant);
+    }
+
+    let from = Position {
+        x: target.x,
+        y: from_y,
+    };
+    let to = Position {
+        x: target.x,
+        y: to_y,
+    };
+    Ok(Some(Ok(OkMovement::InitialDoubleAdvance((from, to)))))
+}
+
+fn find_king(pieces: &[[Option<ChessPiece>; 8]; 8], color: Color) -> Result<Position, FenError> {
+    let mut kings = Vec::new();
+    for (y, row) in pieces.iter().enumerate() {
+        for (x, piece) in row.iter().enumerate() {
+            if let Some(piece) = piece {
+                if piece.get_type() == Type::King && piece.get_color() == color {
+                    kings.push(Position {
+                        x: x as i32,
+                        y: y as i32,
+                    });
+                }
+            }
+        }
+    }
+
+    match kings[..] {
+        [king] => Ok(king),
+        _ => Err(FenError::InvalidKings),
+    }
+}
+
+fn char_to_piece(c: char) -> Option<ChessPiece> {
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let piece_type = match c.to_ascii_lowercase() {
+        'p' => Type::Pawn,
+        'n' => Type::Knight,
+        'b' => Type::Bishop,
+        'r' => Type::Rook,
+        'q' => Type::Queen,
+        'k' => Type::King,
+        _ => return None,
+    };
+    Some(ChessPiece::new(piece_type, color))
+}
+
+fn piece_to_char(piece: &ChessPiece) -> char {
+    let c = match piece.get_type() {
+        Type::Pawn => 'p',
+        Type::Knight => 'n',
+        Type::Bishop => 'b',
+        Type::Rook => 'r',
+        Type::Queen => 'q',
+        Type::King => 'k',
+    };
+    match piece.get_color() {
+        Color::White => c.to_ascii_uppercase(),
+        Color::Black => c,
+    }
+}
@@ -5,6 +5,8 @@ use crate::{
     result::{Movement, MovementError, OkMovement, PromotionError},
 };
 
+pub mod fen;
+
 #[cfg(test)]
 mod test;
 
@@ -178,6 +180,14 @@ impl Board {
         moves
     }
 
+    /// Passes the turn to the other player without moving any piece.
+    /// This is not a legal chess move, it only exists so search algorithms can
+    /// measure how strong a position is when the side to move does nothing (null move pruning)
+    pub fn make_null_move(&mut self) {
+        self.last_move = None;
+        self.change_turn();
+    }
+
     pub fn promote(&mut self, piece: ChessPiece) -> Result<(Position, Type), PromotionError> {
         let piece_type = piece.get_type();
         // can't promote to a pawn or king
@@ -189,12 +199,13 @@ impl Board {
             self.pieces[position.y as usize][position.x as usize] = Some(piece);
             self.promotion = None;
             self.change_turn();
-            let enemy_color = self.next_turn();
+            // The turn already passed to the enemy
+            let enemy_color = self.turn;
             if self.is_king_in_check(enemy_color) {
                 self.check = Some(enemy_color);
                 let is_checkmate = self.is_checkmate(enemy_color);
                 if is_checkmate {
-                    self.winner = Some(self.turn);
+                    self.winner = Some(self.next_turn());
                 }
             } else {
                 self.check = None;
@@ -502,10 +513,7 @@ impl Board {
     }
 
     fn next_turn(&self) -> Color {
-        match self.turn {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        }
+        self.turn.opponent()
     }
 
     fn initial_pieces_setup() -> [[Option<ChessPiece>; 8]; 8] {
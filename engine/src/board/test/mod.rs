@@ -1276,3 +1276,135 @@ fn test_castling_capturing_bishop() {
 
     assert!(!board.move_piece(from, to).is_ok());
 }
+
+#[test]
+fn test_starting_fen() {
+    let board = Board::from_fen(crate::board::fen::STARTING_FEN).unwrap();
+    assert!(board == Board::new());
+    assert_eq!(board.to_fen(), crate::board::fen::STARTING_FEN);
+}
+
+#[test]
+fn test_fen_round_trip() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 1",
+        "4k3/8/8/8/8/8/8/4K2R b K - 0 1",
+    ];
+    for fen in fens {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.to_fen(), fen);
+    }
+}
+
+#[test]
+fn test_fen_en_passant() {
+    let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+    let mut board = Board::from_fen(fen).unwrap();
+
+    let from = Position::from_str("e5").unwrap();
+    let to = Position::from_str("f6").unwrap();
+    assert_eq!(
+        board.move_piece(from, to),
+        Ok(OkMovement::EnPassant((from, to)))
+    );
+
+    let from = Position::from_str("d5").unwrap();
+    let to = Position::from_str("d4").unwrap();
+    assert!(board.move_piece(from, to).is_ok());
+}
+
+#[test]
+fn test_fen_castling_rights() {
+    let mut board = Board::from_fen("4k3/8/8/8/8/8/8/R3K2R w Q - 0 1").unwrap();
+    let king = Position::from_str("e1").unwrap();
+
+    let kingside = Position::from_str("g1").unwrap();
+    assert!(board.move_piece(king, kingside).is_err());
+
+    let queenside = Position::from_str("c1").unwrap();
+    assert!(board.move_piece(king, queenside).is_ok());
+}
+
+#[test]
+fn test_fen_check_and_checkmate() {
+    let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1").unwrap();
+    assert_eq!(board.get_check(), None);
+
+    let board = Board::from_fen("R3k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(board.get_check(), Some(Color::Black));
+    assert_eq!(board.get_winner(), None);
+
+    let board = Board::from_fen("R3k3/7R/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(board.get_winner(), Some(Color::White));
+}
+
+#[test]
+fn test_invalid_fen() {
+    use crate::result::FenError;
+
+    assert_eq!(Board::from_fen("").err(), Some(FenError::MissingField));
+    assert_eq!(
+        Board::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").err(),
+        Some(FenError::InvalidKings)
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1").err(),
+        Some(FenError::InvalidTurn)
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").err(),
+        Some(FenError::InvalidCastling)
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - e3 0 1").err(),
+        Some(FenError::InvalidEnPassant)
+    );
+    assert_eq!(
+        Board::from_fen("4k3/8/8/8/8/8/8/4K4 w - - 0 1").err(),
+        Some(FenError::InvalidPlacement)
+    );
+    // The side that is not moving is in check
+    assert_eq!(
+        Board::from_fen("R3k3/8/8/8/8/8/8/4K3 w - - 0 1").err(),
+        Some(FenError::InvalidPlacement)
+    );
+}
+
+#[test]
+fn test_promotion_check() {
+    let mut board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let from = Position::from_str("a7").unwrap();
+    let to = Position::from_str("a8").unwrap();
+    board.move_piece(from, to).unwrap();
+    assert_eq!(board.get_promotion(), Some(to));
+
+    board
+        .promote(ChessPiece::create_queen(Color::White))
+        .unwrap();
+    assert_eq!(board.get_turn(), Color::Black);
+    assert_eq!(board.get_check(), Some(Color::Black));
+    assert_eq!(board.get_winner(), None);
+}
+
+#[test]
+fn test_promotion_checkmate() {
+    let mut board = Board::from_fen("4kb2/P2ppppp/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let from = Position::from_str("a7").unwrap();
+    let to = Position::from_str("a8").unwrap();
+    board.move_piece(from, to).unwrap();
+
+    let mut knight = board;
+    knight
+        .promote(ChessPiece::create_knight(Color::White))
+        .unwrap();
+    assert_eq!(knight.get_check(), None);
+    assert_eq!(knight.get_winner(), None);
+
+    board
+        .promote(ChessPiece::create_rook(Color::White))
+        .unwrap();
+    assert_eq!(board.get_check(), Some(Color::Black));
+    assert_eq!(board.get_winner(), Some(Color::White));
+}
@@ -0,0 +1,316 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::{
+    board::Board,
+    piece::{position::Position, ChessPiece, Color, Type},
+    result::{FenError, MovementError, OkMovement, PromotionError},
+};
+
+#[cfg(test)]
+mod test;
+
+/// A position is drawn once the same player can only make this many moves without
+/// moving a pawn or capturing
+const FIFTY_MOVE_RULE_PLIES: u32 = 100;
+const THREEFOLD_REPETITION: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DrawReason {
+    Stalemate,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    /// Both players agreed, the rules never draw this way on their own
+    Agreement,
+}
+
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            DrawReason::Stalemate => "Stalemate",
+            DrawReason::InsufficientMaterial => "Insufficient material",
+            DrawReason::ThreefoldRepetition => "Threefold repetition",
+            DrawReason::FiftyMoveRule => "Fifty-move rule",
+            DrawReason::Agreement => "Agreement",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Snapshot {
+    board: Board,
+    halfmove_clock: u32,
+}
+
+/// A board plus everything the board alone can't know: the positions already played,
+/// so moves can be taken back and repetitions found, and the fifty-move rule clock
+#[derive(Clone)]
+pub struct Game {
+    board: Board,
+    history: Vec<Snapshot>,
+    halfmove_clock: u32,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Game {
+        Game::from_board(Board::new())
+    }
+
+    pub fn from_board(board: Board) -> Game {
+        Game {
+            board,
+            history: Vec::new(),
+            halfmove_clock: 0,
+        }
+    }
+
+    /// Creates a game from a FEN string, keeping its halfmove clock
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        let board = Board::from_fen(fen)?;
+        let halfmove_clock = match fen.split_whitespace().nth(4) {
+            Some(clock) => clock.parse().map_err(|_| FenError::InvalidClock)?,
+            None => 0,
+        };
+        Ok(Game {
+            board,
+            history: Vec::new(),
+            halfmove_clock,
+        })
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Positions before every move of the game, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &Board> {
+        self.history.iter().map(|snapshot| &snapshot.board)
+    }
+
+    /// Number of moves played since the last capture or pawn move
+    pub fn get_halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn move_piece(
+        &mut self,
+        from: Position,
+        to: Position,
+    ) -> Result<OkMovement, MovementError> {
+        // Stalemated players have no legal move anyway
+        if self.is_insufficient_material() {
+            return Err(MovementError::GameIsOver);
+        }
+
+        let snapshot = Snapshot {
+            board: self.board,
+            halfmove_clock: self.halfmove_clock,
+        };
+        let pawn_moved = self
+            .board
+            .get_piece_at(&from)
+            .is_some_and(|piece| piece.get_type() == Type::Pawn);
+
+        let movement = self.board.move_piece(from, to)?;
+
+        let capture = matches!(movement, OkMovement::Capture(_) | OkMovement::EnPassant(_));
+        self.halfmove_clock = if pawn_moved || capture {
+            0
+        } else {
+            self.halfmove_clock + 1
+        };
+        self.history.push(snapshot);
+
+        Ok(movement)
+    }
+
+    pub fn promote(&mut self, piece: ChessPiece) -> Result<(Position, Type), PromotionError> {
+        self.board.promote(piece)
+    }
+
+    pub fn resign(&mut self) {
+        self.board.resign();
+    }
+
+    /// Takes back the last move (with its promotion), returns false if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(snapshot) => {
+                self.board = snapshot.board;
+                self.halfmove_clock = snapshot.halfmove_clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How many times the current position happened in the game, counting the current one.
+    /// Positions are the same when the same pieces are on the same squares, with the same
+    /// player to move, castling rights and en passant capture
+    pub fn repetitions(&self) -> usize {
+        if self.board.get_promotion().is_some() {
+            return 1;
+        }
+
+        let current = PositionKey::new(&self.board);
+        // A capture or a pawn move can't be undone, positions before them can't repeat
+        let reversible = (self.halfmove_clock as usize).min(self.history.len());
+        let previous = self.history[self.history.len() - reversible..]
+            .iter()
+            .filter(|snapshot| PositionKey::new(&snapshot.board) == current)
+            .count();
+
+        previous + 1
+    }
+
+    /// The player to move has no legal move and is not in check
+    pub fn is_stalemate(&self) -> bool {
+        let board = &self.board;
+        board.get_winner().is_none()
+            && board.get_promotion().is_none()
+            && board.get_check() != Some(board.get_turn())
+            && board.legal_moves().is_empty()
+    }
+
+    /// Neither player has enough pieces left to checkmate: king against king, king and a
+    /// single minor piece against king, or kings and bishops all on squares of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishops = 0;
+        let mut bishop_square_colors = [false; 2];
+        for (y, row) in self.board.get_pieces().iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                let piece_type = match piece {
+                    Some(piece) => piece.get_type(),
+                    None => continue,
+                };
+                match piece_type {
+                    Type::King => {}
+                    Type::Knight => knights += 1,
+                    Type::Bishop => {
+                        bishops += 1;
+                        bishop_square_colors[(x + y) % 2] = true;
+                    }
+                    Type::Pawn | Type::Rook | Type::Queen => return false,
+                }
+            }
+        }
+
+        let same_color_bishops = !(bishop_square_colors[0] && bishop_square_colors[1]);
+        knights + bishops <= 1 || (knights == 0 && same_color_bishops)
+    }
+
+    /// The player could still checkmate with some series of legal moves: a pawn, a rook or a
+    /// queen, two minor pieces (bishops on squares of the same color count as one), or a
+    /// single minor piece when the opponent has pieces of its own to block its king in. Decides
+    /// whether running out of time loses or draws
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let mut knights = 0;
+        let mut bishop_square_colors = [false; 2];
+        let mut opponent_pieces = false;
+        for (y, row) in self.board.get_pieces().iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                let Some(piece) = piece else {
+                    continue;
+                };
+                if piece.get_color() != color {
+                    opponent_pieces |= piece.get_type() != Type::King;
+                    continue;
+                }
+                match piece.get_type() {
+                    Type::King => {}
+                    Type::Knight => knights += 1,
+                    Type::Bishop => bishop_square_colors[(x + y) % 2] = true,
+                    Type::Pawn | Type::Rook | Type::Queen => return true,
+                }
+            }
+        }
+
+        let minors = knights + bishop_square_colors.iter().filter(|color| **color).count();
+        minors >= 2 || (minors == 1 && opponent_pieces)
+    }
+
+    /// Draws that end the game on their own
+    pub fn draw(&self) -> Option<DrawReason> {
+        if self.board.get_winner().is_some() {
+            return None;
+        }
+        if self.is_stalemate() {
+            return Some(DrawReason::Stalemate);
+        }
+        if self.is_insufficient_material() {
+            return Some(DrawReason::InsufficientMaterial);
+        }
+        None
+    }
+
+    /// Draws that a player can claim, the game goes on if nobody does
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.board.get_winner().is_some() || self.board.get_promotion().is_some() {
+            return None;
+        }
+        if self.repetitions() >= THREEFOLD_REPETITION {
+            return Some(DrawReason::ThreefoldRepetition);
+        }
+        if self.halfmove_clock >= FIFTY_MOVE_RULE_PLIES {
+            return Some(DrawReason::FiftyMoveRule);
+        }
+        None
+    }
+
+    /// The game ended by checkmate or by a draw that doesn't need to be claimed
+    pub fn is_over(&self) -> bool {
+        self.board.get_winner().is_some() || self.draw().is_some()
+    }
+}
+
+/// Everything that makes two positions the same for the repetition rule
+#[derive(PartialEq, Eq)]
+struct PositionKey {
+    pieces: [[Option<(Type, Color)>; 8]; 8],
+    turn: Color,
+    castling_rights: [bool; 4],
+    en_passant: Option<Position>,
+}
+
+impl PositionKey {
+    fn new(board: &Board) -> PositionKey {
+        let pieces = board
+            .get_pieces()
+            .map(|row| row.map(|piece| piece.map(|piece| (piece.get_type(), piece.get_color()))));
+        PositionKey {
+            pieces,
+            turn: board.get_turn(),
+            castling_rights: board.castling_rights(),
+            en_passant: en_passant(board),
+        }
+    }
+}
+
+/// The pawn that can be taken en passant, only when an enemy pawn stands next to it
+fn en_passant(board: &Board) -> Option<Position> {
+    let to = match board.get_last_move() {
+        Some(Ok(OkMovement::InitialDoubleAdvance((_, to)))) => to,
+        _ => return None,
+    };
+    let capturer = [-1, 1].into_iter().any(|dx| {
+        let position = Position {
+            x: to.x + dx,
+            y: to.y,
+        };
+        board.get_piece_at(&position).is_some_and(|piece| {
+            piece.get_type() == Type::Pawn && piece.get_color() == board.get_turn()
+        })
+    });
+    capturer.then_some(to)
+}
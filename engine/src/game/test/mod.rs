@@ -0,0 +1,167 @@
+use std::str::FromStr;
+
+use crate::{
+    game::{DrawReason, Game},
+    piece::{position::Position, ChessPiece, Color},
+    result::MovementError,
+};
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for movement in moves {
+        let (from, to) = movement.split_once(' ').unwrap();
+        let from = Position::from_str(from).unwrap();
+        let to = Position::from_str(to).unwrap();
+        game.move_piece(from, to).unwrap();
+    }
+}
+
+#[test]
+fn test_threefold_repetition() {
+    let mut game = Game::new();
+    let knight_dance = ["g1 f3", "g8 f6", "f3 g1", "f6 g8"];
+
+    play(&mut game, &knight_dance);
+    assert_eq!(game.repetitions(), 2);
+    assert_eq!(game.claimable_draw(), None);
+
+    play(&mut game, &knight_dance);
+    assert_eq!(game.repetitions(), 3);
+    assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+    // Repetitions have to be claimed
+    assert!(!game.is_over());
+}
+
+#[test]
+fn test_repetition_needs_same_castling_rights() {
+    let mut game = Game::new();
+    play(
+        &mut game,
+        &["e2 e4", "e7 e5", "e1 e2", "e8 e7", "e2 e1", "e7 e8"],
+    );
+    play(&mut game, &["e1 e2", "e8 e7", "e2 e1", "e7 e8"]);
+    // The first time the kings were on e1 and e8 castling was still possible
+    assert_eq!(game.repetitions(), 2);
+}
+
+#[test]
+fn test_fifty_move_rule() {
+    let mut game = Game::from_fen("8/8/4k3/8/8/3K4/8/R7 w - - 98 60").unwrap();
+    assert_eq!(game.get_halfmove_clock(), 98);
+
+    play(&mut game, &["a1 a2", "e6 e5"]);
+    assert_eq!(game.get_halfmove_clock(), 100);
+    assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+}
+
+#[test]
+fn test_halfmove_clock_resets() {
+    let mut game = Game::new();
+    play(&mut game, &["g1 f3", "b8 c6"]);
+    assert_eq!(game.get_halfmove_clock(), 2);
+
+    play(&mut game, &["e2 e4"]);
+    assert_eq!(game.get_halfmove_clock(), 0);
+
+    play(&mut game, &["c6 d4", "f3 d4"]);
+    assert_eq!(game.get_halfmove_clock(), 0);
+}
+
+#[test]
+fn test_stalemate() {
+    let game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert!(game.is_stalemate());
+    assert_eq!(game.draw(), Some(DrawReason::Stalemate));
+    assert!(game.is_over());
+
+    let game = Game::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert!(!game.is_stalemate());
+    assert_eq!(game.draw(), None);
+}
+
+#[test]
+fn test_insufficient_material() {
+    let insufficient = [
+        "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/6N1 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/5B2 w - - 0 1",
+        "8/8/2b1k3/8/8/3K4/8/5B2 w - - 0 1",
+    ];
+    for fen in insufficient {
+        let game = Game::from_fen(fen).unwrap();
+        assert_eq!(
+            game.draw(),
+            Some(DrawReason::InsufficientMaterial),
+            "{}",
+            fen
+        );
+    }
+
+    let sufficient = [
+        "8/8/4k3/8/8/3K4/8/4BB2 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/4NB2 w - - 0 1",
+        "8/8/4k3/8/8/3K4/P7/8 w - - 0 1",
+        "8/8/4k3/8/8/3K4/8/7R w - - 0 1",
+    ];
+    for fen in sufficient {
+        let game = Game::from_fen(fen).unwrap();
+        assert_eq!(game.draw(), None, "{}", fen);
+    }
+}
+
+#[test]
+fn test_mating_material() {
+    let cases = [
+        ("8/8/4k3/8/8/3K4/8/8 w - - 0 1", false),
+        ("8/8/4k3/8/8/3K4/8/6N1 w - - 0 1", false),
+        ("8/8/4k3/8/8/3K4/8/4B1B1 w - - 0 1", false),
+        ("8/8/4k3/8/8/3K4/8/4BB2 w - - 0 1", true),
+        ("8/8/4k3/8/8/3K4/8/5NN1 w - - 0 1", true),
+        ("8/8/4k3/8/8/3K4/P7/8 w - - 0 1", true),
+        // The black pawn can block its own king
+        ("8/4p3/4k3/8/8/3K4/8/6N1 w - - 0 1", true),
+    ];
+    for (fen, mating) in cases {
+        let game = Game::from_fen(fen).unwrap();
+        assert_eq!(game.has_mating_material(Color::White), mating, "{}", fen);
+    }
+
+    let game = Game::from_fen("8/4p3/4k3/8/8/3K4/8/6N1 w - - 0 1").unwrap();
+    assert!(game.has_mating_material(Color::Black));
+}
+
+#[test]
+fn test_no_moves_after_a_dead_position() {
+    let mut game = Game::from_fen("8/8/4k3/8/8/3K4/8/8 w - - 0 1").unwrap();
+    let result = game.move_piece(
+        Position::from_str("d3").unwrap(),
+        Position::from_str("d4").unwrap(),
+    );
+    assert_eq!(result, Err(MovementError::GameIsOver));
+}
+
+#[test]
+fn test_undo() {
+    let mut game = Game::new();
+    assert!(!game.undo());
+
+    play(&mut game, &["g1 f3", "g8 f6", "f3 g1"]);
+    let fen = game.get_board().to_fen();
+    play(&mut game, &["e7 e5"]);
+
+    assert!(game.undo());
+    assert_eq!(game.get_board().to_fen(), fen);
+    assert_eq!(game.get_halfmove_clock(), 3);
+    assert_eq!(game.history().count(), 3);
+}
+
+#[test]
+fn test_undo_promotion() {
+    let mut game = Game::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let fen = game.get_board().to_fen();
+    play(&mut game, &["a7 a8"]);
+    game.promote(ChessPiece::create_queen(Color::White))
+        .unwrap();
+
+    assert!(game.undo());
+    assert_eq!(game.get_board().to_fen(), fen);
+}
@@ -1,3 +1,6 @@
 pub mod board;
+pub mod game;
 pub mod piece;
+pub mod player;
 pub mod result;
+pub mod tactics;
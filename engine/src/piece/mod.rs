@@ -15,6 +15,33 @@ pub mod position;
 #[cfg(test)]
 mod test;
 
+/// Jumps of the knight, as (file, rank) offsets
+pub(crate) const KNIGHT_JUMPS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+/// Directions of the rook
+pub(crate) const STRAIGHT: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+/// Directions of the bishop
+pub(crate) const DIAGONAL: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+/// Directions of the queen, and steps of the king
+pub(crate) const ALL_DIRECTIONS: [(i32, i32); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Type {
@@ -47,6 +74,15 @@ pub enum Color {
     Black,
 }
 
+impl Color {
+    pub fn opponent(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let color = match self {
@@ -145,22 +181,72 @@ impl ChessPiece {
         return Ok(movement);
     }
 
-    //TODO: Too expensive, refactor
     /// Returns a list of legal moves for the piece at the given position
     pub fn legal_moves(&self, from: Position, board: &Board) -> Vec<Position> {
-        let mut legal_moves = Vec::new();
+        let mut legal_moves: Vec<Position> = self
+            .candidate_targets(from, board)
+            .into_iter()
+            .filter(|to| self.can_move(from, *to, board).is_ok())
+            .collect();
+
+        // Keep the same ordering as a full board scan (file by file)
+        legal_moves.sort();
+        legal_moves
+    }
+
+    /// Returns the squares this piece could reach if the board was ignoring checks,
+    /// every returned square still has to be validated by `can_move`
+    fn candidate_targets(&self, from: Position, board: &Board) -> Vec<Position> {
+        let steps: Vec<(i32, i32)> = match self.piece_type {
+            Type::Pawn => {
+                let direction = match self.color {
+                    Color::White => 1,
+                    Color::Black => -1,
+                };
+                vec![
+                    (0, direction),
+                    (0, 2 * direction),
+                    (1, direction),
+                    (-1, direction),
+                ]
+            }
+            Type::Knight => KNIGHT_JUMPS.to_vec(),
+            Type::King => [&ALL_DIRECTIONS[..], &[(2, 0), (-2, 0)]].concat(),
+            Type::Bishop => return Self::ray_targets(from, board, &DIAGONAL),
+            Type::Rook => return Self::ray_targets(from, board, &STRAIGHT),
+            Type::Queen => return Self::ray_targets(from, board, &ALL_DIRECTIONS),
+        };
 
-        for x in 0..8 {
-            for y in 0..8 {
-                let to = Position { x, y };
+        steps
+            .into_iter()
+            .map(|(x, y)| Position {
+                x: from.x + x,
+                y: from.y + y,
+            })
+            .filter(|to| to.is_valid())
+            .collect()
+    }
 
-                if self.can_move(from, to, board).is_ok() {
-                    legal_moves.push(to);
+    /// Walks each direction until the edge of the board or the first piece (included)
+    fn ray_targets(from: Position, board: &Board, directions: &[(i32, i32)]) -> Vec<Position> {
+        let mut targets = Vec::new();
+        for (x, y) in directions {
+            let mut to = from;
+            loop {
+                to = Position {
+                    x: to.x + x,
+                    y: to.y + y,
+                };
+                if !to.is_valid() {
+                    break;
+                }
+                targets.push(to);
+                if board.get_piece_at(&to).is_some() {
+                    break;
                 }
             }
         }
-
-        legal_moves
+        targets
     }
 
     fn can_move_pawn(&self, from: &Position, to: &Position, board: &Board) -> Movement {
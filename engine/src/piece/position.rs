@@ -7,6 +7,13 @@ pub struct Position {
     pub y: i32,
 }
 
+impl Position {
+    /// Returns true if the position is inside the board
+    pub fn is_valid(&self) -> bool {
+        self.x >= 0 && self.x < 8 && self.y >= 0 && self.y < 8
+    }
+}
+
 impl<'de> Deserialize<'de> for Position {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
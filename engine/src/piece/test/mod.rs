@@ -605,3 +605,9 @@ fn test_double_check() {
 
     assert!(king.can_move(from, to, &board).is_ok());
 }
+
+#[test]
+fn opponent_test() {
+    assert_eq!(Color::White.opponent(), Color::Black);
+    assert_eq!(Color::Black.opponent(), Color::White);
+}
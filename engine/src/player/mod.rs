@@ -0,0 +1,30 @@
+use std::error::Error;
+
+use crate::{
+    board::Board,
+    piece::{position::Position, Type},
+};
+
+pub mod runner;
+
+#[cfg(test)]
+mod test;
+
+/// Anyone who can sit at the board: a human at a terminal, an AI, a remote client...
+pub trait Player {
+    /// Chooses the move of the side to move, None resigns the game
+    fn choose_move(&mut self, board: &Board) -> Option<(Position, Position)>;
+
+    /// Chooses the piece for the pawn waiting on the last rank
+    fn choose_promotion(&mut self, board: &Board) -> Type;
+
+    /// Called when the chosen move or promotion was not accepted, the player is asked again
+    fn rejected(&mut self, _error: &dyn Error) {}
+
+    /// Called before the player is asked to move, with the positions before every move of the
+    /// game, oldest first
+    fn set_history(&mut self, _history: &[Board]) {}
+
+    /// Called once the chosen move, and its promotion, were played
+    fn played(&mut self) {}
+}
@@ -0,0 +1,126 @@
+use crate::{
+    board::Board,
+    game::{DrawReason, Game},
+    piece::{ChessPiece, Color},
+};
+
+use super::Player;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Checkmate { winner: Color },
+    Resignation { winner: Color },
+    Draw(DrawReason),
+}
+
+impl Outcome {
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate { winner } | Outcome::Resignation { winner } => Some(*winner),
+            Outcome::Draw(_) => None,
+        }
+    }
+}
+
+/// Plays a game between two players until it ends. Draws that could be claimed
+/// (threefold repetition, fifty-move rule) end the game as if a player claimed them
+pub struct GameRunner {
+    game: Game,
+}
+
+impl Default for GameRunner {
+    fn default() -> Self {
+        GameRunner::new()
+    }
+}
+
+impl GameRunner {
+    pub fn new() -> GameRunner {
+        GameRunner::from_game(Game::new())
+    }
+
+    /// Continues an already started game
+    pub fn from_game(game: Game) -> GameRunner {
+        GameRunner { game }
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The outcome of the game, None while it goes on
+    pub fn outcome(&self) -> Option<Outcome> {
+        let board = self.game.get_board();
+        if let Some(winner) = board.get_winner() {
+            return Some(Outcome::Checkmate { winner });
+        }
+        self.game
+            .draw()
+            .or_else(|| self.game.claimable_draw())
+            .map(Outcome::Draw)
+    }
+
+    /// Asks the player to move until a move (and its promotion) is accepted.
+    /// Returns the outcome if the game ended
+    pub fn step(&mut self, white: &mut dyn Player, black: &mut dyn Player) -> Option<Outcome> {
+        if let Some(outcome) = self.outcome() {
+            return Some(outcome);
+        }
+
+        let color = self.game.get_board().get_turn();
+        let player: &mut dyn Player = match color {
+            Color::White => &mut *white,
+            Color::Black => &mut *black,
+        };
+
+        let history: Vec<Board> = self.game.history().copied().collect();
+        player.set_history(&history);
+        loop {
+            let board = *self.game.get_board();
+            if board.get_promotion().is_some() {
+                let piece = ChessPiece::new(player.choose_promotion(&board), color);
+                match self.game.promote(piece) {
+                    Ok(_) => break,
+                    Err(error) => player.rejected(&error),
+                }
+                continue;
+            }
+
+            let (from, to) = match player.choose_move(&board) {
+                Some(movement) => movement,
+                None => {
+                    self.game.resign();
+                    let winner = color.opponent();
+                    return Some(Outcome::Resignation { winner });
+                }
+            };
+            match self.game.move_piece(from, to) {
+                Ok(_) if self.game.get_board().get_promotion().is_some() => continue,
+                Ok(_) => break,
+                Err(error) => player.rejected(&error),
+            }
+        }
+        player.played();
+
+        self.outcome()
+    }
+
+    /// Plays until the game ends, `on_move` is called after every move
+    pub fn run(
+        &mut self,
+        white: &mut dyn Player,
+        black: &mut dyn Player,
+        mut on_move: impl FnMut(&Game),
+    ) -> Outcome {
+        loop {
+            let moves = self.game.history().count();
+            let outcome = self.step(white, black);
+            if self.game.history().count() > moves {
+                on_move(&self.game);
+            }
+            if let Some(outcome) = outcome {
+                return outcome;
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{collections::VecDeque, error::Error, str::FromStr};
+
+use crate::{
+    board::Board,
+    game::{DrawReason, Game},
+    piece::{position::Position, Color, Type},
+    player::{
+        runner::{GameRunner, Outcome},
+        Player,
+    },
+};
+
+/// Plays the given moves and promotions, then resigns
+struct ScriptedPlayer {
+    moves: VecDeque<(Position, Position)>,
+    promotions: VecDeque<Type>,
+    rejections: usize,
+    /// Length of the history the player was given before each move
+    histories: Vec<usize>,
+    played: usize,
+}
+
+impl ScriptedPlayer {
+    fn new(moves: &[&str], promotions: &[Type]) -> ScriptedPlayer {
+        let moves = moves
+            .iter()
+            .map(|movement| {
+                let (from, to) = movement.split_once(' ').unwrap();
+                (
+                    Position::from_str(from).unwrap(),
+                    Position::from_str(to).unwrap(),
+                )
+            })
+            .collect();
+        ScriptedPlayer {
+            moves,
+            promotions: promotions.iter().copied().collect(),
+            rejections: 0,
+            histories: Vec::new(),
+            played: 0,
+        }
+    }
+}
+
+impl Player for ScriptedPlayer {
+    fn choose_move(&mut self, _board: &Board) -> Option<(Position, Position)> {
+        self.moves.pop_front()
+    }
+
+    fn choose_promotion(&mut self, _board: &Board) -> Type {
+        self.promotions.pop_front().unwrap_or(Type::Queen)
+    }
+
+    fn rejected(&mut self, _error: &dyn Error) {
+        self.rejections += 1;
+    }
+
+    fn set_history(&mut self, history: &[Board]) {
+        self.histories.push(history.len());
+    }
+
+    fn played(&mut self) {
+        self.played += 1;
+    }
+}
+
+#[test]
+fn test_runner_checkmate() {
+    let mut white = ScriptedPlayer::new(&["f2 f3", "g2 g4"], &[]);
+    let mut black = ScriptedPlayer::new(&["e7 e5", "d8 h4"], &[]);
+    let mut runner = GameRunner::new();
+
+    let mut moves = 0;
+    let outcome = runner.run(&mut white, &mut black, |_| moves += 1);
+
+    assert_eq!(
+        outcome,
+        Outcome::Checkmate {
+            winner: Color::Black
+        }
+    );
+    assert_eq!(outcome.winner(), Some(Color::Black));
+    assert_eq!(moves, 4);
+    assert_eq!(white.histories, [0, 2]);
+    assert_eq!(black.histories, [1, 3]);
+    assert_eq!((white.played, black.played), (2, 2));
+}
+
+#[test]
+fn test_runner_resignation() {
+    let mut white = ScriptedPlayer::new(&["e2 e4"], &[]);
+    let mut black = ScriptedPlayer::new(&[], &[]);
+    let mut runner = GameRunner::new();
+
+    let outcome = runner.run(&mut white, &mut black, |_| {});
+
+    assert_eq!(
+        outcome,
+        Outcome::Resignation {
+            winner: Color::White
+        }
+    );
+    assert_eq!(
+        runner.get_game().get_board().get_winner(),
+        Some(Color::White)
+    );
+}
+
+#[test]
+fn test_runner_asks_again_after_illegal_move() {
+    let mut white = ScriptedPlayer::new(&["e2 e5", "e2 e4"], &[]);
+    let mut black = ScriptedPlayer::new(&[], &[]);
+    let mut runner = GameRunner::new();
+
+    assert_eq!(runner.step(&mut white, &mut black), None);
+    assert_eq!(white.rejections, 1);
+    // Asked twice, played once
+    assert_eq!(white.played, 1);
+    assert_eq!(runner.get_game().history().count(), 1);
+    assert_eq!(runner.get_game().get_board().get_turn(), Color::Black);
+}
+
+#[test]
+fn test_runner_promotion() {
+    let game = Game::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mut white = ScriptedPlayer::new(&["a7 a8"], &[Type::King, Type::Rook]);
+    let mut black = ScriptedPlayer::new(&[], &[]);
+    let mut runner = GameRunner::from_game(game);
+
+    assert_eq!(runner.step(&mut white, &mut black), None);
+    // A pawn can't become a king
+    assert_eq!(white.rejections, 1);
+    let board = runner.get_game().get_board();
+    let rook = board
+        .get_piece_at(&Position::from_str("a8").unwrap())
+        .unwrap();
+    assert!(rook.get_type() == Type::Rook);
+    assert_eq!(board.get_turn(), Color::Black);
+}
+
+#[test]
+fn test_runner_draws() {
+    let knight_dance = ["g1 f3", "f3 g1", "g1 f3", "f3 g1"];
+    let mut white = ScriptedPlayer::new(&knight_dance, &[]);
+    let mut black = ScriptedPlayer::new(&["g8 f6", "f6 g8", "g8 f6", "f6 g8"], &[]);
+    let mut runner = GameRunner::new();
+
+    let outcome = runner.run(&mut white, &mut black, |_| {});
+    assert_eq!(outcome, Outcome::Draw(DrawReason::ThreefoldRepetition));
+    assert_eq!(outcome.winner(), None);
+
+    let game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    let runner = GameRunner::from_game(game);
+    assert_eq!(runner.outcome(), Some(Outcome::Draw(DrawReason::Stalemate)));
+}
@@ -66,3 +66,31 @@ pub enum OkMovement {
 }
 
 pub type Movement = Result<OkMovement, MovementError>;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FenError {
+    MissingField,
+    InvalidPlacement,
+    InvalidTurn,
+    InvalidCastling,
+    InvalidEnPassant,
+    InvalidClock,
+    InvalidKings,
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let error_message = match self {
+            FenError::MissingField => "Missing FEN field",
+            FenError::InvalidPlacement => "Invalid piece placement",
+            FenError::InvalidTurn => "Invalid active color",
+            FenError::InvalidCastling => "Invalid castling rights",
+            FenError::InvalidEnPassant => "Invalid en passant square",
+            FenError::InvalidClock => "Invalid move clock",
+            FenError::InvalidKings => "Each side must have exactly one king",
+        };
+        write!(f, "{}", error_message)
+    }
+}
+
+impl Error for FenError {}
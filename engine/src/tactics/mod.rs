@@ -0,0 +1,442 @@
+use std::fmt::Display;
+
+use crate::{
+    board::Board,
+    piece::{
+        position::Position, ChessPiece, Color, Type, ALL_DIRECTIONS, DIAGONAL, KNIGHT_JUMPS,
+        STRAIGHT,
+    },
+};
+
+#[cfg(test)]
+mod test;
+
+/// Usual value of a piece in pawns, the king is worth more than everything else together
+pub fn piece_value(piece_type: Type) -> u32 {
+    match piece_type {
+        Type::Pawn => 1,
+        Type::Knight | Type::Bishop => 3,
+        Type::Rook => 5,
+        Type::Queen => 9,
+        Type::King => 100,
+    }
+}
+
+/// A slider attacks `pinned`, which can't move without exposing the more valuable `target`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pin {
+    pub pinner: Position,
+    pub pinned: Position,
+    pub target: Position,
+    /// The target is the king, the pinned piece can't leave the line at all
+    pub absolute: bool,
+}
+
+/// A slider attacks `front`, which can't move without exposing the less valuable `behind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Skewer {
+    pub attacker: Position,
+    pub front: Position,
+    pub behind: Position,
+}
+
+/// One piece attacks several pieces it can win: the king, more valuable or undefended pieces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fork {
+    pub attacker: Position,
+    pub targets: Vec<Position>,
+}
+
+/// Moving `blocker` out of the way opens the line from the slider on `attacker` to `target`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveredAttack {
+    pub attacker: Position,
+    pub blocker: Position,
+    pub target: Position,
+}
+
+/// A piece that is attacked and undefended, or attacked by a less valuable piece
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HangingPiece {
+    pub square: Position,
+    pub attackers: Vec<Position>,
+    pub defenders: Vec<Position>,
+}
+
+/// The only defender of several attacked pieces, it can't keep guarding all of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverloadedDefender {
+    pub defender: Position,
+    pub defended: Vec<Position>,
+}
+
+/// A king on its first rank that can't step forward, a rook or queen check on that rank mates
+/// unless something covers it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackRankWeakness {
+    pub king: Position,
+    /// Own pieces standing on the king's escape squares
+    pub blockers: Vec<Position>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Motif {
+    Pin(Pin),
+    Skewer(Skewer),
+    Fork(Fork),
+    DiscoveredAttack(DiscoveredAttack),
+    HangingPiece(HangingPiece),
+    OverloadedDefender(OverloadedDefender),
+    BackRankWeakness(BackRankWeakness),
+}
+
+/// `fork`, `pin`, `skewer`, `discoveredAttack`, `hangingPiece`, `overloading` or
+/// `backRankWeakness`
+impl Display for Motif {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Motif::Pin(_) => "pin",
+            Motif::Skewer(_) => "skewer",
+            Motif::Fork(_) => "fork",
+            Motif::DiscoveredAttack(_) => "discoveredAttack",
+            Motif::HangingPiece(_) => "hangingPiece",
+            Motif::OverloadedDefender(_) => "overloading",
+            Motif::BackRankWeakness(_) => "backRankWeakness",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Tactical patterns of a position. Every query is asked for the side that can use the
+/// pattern: `pins(Color::White)` are the pins of white pieces against black ones,
+/// `hanging_pieces(Color::White)` the black pieces White can win
+impl Board {
+    /// Squares attacked by the piece on `from`, including squares of pieces of its own color
+    /// (which it defends). Lines stop at the first piece
+    pub fn attacked_squares(&self, from: Position) -> Vec<Position> {
+        let Some(piece) = self.get_piece_at(&from) else {
+            return Vec::new();
+        };
+        let steps: &[(i32, i32)] = match piece.get_type() {
+            Type::Pawn => match piece.get_color() {
+                Color::White => &[(1, 1), (-1, 1)],
+                Color::Black => &[(1, -1), (-1, -1)],
+            },
+            Type::Knight => &KNIGHT_JUMPS,
+            Type::King => &ALL_DIRECTIONS,
+            Type::Bishop => return self.lines(from, &DIAGONAL),
+            Type::Rook => return self.lines(from, &STRAIGHT),
+            Type::Queen => return self.lines(from, &ALL_DIRECTIONS),
+        };
+        steps
+            .iter()
+            .map(|(x, y)| Position {
+                x: from.x + x,
+                y: from.y + y,
+            })
+            .filter(|to| to.is_valid())
+            .collect()
+    }
+
+    /// Squares of the pieces of `color` attacking `target`
+    pub fn attackers(&self, target: Position, color: Color) -> Vec<Position> {
+        self.pieces_of(color)
+            .filter(|(from, _)| self.attacked_squares(*from).contains(&target))
+            .map(|(from, _)| from)
+            .collect()
+    }
+
+    pub fn pins(&self, color: Color) -> Vec<Pin> {
+        self.lines_through_two(color)
+            .filter_map(|(pinner, (pinned, pinned_piece), (target, target_piece))| {
+                let enemy = color.opponent();
+                let enemies =
+                    pinned_piece.get_color() == enemy && target_piece.get_color() == enemy;
+                let valuable =
+                    piece_value(target_piece.get_type()) > piece_value(pinned_piece.get_type());
+                (enemies && valuable && pinned_piece.get_type() != Type::King).then_some(Pin {
+                    pinner,
+                    pinned,
+                    target,
+                    absolute: target_piece.get_type() == Type::King,
+                })
+            })
+            .collect()
+    }
+
+    pub fn skewers(&self, color: Color) -> Vec<Skewer> {
+        self.lines_through_two(color)
+            .filter_map(|(attacker, (front, front_piece), (behind, behind_piece))| {
+                let enemy = color.opponent();
+                let enemies = front_piece.get_color() == enemy && behind_piece.get_color() == enemy;
+                let valuable =
+                    piece_value(front_piece.get_type()) > piece_value(behind_piece.get_type());
+                (enemies && valuable && behind_piece.get_type() != Type::Pawn).then_some(Skewer {
+                    attacker,
+                    front,
+                    behind,
+                })
+            })
+            .collect()
+    }
+
+    pub fn forks(&self, color: Color) -> Vec<Fork> {
+        self.pieces_of(color)
+            .filter_map(|(attacker, piece)| {
+                let targets: Vec<Position> = self
+                    .attacked_squares(attacker)
+                    .into_iter()
+                    .filter(|target| self.can_win(&piece, *target))
+                    .collect();
+                (targets.len() >= 2).then_some(Fork { attacker, targets })
+            })
+            .collect()
+    }
+
+    /// Pieces of `color` standing between one of its sliders and an enemy piece the slider
+    /// would then win
+    pub fn discovered_attacks(&self, color: Color) -> Vec<DiscoveredAttack> {
+        self.lines_through_two(color)
+            .filter_map(|(attacker, (blocker, blocker_piece), (target, _))| {
+                let slider = self.get_piece_at(&attacker)?;
+                (blocker_piece.get_color() == color && self.can_win(slider, target)).then_some(
+                    DiscoveredAttack {
+                        attacker,
+                        blocker,
+                        target,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Enemy pieces (the king excepted) `color` can take without losing the exchange
+    pub fn hanging_pieces(&self, color: Color) -> Vec<HangingPiece> {
+        self.pieces_of(color.opponent())
+            .filter(|(_, piece)| piece.get_type() != Type::King)
+            .filter_map(|(square, piece)| {
+                let attackers = self.attackers(square, color);
+                let defenders = self.attackers(square, color.opponent());
+                let cheapest = attackers
+                    .iter()
+                    .filter_map(|attacker| self.get_piece_at(attacker))
+                    .map(|attacker| piece_value(attacker.get_type()))
+                    .min()?;
+                let hanging = defenders.is_empty() || cheapest < piece_value(piece.get_type());
+                hanging.then_some(HangingPiece {
+                    square,
+                    attackers,
+                    defenders,
+                })
+            })
+            .collect()
+    }
+
+    /// Enemy pieces that are the only defender of two or more pieces attacked by `color`
+    pub fn overloaded_defenders(&self, color: Color) -> Vec<OverloadedDefender> {
+        let enemy = color.opponent();
+        let mut overloaded: Vec<OverloadedDefender> = Vec::new();
+        for (square, piece) in self.pieces_of(enemy) {
+            if piece.get_type() == Type::King || self.attackers(square, color).is_empty() {
+                continue;
+            }
+            let [defender] = self.attackers(square, enemy)[..] else {
+                continue;
+            };
+            match overloaded.iter_mut().find(|o| o.defender == defender) {
+                Some(overloaded) => overloaded.defended.push(square),
+                None => overloaded.push(OverloadedDefender {
+                    defender,
+                    defended: vec![square],
+                }),
+            }
+        }
+        overloaded.retain(|overloaded| overloaded.defended.len() >= 2);
+        overloaded
+    }
+
+    /// The enemy king is stuck on its first rank while `color` has a rook or a queen
+    pub fn back_rank_weakness(&self, color: Color) -> Option<BackRankWeakness> {
+        let enemy = color.opponent();
+        let heavy = self
+            .pieces_of(color)
+            .any(|(_, piece)| matches!(piece.get_type(), Type::Rook | Type::Queen));
+        let (king, _) = self
+            .pieces_of(enemy)
+            .find(|(_, piece)| piece.get_type() == Type::King)?;
+        let (back_rank, forward) = match enemy {
+            Color::White => (0, 1),
+            Color::Black => (7, -1),
+        };
+        if !heavy || king.y != back_rank {
+            return None;
+        }
+
+        let mut blockers = Vec::new();
+        for x in king.x - 1..=king.x + 1 {
+            let square = Position {
+                x,
+                y: back_rank + forward,
+            };
+            if !square.is_valid() {
+                continue;
+            }
+            match self.get_piece_at(&square) {
+                Some(piece) if piece.get_color() == enemy => blockers.push(square),
+                _ if !self.attackers(square, color).is_empty() => {}
+                _ => return None,
+            }
+        }
+        Some(BackRankWeakness { king, blockers })
+    }
+
+    /// Every pattern `color` can use in the position
+    pub fn motifs(&self, color: Color) -> Vec<Motif> {
+        let mut motifs: Vec<Motif> = Vec::new();
+        motifs.extend(self.forks(color).into_iter().map(Motif::Fork));
+        motifs.extend(self.pins(color).into_iter().map(Motif::Pin));
+        motifs.extend(self.skewers(color).into_iter().map(Motif::Skewer));
+        motifs.extend(
+            self.discovered_attacks(color)
+                .into_iter()
+                .map(Motif::DiscoveredAttack),
+        );
+        motifs.extend(
+            self.hanging_pieces(color)
+                .into_iter()
+                .map(Motif::HangingPiece),
+        );
+        motifs.extend(
+            self.overloaded_defenders(color)
+                .into_iter()
+                .map(Motif::OverloadedDefender),
+        );
+        motifs.extend(self.back_rank_weakness(color).map(Motif::BackRankWeakness));
+        motifs
+    }
+
+    /// The patterns a move of the side to move plays: forks, pins and skewers by the moved
+    /// piece, the discovered attack it unmasks and the hanging piece it takes. Pawns reaching
+    /// the last rank become queens. Empty when the move is not legal
+    pub fn move_motifs(&self, from: Position, to: Position) -> Vec<Motif> {
+        let color = self.get_turn();
+        let mut after = *self;
+        if after.move_piece(from, to).is_err() {
+            return Vec::new();
+        }
+        if after.get_promotion().is_some() {
+            let _ = after.promote(ChessPiece::create_queen(color));
+        }
+
+        let mut motifs: Vec<Motif> = Vec::new();
+        for fork in after.forks(color) {
+            if fork.attacker == to {
+                motifs.push(Motif::Fork(fork));
+            }
+        }
+        for pin in after.pins(color) {
+            if pin.pinner == to {
+                motifs.push(Motif::Pin(pin));
+            }
+        }
+        for skewer in after.skewers(color) {
+            if skewer.attacker == to {
+                motifs.push(Motif::Skewer(skewer));
+            }
+        }
+        for discovered in self.discovered_attacks(color) {
+            let opened = after
+                .attacked_squares(discovered.attacker)
+                .contains(&discovered.target);
+            if discovered.blocker == from && opened {
+                motifs.push(Motif::DiscoveredAttack(discovered));
+            }
+        }
+        for hanging in self.hanging_pieces(color) {
+            if hanging.square == to {
+                motifs.push(Motif::HangingPiece(hanging));
+            }
+        }
+        motifs
+    }
+
+    /// True when `piece` attacking `target` wins something: the enemy king, a more valuable
+    /// piece or an undefended one
+    fn can_win(&self, piece: &ChessPiece, target: Position) -> bool {
+        let Some(target_piece) = self.get_piece_at(&target) else {
+            return false;
+        };
+        let enemy = piece.get_color().opponent();
+        let target_type = target_piece.get_type();
+        target_piece.get_color() == enemy
+            && (target_type == Type::King
+                || piece_value(target_type) > piece_value(piece.get_type())
+                || (target_type != Type::Pawn && self.attackers(target, enemy).is_empty()))
+    }
+
+    /// For every slider of `color` and every direction it moves in, the first two pieces on
+    /// the line
+    #[allow(clippy::type_complexity)]
+    fn lines_through_two(
+        &self,
+        color: Color,
+    ) -> impl Iterator<Item = (Position, (Position, ChessPiece), (Position, ChessPiece))> + '_ {
+        self.pieces_of(color).flat_map(move |(from, piece)| {
+            let directions: &[(i32, i32)] = match piece.get_type() {
+                Type::Bishop => &DIAGONAL,
+                Type::Rook => &STRAIGHT,
+                Type::Queen => &ALL_DIRECTIONS,
+                _ => &[],
+            };
+            directions.iter().filter_map(move |direction| {
+                let mut pieces = self.pieces_on_line(from, *direction);
+                Some((from, pieces.next()?, pieces.next()?))
+            })
+        })
+    }
+
+    /// Pieces met walking from `from` in a direction
+    fn pieces_on_line(
+        &self,
+        from: Position,
+        (x, y): (i32, i32),
+    ) -> impl Iterator<Item = (Position, ChessPiece)> + '_ {
+        (1..8)
+            .map(move |step| Position {
+                x: from.x + x * step,
+                y: from.y + y * step,
+            })
+            .take_while(|square| square.is_valid())
+            .filter_map(|square| Some((square, *self.get_piece_at(&square)?)))
+    }
+
+    /// Squares in every direction up to the first piece included
+    fn lines(&self, from: Position, directions: &[(i32, i32)]) -> Vec<Position> {
+        let mut squares = Vec::new();
+        for (x, y) in directions {
+            for step in 1..8 {
+                let square = Position {
+                    x: from.x + x * step,
+                    y: from.y + y * step,
+                };
+                if !square.is_valid() {
+                    break;
+                }
+                squares.push(square);
+                if self.get_piece_at(&square).is_some() {
+                    break;
+                }
+            }
+        }
+        squares
+    }
+
+    fn pieces_of(&self, color: Color) -> impl Iterator<Item = (Position, ChessPiece)> + '_ {
+        (0..8)
+            .flat_map(|y| (0..8).map(move |x| Position { x, y }))
+            .filter_map(move |square| {
+                let piece = *self.get_piece_at(&square)?;
+                (piece.get_color() == color).then_some((square, piece))
+            })
+    }
+}
@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use crate::{
+    board::Board,
+    piece::{position::Position, Color},
+    tactics::{DiscoveredAttack, Motif, Pin, Skewer},
+};
+
+fn square(name: &str) -> Position {
+    Position::from_str(name).unwrap()
+}
+
+fn squares(names: &[&str]) -> Vec<Position> {
+    let mut squares: Vec<Position> = names.iter().map(|name| square(name)).collect();
+    squares.sort();
+    squares
+}
+
+#[test]
+fn test_attacked_squares() {
+    let board = Board::from_fen("4k3/8/8/3p4/8/1N6/P7/R3K3 w - - 0 1").unwrap();
+    let mut rook = board.attacked_squares(square("a1"));
+    rook.sort();
+    // The pawn on a2 is defended, the line stops there
+    assert_eq!(rook, squares(&["a2", "b1", "c1", "d1", "e1"]));
+    let mut pawn = board.attacked_squares(square("d5"));
+    pawn.sort();
+    assert_eq!(pawn, squares(&["c4", "e4"]));
+    assert_eq!(
+        board.attackers(square("d4"), Color::White),
+        vec![square("b3")]
+    );
+    assert!(board.attacked_squares(square("h8")).is_empty());
+}
+
+#[test]
+fn test_pins() {
+    // The bishop pins the knight to the king, the rook pins the knight to the queen
+    let board = Board::from_fen("3qk3/8/2n5/1B6/8/8/3n4/3RK3 w - - 0 1").unwrap();
+    let pins = board.pins(Color::White);
+    assert!(pins.contains(&Pin {
+        pinner: square("b5"),
+        pinned: square("c6"),
+        target: square("e8"),
+        absolute: true,
+    }));
+    assert!(pins.contains(&Pin {
+        pinner: square("d1"),
+        pinned: square("d2"),
+        target: square("d8"),
+        absolute: false,
+    }));
+    assert_eq!(pins.len(), 2);
+    assert!(board.pins(Color::Black).is_empty());
+}
+
+#[test]
+fn test_skewers() {
+    let board = Board::from_fen("8/8/8/R1k4q/8/8/8/6K1 b - - 0 1").unwrap();
+    assert_eq!(
+        board.skewers(Color::White),
+        vec![Skewer {
+            attacker: square("a5"),
+            front: square("c5"),
+            behind: square("h5"),
+        }]
+    );
+}
+
+#[test]
+fn test_forks() {
+    let board = Board::from_fen("r3k3/2N5/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+    let forks = board.forks(Color::White);
+    assert_eq!(forks.len(), 1);
+    assert_eq!(forks[0].attacker, square("c7"));
+    let mut targets = forks[0].targets.clone();
+    targets.sort();
+    assert_eq!(targets, squares(&["a8", "e8"]));
+
+    // A defended pawn is not worth forking
+    let board = Board::from_fen("4k3/8/8/1p1r4/2N5/8/8/4K3 w - - 0 1").unwrap();
+    assert!(board.forks(Color::White).is_empty());
+}
+
+#[test]
+fn test_discovered_attacks() {
+    // Moving the knight opens the rook's line to the queen
+    let board = Board::from_fen("3qk3/8/8/8/3N4/8/8/3RK3 w - - 0 1").unwrap();
+    let discovered = DiscoveredAttack {
+        attacker: square("d1"),
+        blocker: square("d4"),
+        target: square("d8"),
+    };
+    assert_eq!(board.discovered_attacks(Color::White), vec![discovered]);
+
+    let motifs = board.move_motifs(square("d4"), square("c6"));
+    assert_eq!(motifs, vec![Motif::DiscoveredAttack(discovered)]);
+    // The rook's line stays closed
+    assert!(board.move_motifs(square("d4"), square("d5")).is_empty());
+}
+
+#[test]
+fn test_hanging_pieces() {
+    // The bishop is undefended, the rook is attacked by a pawn, the knight is defended
+    let board = Board::from_fen("4k3/8/2b5/3n4/1r6/P1Q5/8/4K3 w - - 0 1").unwrap();
+    let mut hanging: Vec<Position> = board
+        .hanging_pieces(Color::White)
+        .into_iter()
+        .map(|hanging| hanging.square)
+        .collect();
+    hanging.sort();
+    assert_eq!(hanging, squares(&["b4", "c6"]));
+
+    let motifs = board.move_motifs(square("c3"), square("c6"));
+    assert!(motifs.contains(&Motif::HangingPiece(
+        board.hanging_pieces(Color::White)[1].clone()
+    )));
+    assert!(board.move_motifs(square("c3"), square("c2")).is_empty());
+    // Not White's turn
+    assert!(board.move_motifs(square("d5"), square("c3")).is_empty());
+}
+
+#[test]
+fn test_overloaded_defenders() {
+    // The queen alone defends the rook and the knight, both attacked
+    let board = Board::from_fen("4k3/8/8/1r1q1n2/8/8/1R3R2/4K3 w - - 0 1").unwrap();
+    let overloaded = board.overloaded_defenders(Color::White);
+    assert_eq!(overloaded.len(), 1);
+    assert_eq!(overloaded[0].defender, square("d5"));
+    let mut defended = overloaded[0].defended.clone();
+    defended.sort();
+    assert_eq!(defended, squares(&["b5", "f5"]));
+}
+
+#[test]
+fn test_back_rank_weakness() {
+    let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    let weakness = board.back_rank_weakness(Color::White).unwrap();
+    assert_eq!(weakness.king, square("g8"));
+    let mut blockers = weakness.blockers.clone();
+    blockers.sort();
+    assert_eq!(blockers, squares(&["f7", "g7", "h7"]));
+    assert!(board
+        .motifs(Color::White)
+        .iter()
+        .any(|motif| motif.to_string() == "backRankWeakness"));
+
+    // Luft on h6
+    let board = Board::from_fen("6k1/5pp1/7p/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    assert_eq!(board.back_rank_weakness(Color::White), None);
+    // Nothing to give the check with
+    let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/B5K1 w - - 0 1").unwrap();
+    assert_eq!(board.back_rank_weakness(Color::White), None);
+}
@@ -0,0 +1,155 @@
+use ai::{
+    analysis::{Analyser, AnalysisError, GameAnalysis, MoveClass},
+    moves::{self, Move},
+    pgn,
+    search::SearchLimits,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use engine::{board::Board, piece::Color};
+use serde::Serialize;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::lobby::RoomId;
+
+/// Plies searched for every position of an analysed game
+const ANALYSIS_DEPTH: u8 = 8;
+/// Analyses searching at the same time, each one keeps a blocking thread busy
+const MAX_RUNNING: usize = 2;
+/// Reports kept, the oldest is dropped first
+const CACHE_SIZE: usize = 64;
+
+/// A finished game of a room and its report
+type CachedReport = (RoomId, Vec<Move>, Arc<AnalysisReport>);
+
+/// The reports of the last games analysed, and the analyses running
+pub struct Analyses {
+    /// By room, with the moves of the game analysed since a room can play rematches
+    reports: Mutex<VecDeque<CachedReport>>,
+    running: Semaphore,
+}
+
+impl Default for Analyses {
+    fn default() -> Self {
+        Self {
+            reports: Mutex::new(VecDeque::new()),
+            running: Semaphore::new(MAX_RUNNING),
+        }
+    }
+}
+
+impl Analyses {
+    pub fn cached(&self, room_id: RoomId, game: &[Move]) -> Option<Arc<AnalysisReport>> {
+        let reports = self.reports.lock().ok()?;
+        reports
+            .iter()
+            .find(|(room, moves, _)| *room == room_id && moves == game)
+            .map(|(_, _, report)| report.clone())
+    }
+
+    pub fn insert(&self, room_id: RoomId, game: Vec<Move>, report: Arc<AnalysisReport>) {
+        let Ok(mut reports) = self.reports.lock() else {
+            return;
+        };
+        reports.retain(|(room, _, _)| *room != room_id);
+        if reports.len() >= CACHE_SIZE {
+            reports.pop_front();
+        }
+        reports.push_back((room_id, game, report));
+    }
+
+    /// None when [`MAX_RUNNING`] analyses are already running
+    pub fn start(&self) -> Option<SemaphorePermit<'_>> {
+        self.running.try_acquire().ok()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisReport {
+    white: PlayerReport,
+    black: PlayerReport,
+    moves: Vec<MoveReport>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayerReport {
+    accuracy: Option<f64>,
+    average_loss: Option<i32>,
+    inaccuracies: usize,
+    mistakes: usize,
+    blunders: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveReport {
+    ply: usize,
+    color: Color,
+    san: String,
+    class: String,
+    loss: i32,
+    accuracy: f64,
+    best_move: Option<String>,
+    better_line: Option<String>,
+    missed_motifs: Vec<String>,
+}
+
+/// Analyses a game played from the starting position, this blocks for a while
+pub fn analyse(game: &[Move]) -> Result<AnalysisReport, AnalysisError> {
+    let mut analyser = Analyser::new(SearchLimits::depth(ANALYSIS_DEPTH));
+    let analysis = analyser.analyse(&Board::new(), game)?;
+    Ok(AnalysisReport::new(&analysis))
+}
+
+impl AnalysisReport {
+    fn new(analysis: &GameAnalysis) -> AnalysisReport {
+        let mut board = analysis.start;
+        let mut moves = Vec::with_capacity(analysis.moves.len());
+        for (index, analysed) in analysis.moves.iter().enumerate() {
+            // Both lines start at the position before the move
+            let number = index as u32 / 2 + 1;
+            moves.push(MoveReport {
+                ply: analysed.ply,
+                color: analysed.color,
+                san: pgn::to_san(&board, analysed.movement),
+                class: analysed.class.to_string(),
+                loss: analysed.loss,
+                accuracy: analysed.accuracy(),
+                best_move: analysed.best_move.map(|best| pgn::to_san(&board, best)),
+                better_line: match analysed.better_line.is_empty() {
+                    true => None,
+                    false => Some(pgn::line_to_san(&board, number, &analysed.better_line)),
+                },
+                missed_motifs: analysed
+                    .missed_motifs
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect(),
+            });
+            board = moves::apply(&board, analysed.movement).unwrap_or(board);
+        }
+
+        AnalysisReport {
+            white: PlayerReport::new(analysis, Color::White),
+            black: PlayerReport::new(analysis, Color::Black),
+            moves,
+        }
+    }
+}
+
+impl PlayerReport {
+    fn new(analysis: &GameAnalysis, color: Color) -> PlayerReport {
+        PlayerReport {
+            accuracy: analysis.accuracy(color),
+            average_loss: analysis.average_loss(color),
+            inaccuracies: analysis.count(color, MoveClass::Inaccuracy),
+            mistakes: analysis.count(color, MoveClass::Mistake),
+            blunders: analysis.count(color, MoveClass::Blunder),
+        }
+    }
+}
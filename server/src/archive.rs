@@ -0,0 +1,42 @@
+use std::{env, fs::OpenOptions, io::Write};
+
+use ai::{
+    moves::Move,
+    pgn::{GameResult, PgnGame},
+};
+use engine::{board::Board, piece::Color};
+
+use crate::lobby::RoomId;
+
+/// PGN file finished games are appended to, games are not kept when it isn't set
+const ARCHIVE_VARIABLE: &str = "GAME_ARCHIVE";
+
+pub fn archive_game(room_id: RoomId, moves: Vec<Move>, winner: Option<Color>) {
+    let Ok(path) = env::var(ARCHIVE_VARIABLE) else {
+        return;
+    };
+    let result = match winner {
+        Some(Color::White) => GameResult::WhiteWins,
+        Some(Color::Black) => GameResult::BlackWins,
+        None => GameResult::Draw,
+    };
+    let game = PgnGame {
+        tags: vec![
+            ("Event".to_string(), "Online game".to_string()),
+            ("Site".to_string(), room_id.to_string()),
+            ("Result".to_string(), result.to_string()),
+        ],
+        start: Board::new(),
+        moves,
+        result,
+    };
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", game));
+    if let Err(error) = written {
+        println!("Couldn't archive the game of room {}: {}", room_id, error);
+    }
+}
@@ -8,7 +8,16 @@ pub enum Command {
     Move { from: Position, to: Position },
     Promote { piece: Type },
     Resign(bool),
-    Reset(bool),
+    OfferDraw(bool),
+    AcceptDraw(bool),
+    DeclineDraw(bool),
+    ClaimDraw(bool),
+    RequestTakeback(bool),
+    AcceptTakeback(bool),
+    DeclineTakeback(bool),
+    OfferRematch(bool),
+    AcceptRematch(bool),
+    DeclineRematch(bool),
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -2,8 +2,17 @@ use std::time::Instant;
 
 use crate::{
     commands,
-    lobby::{client::Client, Lobby},
-    messages::{result::ResultMessage, CommandMessage, ConnectMessage, DisconnectMessage},
+    lobby::{
+        client::Client,
+        queue::Search,
+        room::{bot::BotSettings, time_control::TimeControl},
+        Lobby, SessionToken,
+    },
+    messages::{
+        result::ResultMessage, CommandMessage, ConnectMessage, DisconnectMessage, JoinQueue,
+        Matched,
+    },
+    rating::Profile,
     CLIENT_TIMEOUT, HEARTBEAT_INTERVAL,
 };
 use actix::{
@@ -18,15 +27,78 @@ pub struct Con {
     room: Uuid,
     lobby_addr: Addr<Lobby>,
     heartbeat: Instant,
+    time_control: TimeControl,
+    bot: Option<BotSettings>,
+    spectator: bool,
+    session: Option<SessionToken>,
+    /// Set while the connection waits in the matchmaking queue, its room is not known yet
+    search: Option<Search>,
+    /// The registered player the connection plays as
+    player: Option<Profile>,
 }
 
 impl Con {
     pub fn new(room: Uuid, lobby: Addr<Lobby>) -> Con {
+        Con::with_time_control(room, lobby, TimeControl::default())
+    }
+
+    /// A connection creating its room with this time control
+    pub fn with_time_control(room: Uuid, lobby: Addr<Lobby>, time_control: TimeControl) -> Con {
         Con {
             id: Uuid::new_v4(),
             room,
             heartbeat: Instant::now(),
             lobby_addr: lobby,
+            time_control,
+            bot: None,
+            spectator: false,
+            session: None,
+            search: None,
+            player: None,
+        }
+    }
+
+    /// A connection creating its room against the server's bot
+    pub fn against_bot(
+        room: Uuid,
+        lobby: Addr<Lobby>,
+        time_control: TimeControl,
+        bot: BotSettings,
+    ) -> Con {
+        Con {
+            bot: Some(bot),
+            ..Con::with_time_control(room, lobby, time_control)
+        }
+    }
+
+    /// A player taking back the seat their session token holds
+    pub fn resume(room: Uuid, lobby: Addr<Lobby>, session: SessionToken) -> Con {
+        Con {
+            session: Some(session),
+            ..Con::new(room, lobby)
+        }
+    }
+
+    /// A connection waiting in the queue for an opponent, the lobby gives it a room once
+    /// it found one
+    pub fn queued(lobby: Addr<Lobby>, time_control: TimeControl, search: Search) -> Con {
+        Con {
+            search: Some(search),
+            ..Con::with_time_control(Uuid::nil(), lobby, time_control)
+        }
+    }
+
+    /// The connection plays as a registered player
+    pub fn with_player(mut self, player: Option<Profile>) -> Con {
+        self.player = player;
+        self
+    }
+
+    /// A connection watching the game of the room without a seat
+    pub fn spectator(room: Uuid, lobby: Addr<Lobby>) -> Con {
+        Con {
+            spectator: true,
+            ..Con::new(room, lobby)
         }
     }
 
@@ -50,12 +122,28 @@ impl Actor for Con {
 
         let addr = ctx.address();
 
-        let client = Client::new(self.id, addr);
+        let client = Client::new(self.id, addr.clone());
+
+        if let Some(search) = self.search {
+            self.lobby_addr.do_send(JoinQueue {
+                client,
+                matched: addr.recipient(),
+                time_control: self.time_control,
+                search,
+                player: self.player.clone(),
+            });
+            return;
+        }
 
         self.lobby_addr
             .send(ConnectMessage {
                 room_id: self.room,
                 client,
+                time_control: self.time_control,
+                bot: self.bot,
+                spectator: self.spectator,
+                session: self.session,
+                player: self.player.clone(),
             })
             .into_actor(self)
             .then(|res, _, ctx| {
@@ -78,6 +166,15 @@ impl Actor for Con {
     }
 }
 
+impl Handler<Matched> for Con {
+    type Result = ();
+
+    fn handle(&mut self, msg: Matched, _: &mut Self::Context) -> Self::Result {
+        self.room = msg.0;
+        self.search = None;
+    }
+}
+
 impl Handler<ResultMessage> for Con {
     type Result = ();
 
@@ -15,6 +15,11 @@ impl Client {
         Self { id, result_addr }
     }
 
+    /// A client whose results go to another actor than a websocket connection
+    pub fn with_recipient(id: Uuid, result_addr: Recipient<ResultMessage>) -> Self {
+        Self { id, result_addr }
+    }
+
     pub fn result_addr(&self) -> &Recipient<ResultMessage> {
         &self.result_addr
     }
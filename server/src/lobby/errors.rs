@@ -12,6 +12,18 @@ pub enum RoomError {
     NotYourTurn,
     NotEnoughPlayers,
     GameNotOver,
+    GameOver,
+    Spectator,
+    InvalidSession,
+    DrawAlreadyOffered,
+    NoDrawOffer,
+    NoDrawToClaim,
+    TakebackAlreadyRequested,
+    NoTakebackRequest,
+    NoMoveToTakeBack,
+    RematchAlreadyOffered,
+    NoRematchOffer,
+    AlreadyQueued,
 }
 
 impl Display for RoomError {
@@ -26,6 +38,20 @@ impl Display for RoomError {
             RoomError::NotYourTurn => write!(f, "It is not your turn"),
             RoomError::NotEnoughPlayers => write!(f, "Not enough players"),
             RoomError::GameNotOver => write!(f, "Game is not over"),
+            RoomError::GameOver => write!(f, "Game is over"),
+            RoomError::Spectator => write!(f, "Spectators can't play"),
+            RoomError::InvalidSession => write!(f, "Invalid session token"),
+            RoomError::DrawAlreadyOffered => write!(f, "Draw already offered"),
+            RoomError::NoDrawOffer => write!(f, "No draw offer to answer"),
+            RoomError::NoDrawToClaim => {
+                write!(f, "No threefold repetition or fifty-move rule to claim")
+            }
+            RoomError::TakebackAlreadyRequested => write!(f, "Takeback already requested"),
+            RoomError::NoTakebackRequest => write!(f, "No takeback request to answer"),
+            RoomError::NoMoveToTakeBack => write!(f, "No move to take back"),
+            RoomError::RematchAlreadyOffered => write!(f, "Rematch already offered"),
+            RoomError::NoRematchOffer => write!(f, "No rematch offer to answer"),
+            RoomError::AlreadyQueued => write!(f, "Player is already waiting for a game"),
         }
     }
 }
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
 use actix::{Actor, Addr, AsyncContext, Handler};
 
@@ -6,39 +9,112 @@ use uuid::Uuid;
 
 pub mod client;
 mod errors;
+pub mod queue;
 pub mod room;
 
+#[cfg(test)]
+mod test;
+
 pub type ClientId = Uuid;
 pub type RoomId = Uuid;
+/// Given to a seated player so they can take their seat back after a disconnection
+pub type SessionToken = Uuid;
 
 use crate::messages::{
-    inner::{AvailableRoom, AvailableRooms},
-    CommandMessage, ConnectMessage, DisconnectMessage,
+    inner::{AvailableRoom, AvailableRooms, RoomAddress, RoomListing},
+    result::ResultMessage,
+    CommandMessage, ConnectMessage, DisconnectMessage, JoinQueue, Matched,
 };
+use crate::rating::Players;
 
+use self::client::Client;
+use self::errors::RoomError;
+use self::queue::{Queue, QueueEntry, QUEUE_INTERVAL};
 use self::room::{
     message::{RoomMessage, RoomMessageType},
+    time_control::TimeControl,
     Room,
 };
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct Lobby {
     available_rooms: HashSet<RoomId>,
+    /// Rooms nobody can join from the list: games against the bot and queue pairings
+    private_rooms: HashSet<RoomId>,
     rooms: HashMap<RoomId, Addr<Room>>,
+    time_controls: HashMap<RoomId, TimeControl>,
+    queue: Queue,
+    players: Addr<Players>,
 }
 
 impl Lobby {
+    pub fn new(players: Addr<Players>) -> Self {
+        Self {
+            available_rooms: HashSet::new(),
+            private_rooms: HashSet::new(),
+            rooms: HashMap::new(),
+            time_controls: HashMap::new(),
+            queue: Queue::default(),
+            players,
+        }
+    }
+
     pub fn available_room(&self, room_id: RoomId) -> bool {
         self.available_rooms.contains(&room_id)
     }
 
-    pub fn available_rooms(&self) -> Vec<RoomId> {
-        self.available_rooms.iter().cloned().collect()
+    pub fn available_rooms(&self) -> Vec<RoomListing> {
+        self.available_rooms
+            .iter()
+            .map(|room_id| RoomListing {
+                id: *room_id,
+                time_control: self.time_controls[room_id],
+            })
+            .collect()
+    }
+
+    fn create_room(
+        &mut self,
+        room_id: RoomId,
+        room: Room,
+        time_control: TimeControl,
+    ) -> &mut Addr<Room> {
+        self.time_controls.insert(room_id, time_control);
+        self.rooms.insert(room_id, room.start());
+        self.rooms.get_mut(&room_id).unwrap()
+    }
+
+    /// Seats every pair the queue found in a room of its own, the player who waited the
+    /// longest plays white
+    fn match_players(&mut self, ctx: &mut <Self as Actor>::Context) {
+        for (white, black) in self.queue.pairs(Instant::now()) {
+            let room_id = RoomId::new_v4();
+            let room = Room::new(
+                room_id,
+                ctx.address().recipient(),
+                self.players.clone().recipient(),
+                white.time_control,
+            );
+            self.private_rooms.insert(room_id);
+            let room = self.create_room(room_id, room, white.time_control);
+            for player in [white, black] {
+                room.do_send(room::message::Connect {
+                    client: player.client,
+                    session: None,
+                    player: player.player,
+                });
+                player.matched.do_send(Matched(room_id));
+            }
+        }
     }
 }
 
 impl Actor for Lobby {
     type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(QUEUE_INTERVAL, |act, ctx| act.match_players(ctx));
+    }
 }
 
 impl Handler<ConnectMessage> for Lobby {
@@ -47,24 +123,76 @@ impl Handler<ConnectMessage> for Lobby {
     fn handle(&mut self, msg: ConnectMessage, ctx: &mut Self::Context) -> Self::Result {
         let client = msg.client;
         let room_id = msg.room_id;
+        if msg.spectator {
+            // Spectators only join rooms that exist
+            match self.rooms.get(&room_id) {
+                Some(room) => room.do_send(room::message::Watch(client)),
+                None => room_not_found(room_id, &client),
+            }
+            return;
+        }
         let room = match self.rooms.get_mut(&room_id) {
             Some(room) => room,
+            // A token can only give back a seat of an existing room
+            None if msg.session.is_some() => {
+                room_not_found(room_id, &client);
+                return;
+            }
             None => {
                 let addr = ctx.address();
-                let room = Room::new(room_id, addr.recipient());
-                self.available_rooms.insert(room_id);
-                let room = room.start();
-                self.rooms.insert(room_id, room);
-                self.rooms.get_mut(&room_id).unwrap()
+                let mut room = Room::new(
+                    room_id,
+                    addr.recipient(),
+                    self.players.clone().recipient(),
+                    msg.time_control,
+                );
+                // Nobody else can join a game against the bot
+                match msg.bot {
+                    Some(bot) => {
+                        room = room.with_bot(bot);
+                        self.private_rooms.insert(room_id);
+                    }
+                    None => {
+                        self.available_rooms.insert(room_id);
+                    }
+                }
+                self.create_room(room_id, room, msg.time_control)
             }
         };
 
-        let room_msg = room::message::Connect(client);
+        let room_msg = room::message::Connect {
+            client,
+            session: msg.session,
+            player: msg.player,
+        };
 
         room.do_send(room_msg);
     }
 }
 
+impl Handler<JoinQueue> for Lobby {
+    type Result = ();
+
+    fn handle(&mut self, msg: JoinQueue, ctx: &mut Self::Context) -> Self::Result {
+        let client = msg.client;
+        let queued = self.queue.push(QueueEntry {
+            client: client.clone(),
+            matched: msg.matched,
+            time_control: msg.time_control,
+            search: msg.search,
+            player: msg.player,
+            joined: Instant::now(),
+        });
+        if let Err(e) = queued {
+            // Queued connections have no room yet
+            let msg = ResultMessage::error(RoomId::nil(), client.id(), e.to_string());
+            client.result_addr().do_send(msg);
+            return;
+        }
+        self.match_players(ctx);
+    }
+}
+
 impl Handler<RoomMessage> for Lobby {
     type Result = ();
 
@@ -81,10 +209,15 @@ impl Handler<RoomMessage> for Lobby {
                 println!("Room {} is empty removing!", room_id);
                 self.rooms.remove(&room_id);
                 self.available_rooms.remove(&room_id);
+                self.private_rooms.remove(&room_id);
+                self.time_controls.remove(&room_id);
                 println!("Rooms: {}", self.rooms.len())
             }
+            // Only the rooms anyone can join go back on the list when a seat frees up
             RoomMessageType::Disconnect => {
-                self.available_rooms.insert(room_id);
+                if !self.private_rooms.contains(&room_id) {
+                    self.available_rooms.insert(room_id);
+                }
             }
         }
     }
@@ -96,6 +229,7 @@ impl Handler<DisconnectMessage> for Lobby {
     fn handle(&mut self, msg: DisconnectMessage, _: &mut Self::Context) -> Self::Result {
         let room_id = msg.room_id;
         let client_id = msg.client_id;
+        self.queue.remove(client_id);
         let room = match self.rooms.get_mut(&room_id) {
             Some(room) => room,
             None => {
@@ -130,7 +264,7 @@ impl Handler<CommandMessage> for Lobby {
 }
 
 impl Handler<AvailableRooms> for Lobby {
-    type Result = Vec<RoomId>;
+    type Result = Vec<RoomListing>;
 
     fn handle(&mut self, _: AvailableRooms, _: &mut Self::Context) -> Self::Result {
         self.available_rooms()
@@ -144,3 +278,18 @@ impl Handler<AvailableRoom> for Lobby {
         return self.available_room(msg.0);
     }
 }
+
+impl Handler<RoomAddress> for Lobby {
+    type Result = Option<Addr<Room>>;
+
+    fn handle(&mut self, msg: RoomAddress, _: &mut Self::Context) -> Self::Result {
+        self.rooms.get(&msg.0).cloned()
+    }
+}
+
+/// Answers a connection to a room that doesn't exist
+fn room_not_found(room_id: RoomId, client: &Client) {
+    let error = RoomError::RoomNotFound.to_string();
+    let msg = ResultMessage::error(room_id, client.id(), error);
+    client.result_addr().do_send(msg);
+}
@@ -0,0 +1,136 @@
+use std::time::{Duration, Instant};
+
+use actix::Recipient;
+use serde::Deserialize;
+
+use crate::{messages::Matched, rating::Profile};
+
+use super::{
+    client::Client,
+    errors::RoomError,
+    room::time_control::{Category, TimeControl},
+    ClientId,
+};
+
+/// How often the queue looks for pairs again, as the rating ranges widen
+pub const QUEUE_INTERVAL: Duration = Duration::from_secs(2);
+/// Rating points added to a player's range for every [`WIDEN_INTERVAL`] they wait
+const WIDEN_STEP: u32 = 50;
+const WIDEN_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_RATING: u32 = 1500;
+const DEFAULT_RANGE: u32 = 200;
+
+/// Query parameters of `ws/queue`, next to the time control ones
+#[derive(Debug, Default, Deserialize)]
+pub struct QueueQuery {
+    pub rating: Option<u32>,
+    /// Largest rating difference accepted at first
+    pub range: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Search {
+    pub rating: u32,
+    pub range: u32,
+}
+
+impl Search {
+    /// A registered player searches around their own rating in the category instead of the
+    /// query's
+    pub fn new(query: &QueueQuery, player: Option<&Profile>, category: Option<Category>) -> Self {
+        let rating = match (player, category) {
+            (Some(player), Some(category)) => player.ratings.get(category).rating.round() as u32,
+            _ => query.rating.unwrap_or(DEFAULT_RATING),
+        };
+        Self {
+            rating,
+            range: query.range.unwrap_or(DEFAULT_RANGE),
+        }
+    }
+}
+
+/// A player waiting for an opponent
+#[derive(Clone)]
+pub struct QueueEntry {
+    pub client: Client,
+    /// Where to tell the connection which room it plays in
+    pub matched: Recipient<Matched>,
+    pub time_control: TimeControl,
+    pub search: Search,
+    pub player: Option<Profile>,
+    pub joined: Instant,
+}
+
+impl QueueEntry {
+    /// The rating range, wider the longer the player waited
+    fn range(&self, now: Instant) -> u32 {
+        let waited = now.saturating_duration_since(self.joined);
+        let steps = (waited.as_secs() / WIDEN_INTERVAL.as_secs()) as u32;
+        self.search
+            .range
+            .saturating_add(WIDEN_STEP.saturating_mul(steps))
+    }
+
+    fn accepts(&self, other: &QueueEntry, now: Instant) -> bool {
+        self.search.rating.abs_diff(other.search.rating) <= self.range(now)
+    }
+}
+
+/// Players waiting for an opponent, in the order they came
+#[derive(Default, Clone)]
+pub struct Queue {
+    entries: Vec<QueueEntry>,
+}
+
+impl Queue {
+    /// Replaces the entry of the same connection. Returns an error if the registered player
+    /// already waits from another connection, they could be paired with themselves
+    pub fn push(&mut self, entry: QueueEntry) -> Result<(), RoomError> {
+        self.remove(entry.client.id());
+        if let Some(player) = &entry.player {
+            let queued = self.entries.iter().any(|queued| {
+                queued
+                    .player
+                    .as_ref()
+                    .is_some_and(|queued| queued.id == player.id)
+            });
+            if queued {
+                return Err(RoomError::AlreadyQueued);
+            }
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, client_id: ClientId) {
+        self.entries.retain(|entry| entry.client.id() != client_id);
+    }
+
+    /// Takes out the players who found an opponent. Players who waited the longest are paired
+    /// first and come first in their pair
+    pub fn pairs(&mut self, now: Instant) -> Vec<(QueueEntry, QueueEntry)> {
+        let mut pairs = Vec::new();
+        let mut index = 0;
+        while index < self.entries.len() {
+            let first = &self.entries[index];
+            let opponent = (index + 1..self.entries.len())
+                .find(|other| compatible(first, &self.entries[*other], now));
+            match opponent {
+                Some(opponent) => {
+                    let second = self.entries.remove(opponent);
+                    let first = self.entries.remove(index);
+                    pairs.push((first, second));
+                }
+                None => index += 1,
+            }
+        }
+        pairs
+    }
+}
+
+/// Same time control, and each rating within the other player's range
+fn compatible(first: &QueueEntry, second: &QueueEntry, now: Instant) -> bool {
+    first.time_control == second.time_control
+        && first.accepts(second, now)
+        && second.accepts(first, now)
+}
@@ -1,9 +1,9 @@
-use actix::{Actor, ActorContext, Handler};
+use actix::{Actor, ActorContext, Handler, MessageResult};
 
 use crate::{commands::Command, messages::result::ResultMessage};
 
 use super::{
-    message::{self, Connect, Disconnect, RoomMessage},
+    message::{self, Connect, Disconnect, GameMoves, PlayedGame, Watch},
     Room,
 };
 
@@ -22,18 +22,16 @@ impl Actor for Room {
 impl Handler<Connect> for Room {
     type Result = ();
 
-    fn handle(&mut self, msg: Connect, _: &mut Self::Context) -> Self::Result {
-        let client = msg.0;
-        let result = self.add_client(client.clone());
+    fn handle(&mut self, msg: Connect, ctx: &mut Self::Context) -> Self::Result {
+        let client = msg.client;
+        let result = self.add_client(client.clone(), msg.session, msg.player);
         match result {
             Ok(full) => {
                 if full {
-                    let msg = RoomMessage {
-                        room_id: self.id,
-                        message: message::RoomMessageType::Full,
-                    };
-                    self.lobby.do_send(msg);
+                    self.send_lobby(message::RoomMessageType::Full);
                     self.start_game();
+                    self.schedule_flag(ctx);
+                    self.play_bot(ctx);
                 }
             }
             Err(e) => {
@@ -44,27 +42,33 @@ impl Handler<Connect> for Room {
     }
 }
 
+impl Handler<Watch> for Room {
+    type Result = ();
+
+    fn handle(&mut self, msg: Watch, _: &mut Self::Context) -> Self::Result {
+        let client = msg.0;
+        if let Err(e) = self.add_spectator(client.clone()) {
+            let msg = ResultMessage::error(self.id, client.id(), e.to_string());
+            client.result_addr().do_send(msg);
+        }
+    }
+}
+
 impl Handler<Disconnect> for Room {
     type Result = ();
 
     fn handle(&mut self, msg: Disconnect, ctx: &mut Self::Context) -> Self::Result {
         let client_id = msg.0;
+        let color = self.client_color(client_id);
         let result = self.remove_client(client_id);
         match result {
             Ok(empty) => {
                 if empty {
-                    let msg = RoomMessage {
-                        room_id: self.id,
-                        message: message::RoomMessageType::Empty,
-                    };
-                    self.lobby.do_send(msg);
+                    self.send_lobby(message::RoomMessageType::Empty);
                     ctx.stop();
-                } else {
-                    let msg = RoomMessage {
-                        room_id: self.id,
-                        message: message::RoomMessageType::Disconnect,
-                    };
-                    self.lobby.do_send(msg);
+                } else if let Some(color) = color {
+                    // The clock keeps running while the seat waits for the player
+                    self.hold_seat(color, client_id, ctx);
                 }
             }
             Err(_) => {}
@@ -75,7 +79,7 @@ impl Handler<Disconnect> for Room {
 impl Handler<message::Command> for Room {
     type Result = ();
 
-    fn handle(&mut self, msg: message::Command, _: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: message::Command, ctx: &mut Self::Context) -> Self::Result {
         let client_id = msg.client_id;
         let command = msg.command;
 
@@ -88,14 +92,6 @@ impl Handler<message::Command> for Room {
                 let result = self.promote(client_id, piece);
                 result
             }
-            Command::Reset(reset) => {
-                if reset {
-                    let result = self.reset(client_id);
-                    result
-                } else {
-                    Ok(())
-                }
-            }
             Command::Resign(resign) => {
                 if resign {
                     let result = self.resign(client_id);
@@ -104,8 +100,52 @@ impl Handler<message::Command> for Room {
                     Ok(())
                 }
             }
+            Command::OfferDraw(offer) => match offer {
+                true => self.offer_draw(client_id),
+                false => Ok(()),
+            },
+            Command::AcceptDraw(accept) => match accept {
+                true => self.accept_draw(client_id),
+                false => Ok(()),
+            },
+            Command::DeclineDraw(decline) => match decline {
+                true => self.decline_draw(client_id),
+                false => Ok(()),
+            },
+            Command::ClaimDraw(claim) => match claim {
+                true => self.claim_draw(client_id),
+                false => Ok(()),
+            },
+            Command::RequestTakeback(request) => match request {
+                true => self.request_takeback(client_id),
+                false => Ok(()),
+            },
+            Command::AcceptTakeback(accept) => match accept {
+                true => self.accept_takeback(client_id),
+                false => Ok(()),
+            },
+            Command::DeclineTakeback(decline) => match decline {
+                true => self.decline_takeback(client_id),
+                false => Ok(()),
+            },
+            Command::OfferRematch(offer) => match offer {
+                true => self.offer_rematch(client_id),
+                false => Ok(()),
+            },
+            Command::AcceptRematch(accept) => match accept {
+                true => self.accept_rematch(client_id),
+                false => Ok(()),
+            },
+            Command::DeclineRematch(decline) => match decline {
+                true => self.decline_rematch(client_id),
+                false => Ok(()),
+            },
         };
-        let client = self.client(client_id);
+        // Any command may have switched, stopped or restarted the clock
+        self.schedule_flag(ctx);
+        self.rate_game(ctx);
+        self.play_bot(ctx);
+        let client = self.member(client_id);
         let client = match client {
             Some(client) => client,
             None => {
@@ -121,3 +161,14 @@ impl Handler<message::Command> for Room {
         }
     }
 }
+
+impl Handler<GameMoves> for Room {
+    type Result = MessageResult<GameMoves>;
+
+    fn handle(&mut self, _: GameMoves, _: &mut Self::Context) -> Self::Result {
+        MessageResult(PlayedGame {
+            moves: self.game_moves(),
+            over: self.is_over(),
+        })
+    }
+}
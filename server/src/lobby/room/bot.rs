@@ -0,0 +1,160 @@
+use std::{error::Error, fmt::Display, time::Duration};
+
+use actix::{Actor, Handler};
+use ai::{
+    player::AiPlayer,
+    strength::{Level, LevelError},
+};
+use engine::{
+    board::Board,
+    piece::{position::Position, Color, Type},
+    player::Player,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{lobby::client::Client, messages::result::ResultMessage};
+
+/// Query parameters of `ws/room/create` for a game against the server
+#[derive(Debug, Default, Deserialize)]
+pub struct BotQuery {
+    /// Only `ai`
+    pub opponent: Option<String>,
+    pub level: Option<u8>,
+    /// Color of the player creating the room, white by default
+    pub color: Option<String>,
+}
+
+/// The bot of a room and the color it plays in the first game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotSettings {
+    pub level: Level,
+    pub color: Color,
+}
+
+impl BotSettings {
+    /// None when the room waits for a human opponent
+    pub fn from_query(query: &BotQuery) -> Result<Option<Self>, BotError> {
+        match query.opponent.as_deref() {
+            Some("ai") => {}
+            Some(_) => return Err(BotError::UnknownOpponent),
+            None if query.level.is_some() || query.color.is_some() => {
+                return Err(BotError::MissingOpponent)
+            }
+            None => return Ok(None),
+        }
+        let level = match query.level {
+            Some(level) => Level::new(level).map_err(BotError::Level)?,
+            None => Level::default(),
+        };
+        let color = match query.color.as_deref() {
+            Some("white") | None => Color::Black,
+            Some("black") => Color::White,
+            Some(_) => return Err(BotError::UnknownColor),
+        };
+        Ok(Some(Self { level, color }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotError {
+    UnknownOpponent,
+    MissingOpponent,
+    UnknownColor,
+    Level(LevelError),
+}
+
+impl Display for BotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BotError::UnknownOpponent => write!(f, "Unknown opponent, use ai"),
+            BotError::MissingOpponent => write!(f, "A level or a color needs opponent=ai"),
+            BotError::UnknownColor => write!(f, "Unknown color, use white or black"),
+            BotError::Level(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for BotError {}
+
+/// A seat played by the server. The room hands the bot's thinker to a blocking worker for
+/// every move, it is None until the move comes back
+pub struct Bot {
+    client: Client,
+    thinker: Option<Thinker>,
+}
+
+impl Bot {
+    pub fn new(level: Level) -> Self {
+        let addr = BotCon.start();
+        Self {
+            client: Client::with_recipient(Uuid::new_v4(), addr.recipient()),
+            thinker: Some(Thinker {
+                player: AiPlayer::new(level, StdRng::from_entropy()),
+            }),
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.client.id()
+    }
+
+    /// The thinker, unless it is busy with a move
+    pub fn take_thinker(&mut self) -> Option<Thinker> {
+        self.thinker.take()
+    }
+
+    pub fn return_thinker(&mut self, thinker: Thinker) {
+        self.thinker = Some(thinker);
+    }
+}
+
+/// Searches the bot's moves, this blocks for as long as its level searches or its clock allows
+pub struct Thinker {
+    player: AiPlayer<StdRng>,
+}
+
+impl Thinker {
+    /// `history` are the positions played before `board`, so the bot sees repetitions coming.
+    /// `time` is the most the move can take, None in untimed games. None when the bot has no
+    /// move
+    pub fn choose_move(
+        &mut self,
+        board: &Board,
+        history: &[Board],
+        time: Option<Duration>,
+    ) -> Option<(Position, Position)> {
+        self.player.set_history(history);
+        self.player.set_time(time);
+        self.player.choose_move(board)
+    }
+
+    /// The piece for the pawn the bot's move took to the last rank
+    pub fn choose_promotion(&mut self, board: &Board) -> Type {
+        self.player.choose_promotion(board)
+    }
+
+    /// The room played the bot's move, and its promotion
+    pub fn played(&mut self) {
+        self.player.played();
+    }
+}
+
+/// The connection of the bot's seat. The bot reads the game from the room, so the results sent
+/// to it are dropped
+struct BotCon;
+
+impl Actor for BotCon {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<ResultMessage> for BotCon {
+    type Result = ();
+
+    fn handle(&mut self, _: ResultMessage, _: &mut Self::Context) -> Self::Result {}
+}
@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+
+use engine::piece::Color;
+
+use super::time_control::{Delay, TimeControl};
+
+/// Both players' clocks, computed from the moment the running one was started instead of
+/// being decremented on a timer. At most one clock runs at a time, and none in untimed games
+#[derive(Clone, Copy)]
+pub struct Clock {
+    control: TimeControl,
+    white: Duration,
+    black: Duration,
+    /// The running clock and when it was started
+    running: Option<(Color, Instant)>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let time = control.base_duration().unwrap_or_default();
+        Self {
+            control,
+            white: time,
+            black: time,
+            running: None,
+        }
+    }
+
+    pub fn is_timed(&self) -> bool {
+        self.control.base.is_some()
+    }
+
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Starts the clock of `color`, stopping the other one
+    pub fn start(&mut self, color: Color, now: Instant) {
+        self.stop(now);
+        if self.is_timed() {
+            self.running = Some((color, now));
+        }
+    }
+
+    /// Stops the running clock, charging it the time since it was started
+    pub fn stop(&mut self, now: Instant) {
+        if let Some(color) = self.running() {
+            *self.time_mut(color) = self.remaining(color, now);
+            self.running = None;
+        }
+    }
+
+    /// The running player moved: their clock stops with the increment and the Bronstein delay
+    /// added, and the other one starts
+    pub fn switch(&mut self, now: Instant) {
+        let Some((color, started)) = self.running else {
+            return;
+        };
+        let used = now.saturating_duration_since(started);
+        let mut bonus = millis(self.control.increment);
+        if let Some(Delay::Bronstein(delay)) = self.control.delay {
+            bonus += used.min(millis(delay));
+        }
+        self.stop(now);
+        *self.time_mut(color) += bonus;
+        self.start(color.opponent(), now);
+    }
+
+    /// Time left to `color` at `now`, never below zero. A simple delay passes before the clock
+    /// goes down
+    pub fn remaining(&self, color: Color, now: Instant) -> Duration {
+        let time = match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        };
+        let Some((_, started)) = self.running.filter(|(running, _)| *running == color) else {
+            return time;
+        };
+        let mut used = now.saturating_duration_since(started);
+        if let Some(Delay::Simple(delay)) = self.control.delay {
+            used = used.saturating_sub(millis(delay));
+        }
+        time.saturating_sub(used)
+    }
+
+    /// Milliseconds left to white and black as the clients show them, None in untimed games
+    pub fn millis(&self, now: Instant) -> Option<(u32, u32)> {
+        let millis = |color| self.remaining(color, now).as_millis() as u32;
+        self.is_timed()
+            .then(|| (millis(Color::White), millis(Color::Black)))
+    }
+
+    /// Time until the running clock reaches zero
+    pub fn until_flag(&self, now: Instant) -> Option<Duration> {
+        let (color, started) = self.running?;
+        let mut until = self.remaining(color, now);
+        if let Some(Delay::Simple(delay)) = self.control.delay {
+            // The clock doesn't go down during the delay
+            until += (started + millis(delay)).saturating_duration_since(now);
+        }
+        Some(until)
+    }
+
+    /// The running clock when it reached zero
+    pub fn flagged(&self, now: Instant) -> Option<Color> {
+        self.running()
+            .filter(|color| self.remaining(*color, now).is_zero())
+    }
+
+    fn time_mut(&mut self, color: Color) -> &mut Duration {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+}
+
+fn millis(millis: u32) -> Duration {
+    Duration::from_millis(millis as u64)
+}
@@ -1,13 +1,26 @@
 use actix::Message;
+use ai::moves::Move;
 
 use crate::{
     commands::{self},
-    lobby::{client::Client, ClientId, RoomId},
+    lobby::{client::Client, ClientId, RoomId, SessionToken},
+    rating::Profile,
 };
 
 #[derive(Message)]
 #[rtype(result = "()")]
-pub struct Connect(pub Client);
+pub struct Connect {
+    pub client: Client,
+    /// Token of the seat the client takes back, see [`super::Room::add_client`]
+    pub session: Option<SessionToken>,
+    /// The registered player the client plays as
+    pub player: Option<Profile>,
+}
+
+/// Adds a spectator, see [`super::Room::add_spectator`]
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Watch(pub Client);
 
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -20,6 +33,16 @@ pub struct Command {
     pub command: commands::Command,
 }
 
+/// Asks for the moves of the game, see [`super::Room::game_moves`]
+#[derive(Message)]
+#[rtype(result = "PlayedGame")]
+pub struct GameMoves;
+
+pub struct PlayedGame {
+    pub moves: Vec<Move>,
+    pub over: bool,
+}
+
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct RoomMessage {
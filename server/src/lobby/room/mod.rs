@@ -1,23 +1,47 @@
-use actix::{Actor, AsyncContext, Recipient};
+use std::time::{Duration, Instant};
+
+use actix::{
+    Actor, ActorContext, ActorFutureExt, AsyncContext, Recipient, SpawnHandle, WrapFuture,
+};
+use actix_web::web;
+use ai::{moves::Move, search::SearchLimits};
 use engine::{
     board::Board,
+    game::{DrawReason, Game},
     piece::{position::Position, ChessPiece, Color, Type},
     result::OkMovement,
 };
 
-use crate::messages::result::{ConnectionType, ResultMessage};
+use crate::{
+    archive,
+    messages::result::{ConnectionType, OfferStatus, ResultMessage, TakebackStatus},
+    rating::{message::RateGame, Profile},
+};
 
-use self::message::RoomMessage;
+use self::{
+    bot::{Bot, BotSettings},
+    clock::Clock,
+    message::{RoomMessage, RoomMessageType},
+    time_control::{Delay, TimeControl},
+};
 
-use super::{client::Client, errors::RoomError, ClientId, RoomId};
+use super::{client::Client, errors::RoomError, ClientId, RoomId, SessionToken};
 
 use serde::Serialize;
 
 mod actor;
+pub mod bot;
+mod clock;
 pub mod message;
+pub mod time_control;
 
-//10 minutes in milliseconds
-const MAX_TIME: u32 = 600_000;
+#[cfg(test)]
+mod test;
+
+/// How often the running clock is sent to the clients, they count down on their own in between
+const CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+/// How long the seat of a disconnected player waits for them to come back with their token
+const RECONNECT_GRACE: Duration = Duration::from_secs(60);
 
 #[derive(Clone, Copy, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -36,76 +60,127 @@ pub struct TurnMove {
     pub turn_move_type: TurnMoveType,
 }
 
-#[derive(Clone)]
 pub struct Room {
     id: RoomId,
     white: Option<Client>,
     black: Option<Client>,
-    board: Board,
+    /// Receive every update of the game but can't play
+    spectators: Vec<Client>,
+    /// Session tokens of the white and black seats. A seat with a token and no client is held
+    /// for its player until they reconnect or [`RECONNECT_GRACE`] is over
+    sessions: [Option<SessionToken>; 2],
+    seat_releases: [Option<SpawnHandle>; 2],
+    /// Registered players of the white and black seats, rated games need both
+    profiles: [Option<Profile>; 2],
+    ratings: Recipient<RateGame>,
+    /// The result of the game was sent to the ratings
+    rated: bool,
+    game: Game,
     turn_number: u32,
     moves: Vec<TurnMove>,
+    /// The clocks, stopped, right before every move, put back when the move is taken back
+    clocks: Vec<Clock>,
     lobby: Recipient<RoomMessage>,
-    black_time: u32,
-    white_time: u32,
-    black_timer_ticking: bool,
-    white_timer_ticking: bool,
+    time_control: TimeControl,
+    clock: Clock,
+    /// Fires when the running clock reaches zero
+    flag_timer: Option<SpawnHandle>,
+    /// The player who ran out of time, the game is over once set
+    flagged: Option<Color>,
+    /// The player who left during the game and didn't come back in time, the game is over
+    /// once set
+    abandoned: Option<Color>,
+    /// Why the game was drawn, the game is over once set
+    draw: Option<DrawReason>,
+    /// The player whose draw offer waits for an answer
+    draw_offer: Option<Color>,
+    /// The player whose takeback request waits for an answer
+    takeback_request: Option<Color>,
+    /// The player who offered a rematch once the game was over
+    rematch_offer: Option<Color>,
+    /// The server's seat in a game against the AI
+    bot: Option<Bot>,
 }
 
 impl Room {
-    pub fn new(id: RoomId, lobby: Recipient<RoomMessage>) -> Self {
+    pub fn new(
+        id: RoomId,
+        lobby: Recipient<RoomMessage>,
+        ratings: Recipient<RateGame>,
+        time_control: TimeControl,
+    ) -> Self {
         Self {
             id,
             white: None,
             black: None,
-            board: Board::new(),
+            spectators: Vec::new(),
+            sessions: [None, None],
+            seat_releases: [None, None],
+            profiles: [None, None],
+            ratings,
+            rated: false,
+            game: Game::new(),
             turn_number: 1,
             moves: Vec::new(),
+            clocks: Vec::new(),
             lobby,
-            black_time: MAX_TIME,
-            white_time: MAX_TIME,
-            black_timer_ticking: false,
-            white_timer_ticking: false,
+            time_control,
+            clock: Clock::new(time_control),
+            flag_timer: None,
+            flagged: None,
+            abandoned: None,
+            draw: None,
+            draw_offer: None,
+            takeback_request: None,
+            rematch_offer: None,
+            bot: None,
         }
     }
 
-    /// Returns an error if the client is already in the room
-    /// Returns true if the room is full
-    pub fn add_client(&mut self, client: Client) -> Result<bool, RoomError> {
-        let client_id = client.id();
-        let mut inserted = false;
-        if self.white.is_some() && self.black.is_some() {
-            return Err(RoomError::RoomFull);
+    /// Seats the server's bot, the player creating the room gets the other color
+    pub fn with_bot(mut self, settings: BotSettings) -> Self {
+        let bot = Bot::new(settings.level);
+        match settings.color {
+            Color::White => self.white = Some(bot.client().clone()),
+            Color::Black => self.black = Some(bot.client().clone()),
         }
+        self.bot = Some(bot);
+        self
+    }
 
-        if self.client(client_id).is_some() {
+    /// Seats the client on the first free seat, or on the seat its session token holds after a
+    /// disconnection. A player taking back their seat keeps the profile they sat down with.
+    /// Returns an error if the client is already in the room
+    /// Returns true if the room is full
+    pub fn add_client(
+        &mut self,
+        client: Client,
+        session: Option<SessionToken>,
+        player: Option<Profile>,
+    ) -> Result<bool, RoomError> {
+        if self.member(client.id()).is_some() {
             return Err(RoomError::ClientAlreadyInRoom);
         }
 
-        let (white_client, white_msg_type) = match &self.white {
-            Some(white_client) => (white_client, ConnectionType::EnemyClient),
-            None => {
-                self.white = Some(client.clone());
-                inserted = true;
-                (&client, ConnectionType::SelfClient)
-            }
-        };
-
-        let black_message_opt = match (&self.black, inserted) {
-            (Some(black_client), true) => Some((black_client, ConnectionType::EnemyClient)),
-            (None, false) => {
-                self.black = Some(client.clone());
-
-                Some((&client, ConnectionType::SelfClient))
-            }
-            _ => None,
+        let color = match session {
+            Some(session) => self.held_seat(session).ok_or(RoomError::InvalidSession)?,
+            None => self.free_seat().ok_or(RoomError::RoomFull)?,
         };
+        if session.is_none() {
+            self.profiles[seat_index(color)] = player;
+        }
+        self.sessions[seat_index(color)] = Some(session.unwrap_or_else(SessionToken::new_v4));
+        match color {
+            Color::White => self.white = Some(client.clone()),
+            Color::Black => self.black = Some(client.clone()),
+        }
 
-        let white_msg = ResultMessage::connect(white_client.id(), white_msg_type, &self);
-        white_client.result_addr().do_send(white_msg);
+        let self_msg = ResultMessage::connect(client.id(), ConnectionType::SelfClient, self);
+        client.result_addr().do_send(self_msg);
 
-        if let Some((black_client, black_message)) = black_message_opt {
-            let black_msg = ResultMessage::connect(black_client.id(), black_message, &self);
-            black_client.result_addr().do_send(black_msg);
+        if let Some(enemy) = self.enemy(client.id()) {
+            let enemy_msg = ResultMessage::connect(enemy.id(), ConnectionType::EnemyClient, self);
+            enemy.result_addr().do_send(enemy_msg);
         }
 
         let full = self.white.is_some() && self.black.is_some();
@@ -113,17 +188,46 @@ impl Room {
         Ok(full)
     }
 
+    /// Adds a client that watches the game, whether the seats are taken or not. It gets the
+    /// board, the moves and the clocks right away
+    pub fn add_spectator(&mut self, client: Client) -> Result<(), RoomError> {
+        if self.member(client.id()).is_some() {
+            return Err(RoomError::ClientAlreadyInRoom);
+        }
+
+        let msg = ResultMessage::connect(client.id(), ConnectionType::Spectator, self);
+        client.result_addr().do_send(msg);
+        self.spectators.push(client);
+
+        Ok(())
+    }
+
     /// Returns an error if the client is not in the room
     /// Returns true if the room is empty
     pub fn remove_client(&mut self, client_id: ClientId) -> Result<bool, RoomError> {
-        let enemy = self.enemy(client_id);
+        if self.member(client_id).is_none() {
+            return Err(RoomError::ClientNotInRoom);
+        }
+        self.spectators
+            .retain(|spectator| spectator.id() != client_id);
+
+        // Leaving withdraws the rematch offer
+        let offered = self.client_color(client_id);
+        if let Some(color) = offered.filter(|color| self.rematch_offer == Some(*color)) {
+            self.rematch_offer = None;
+            let result =
+                ResultMessage::rematch_offer(self.id, client_id, color, OfferStatus::Expired);
+            self.send_room_result(result);
+        }
 
-        match enemy {
-            Some(enemy) => {
-                let con_msg = ResultMessage::disconnect(self.id, client_id);
-                enemy.result_addr().do_send(con_msg);
+        if self.client(client_id).is_some() {
+            let con_msg = ResultMessage::disconnect(self.id, client_id);
+            if let Some(enemy) = self.enemy(client_id) {
+                enemy.result_addr().do_send(con_msg.clone());
+            }
+            for spectator in &self.spectators {
+                spectator.result_addr().do_send(con_msg.clone());
             }
-            None => {}
         }
 
         match &self.white {
@@ -144,9 +248,7 @@ impl Room {
             None => {}
         };
 
-        let empty = self.white.is_none() && self.black.is_none();
-
-        return Ok(empty);
+        return Ok(self.is_empty());
     }
 
     pub fn make_move(
@@ -155,17 +257,23 @@ impl Room {
         from: Position,
         to: Position,
     ) -> Result<(), RoomError> {
+        // The flag timer may not have fired yet
+        self.check_flag();
         self.can_play(client_id)?;
 
         let turn_num = self.turn_number;
-        let turn = self.board.get_turn();
+        let turn = self.board().get_turn();
+        let now = Instant::now();
+        let mut clock = self.clock;
+        clock.stop(now);
 
-        let result = self.board.move_piece(from, to);
+        let result = self.game.move_piece(from, to);
 
         let ok_move = match result {
             Ok(movement) => {
-                let promotion = self.board.get_promotion_color();
-                let check = self.board.get_check();
+                self.clocks.push(clock);
+                let promotion = self.board().get_promotion_color();
+                let check = self.board().get_check();
                 let result = ResultMessage::movement(
                     self.id,
                     client_id,
@@ -188,12 +296,12 @@ impl Room {
 
         let turn_move = TurnMove {
             turn_number: turn_num,
-            piece: *self.board.get_piece_at(&to).unwrap(), //SAFE: We just moved this piece
+            piece: *self.board().get_piece_at(&to).unwrap(), //SAFE: We just moved this piece
             client_id,
             turn_move_type: TurnMoveType::Movement(ok_move),
         };
 
-        let promotion = self.board.get_promotion_color();
+        let promotion = self.board().get_promotion_color();
 
         match promotion {
             Some(_) => {}
@@ -208,20 +316,47 @@ impl Room {
             self.turn_number += 1;
         }
 
-        if let Some(winner) = self.board.get_winner() {
+        // Playing on declines the opponent's offer
+        if self.draw_offer == Some(turn.opponent()) {
+            self.draw_offer = None;
+            let result = ResultMessage::draw_offer(
+                self.id,
+                client_id,
+                turn.opponent(),
+                OfferStatus::Expired,
+            );
+            self.send_room_result(result);
+        }
+        // The request was about the moves before this one
+        if let Some(requested) = self.takeback_request.take() {
+            let result = ResultMessage::takeback_request(
+                self.id,
+                client_id,
+                requested,
+                TakebackStatus::Expired,
+            );
+            self.send_room_result(result);
+        }
+
+        if let Some(winner) = self.board().get_winner() {
             let result = ResultMessage::winner(self.id, client_id, winner);
             self.send_room_result(result);
+            self.stop_game();
+            self.archive_game();
+        } else if let Some(reason) = self.game.draw() {
+            self.end_in_draw(client_id, reason);
         }
 
         Ok(())
     }
 
     pub fn promote(&mut self, client_id: ClientId, piece: Type) -> Result<(), RoomError> {
+        self.check_flag();
         self.can_play(client_id)?;
         let color = self
             .client_color(client_id)
             .ok_or(RoomError::ClientNotInRoom)?;
-        let turn = self.board.get_turn();
+        let turn = self.board().get_turn();
         let turn_num = {
             if turn == Color::White {
                 self.turn_number
@@ -239,11 +374,11 @@ impl Room {
             Type::King => ChessPiece::create_king(color),
         };
 
-        let result = self.board.promote(piece);
+        let result = self.game.promote(piece);
 
         let promotion = match result {
             Ok(promotion) => {
-                let check = self.board.get_check();
+                let check = self.board().get_check();
                 let result = ResultMessage::promotion(self.id, client_id, promotion, check);
 
                 self.send_room_result(result);
@@ -271,38 +406,174 @@ impl Room {
 
         self.moves.push(turn_move);
 
+        // A promotion can leave the opponent without a move
+        if let Some(reason) = self.game.draw() {
+            self.end_in_draw(client_id, reason);
+        }
+
         Ok(())
     }
 
     //REFACTOR
     pub fn resign(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
         self.can_play(client_id)?;
 
-        self.board.resign();
+        self.game.resign();
 
-        if let Some(winner) = self.board.get_winner() {
+        if let Some(winner) = self.board().get_winner() {
             let result = ResultMessage::winner(self.id, client_id, winner);
             self.send_room_result(result);
             self.stop_game();
+            self.archive_game();
         }
 
         Ok(())
     }
 
-    //REFACTOR
-    pub fn reset(&mut self, client_id: ClientId) -> Result<(), RoomError> {
-        self.can_play(client_id)?;
+    /// Offers a draw to the opponent, or accepts theirs when they offered first
+    pub fn offer_draw(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
+        let color = self.player_color(client_id)?;
+        if self.is_over() {
+            return Err(RoomError::GameOver);
+        }
 
-        if self.board.get_winner().is_none() {
-            return Err(RoomError::GameNotOver);
-        };
+        match self.draw_offer {
+            Some(offered) if offered == color => Err(RoomError::DrawAlreadyOffered),
+            Some(_) => {
+                self.end_in_draw(client_id, DrawReason::Agreement);
+                Ok(())
+            }
+            None => {
+                self.draw_offer = Some(color);
+                let result =
+                    ResultMessage::draw_offer(self.id, client_id, color, OfferStatus::Offered);
+                self.send_room_result(result);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn accept_draw(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
+        self.opponent_offer(client_id)?;
+        self.end_in_draw(client_id, DrawReason::Agreement);
+        Ok(())
+    }
+
+    pub fn decline_draw(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
+        let offered = self.opponent_offer(client_id)?;
+        self.draw_offer = None;
+        let result = ResultMessage::draw_offer(self.id, client_id, offered, OfferStatus::Declined);
+        self.send_room_result(result);
+        Ok(())
+    }
+
+    /// Draws by threefold repetition or the fifty-move rule, which only end the game when a
+    /// player claims them
+    pub fn claim_draw(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
+        self.player_color(client_id)?;
+        if self.is_over() {
+            return Err(RoomError::GameOver);
+        }
 
-        self.board.reset();
+        let reason = self.game.claimable_draw().ok_or(RoomError::NoDrawToClaim)?;
+        self.end_in_draw(client_id, reason);
+        Ok(())
+    }
+
+    /// Asks the opponent to take back the client's last move, and the opponent's reply to it
+    /// when they already played one
+    pub fn request_takeback(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
+        let color = self.player_color(client_id)?;
+        if self.is_over() {
+            return Err(RoomError::GameOver);
+        }
+        if self.takeback_request.is_some() {
+            return Err(RoomError::TakebackAlreadyRequested);
+        }
+        self.takeback_plies(color)
+            .ok_or(RoomError::NoMoveToTakeBack)?;
+
+        self.takeback_request = Some(color);
+        let result =
+            ResultMessage::takeback_request(self.id, client_id, color, TakebackStatus::Requested);
+        self.send_room_result(result);
+        Ok(())
+    }
 
-        let result = ResultMessage::reset(self.id, client_id);
+    pub fn accept_takeback(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
+        let requested = self.opponent_request(client_id)?;
+        let plies = self
+            .takeback_plies(requested)
+            .ok_or(RoomError::NoMoveToTakeBack)?;
+        self.takeback_request = None;
+        self.take_back(plies);
 
+        let result = ResultMessage::takeback(self.id, client_id, requested, plies, self);
         self.send_room_result(result);
+        if self.clock.is_timed() {
+            self.send_timer(Color::White);
+            self.send_timer(Color::Black);
+        }
+        Ok(())
+    }
 
+    pub fn decline_takeback(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.check_flag();
+        let requested = self.opponent_request(client_id)?;
+        self.takeback_request = None;
+        let result = ResultMessage::takeback_request(
+            self.id,
+            client_id,
+            requested,
+            TakebackStatus::Declined,
+        );
+        self.send_room_result(result);
+        Ok(())
+    }
+
+    /// Offers a new game once this one is over, or accepts the opponent's offer when they
+    /// offered first
+    pub fn offer_rematch(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        let color = self.player_color(client_id)?;
+        if !self.is_over() {
+            return Err(RoomError::GameNotOver);
+        }
+
+        match self.rematch_offer {
+            Some(offered) if offered == color => Err(RoomError::RematchAlreadyOffered),
+            Some(_) => {
+                self.rematch(client_id);
+                Ok(())
+            }
+            None => {
+                self.rematch_offer = Some(color);
+                let result =
+                    ResultMessage::rematch_offer(self.id, client_id, color, OfferStatus::Offered);
+                self.send_room_result(result);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn accept_rematch(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        self.opponent_rematch_offer(client_id)?;
+        self.rematch(client_id);
+        Ok(())
+    }
+
+    pub fn decline_rematch(&mut self, client_id: ClientId) -> Result<(), RoomError> {
+        let offered = self.opponent_rematch_offer(client_id)?;
+        self.rematch_offer = None;
+        let result =
+            ResultMessage::rematch_offer(self.id, client_id, offered, OfferStatus::Declined);
+        self.send_room_result(result);
         Ok(())
     }
 
@@ -328,28 +599,87 @@ impl Room {
         None
     }
 
+    fn board(&self) -> &Board {
+        self.game.get_board()
+    }
+
     pub fn pieces(&self) -> [[Option<ChessPiece>; 8]; 8] {
-        *self.board.get_pieces()
+        *self.board().get_pieces()
     }
 
     pub fn moves(&self) -> &Vec<TurnMove> {
         &self.moves
     }
 
+    /// The moves played so far with their promotions, as the AI reads them
+    pub fn game_moves(&self) -> Vec<Move> {
+        let mut game: Vec<Move> = Vec::with_capacity(self.moves.len());
+        for turn_move in &self.moves {
+            match turn_move.turn_move_type {
+                TurnMoveType::Movement(movement) => {
+                    let (from, to) = match movement {
+                        OkMovement::Valid(movement)
+                        | OkMovement::Capture(movement)
+                        | OkMovement::EnPassant(movement)
+                        | OkMovement::InitialDoubleAdvance(movement) => movement,
+                        OkMovement::Castling(king, _) => king,
+                    };
+                    game.push(Move::new(from, to));
+                }
+                TurnMoveType::Promotion { to, .. } => {
+                    if let Some(last) = game.last_mut() {
+                        last.promotion = Some(to.get_type());
+                    }
+                }
+            }
+        }
+        game
+    }
+
+    pub fn turn_number(&self) -> u32 {
+        self.turn_number
+    }
+
     pub fn id(&self) -> RoomId {
         self.id
     }
 
     pub fn check(&self) -> Option<Color> {
-        self.board.get_check()
+        self.board().get_check()
     }
 
     pub fn promotion(&self) -> Option<Color> {
-        self.board.get_promotion_color()
+        self.board().get_promotion_color()
+    }
+
+    /// Milliseconds left to white and black, None in untimed games
+    pub fn timers(&self) -> Option<(u32, u32)> {
+        self.clock.millis(Instant::now())
+    }
+
+    /// The registered player on the seat of `color`
+    pub fn profile(&self, color: Color) -> Option<&Profile> {
+        self.profiles[seat_index(color)].as_ref()
     }
 
-    pub fn timers(&self) -> (u32, u32) {
-        (self.white_time, self.black_time)
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// The winner by checkmate, resignation, on time or by abandonment
+    pub fn winner(&self) -> Option<Color> {
+        match (self.flagged, self.abandoned) {
+            (Some(flagged), _) => self.timeout_winner(flagged),
+            (None, Some(abandoned)) => Some(abandoned.opponent()),
+            (None, None) => self.board().get_winner(),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.flagged.is_some()
+            || self.abandoned.is_some()
+            || self.draw.is_some()
+            || self.board().get_winner().is_some()
     }
 
     pub fn client(&self, client_id: ClientId) -> Option<&Client> {
@@ -376,6 +706,21 @@ impl Room {
         }
     }
 
+    /// The token that gives the client's seat back after a disconnection
+    pub fn session(&self, client_id: ClientId) -> Option<SessionToken> {
+        let color = self.client_color(client_id)?;
+        self.sessions[seat_index(color)]
+    }
+
+    /// A player or a spectator
+    pub fn member(&self, client_id: ClientId) -> Option<&Client> {
+        self.client(client_id).or_else(|| {
+            self.spectators
+                .iter()
+                .find(|spectator| spectator.id() == client_id)
+        })
+    }
+
     pub fn enemy(&self, client_id: ClientId) -> Option<&Client> {
         self.client(client_id)?;
         let enemy = match &self.white {
@@ -392,14 +737,27 @@ impl Room {
         enemy
     }
 
-    fn can_play(&self, client_id: ClientId) -> Result<(), RoomError> {
+    /// The color of a seated player facing an opponent, who may be away on a held seat
+    fn player_color(&self, client_id: ClientId) -> Result<Color, RoomError> {
+        if self.client(client_id).is_none() && self.member(client_id).is_some() {
+            return Err(RoomError::Spectator);
+        }
         let color = self
             .client_color(client_id)
             .ok_or(RoomError::ClientNotInRoom)?;
-        let turn = self.board.get_turn();
-        if self.white.is_none() || self.black.is_none() {
+        if !self.is_seated(color.opponent()) {
             return Err(RoomError::NotEnoughPlayers);
         }
+
+        Ok(color)
+    }
+
+    fn can_play(&self, client_id: ClientId) -> Result<(), RoomError> {
+        let color = self.player_color(client_id)?;
+        let turn = self.board().get_turn();
+        if self.is_over() {
+            return Err(RoomError::GameOver);
+        }
         if color != turn {
             return Err(RoomError::NotYourTurn);
         }
@@ -407,6 +765,124 @@ impl Room {
         Ok(())
     }
 
+    /// The color of the opponent of the client, when they offered a draw
+    fn opponent_offer(&self, client_id: ClientId) -> Result<Color, RoomError> {
+        let color = self.player_color(client_id)?;
+        if self.is_over() {
+            return Err(RoomError::GameOver);
+        }
+        let opponent = color.opponent();
+        match self.draw_offer == Some(opponent) {
+            true => Ok(opponent),
+            false => Err(RoomError::NoDrawOffer),
+        }
+    }
+
+    /// The color of the opponent of the client, when they asked for a takeback
+    fn opponent_request(&self, client_id: ClientId) -> Result<Color, RoomError> {
+        let color = self.player_color(client_id)?;
+        if self.is_over() {
+            return Err(RoomError::GameOver);
+        }
+        let opponent = color.opponent();
+        match self.takeback_request == Some(opponent) {
+            true => Ok(opponent),
+            false => Err(RoomError::NoTakebackRequest),
+        }
+    }
+
+    /// How many plies take back the last move of `color`: one if it was the last move played,
+    /// two if the opponent already replied. None if they didn't move yet
+    fn takeback_plies(&self, color: Color) -> Option<usize> {
+        self.moves
+            .iter()
+            .rev()
+            .filter(|turn_move| matches!(turn_move.turn_move_type, TurnMoveType::Movement(_)))
+            .position(|turn_move| turn_move.piece.get_color() == color)
+            .map(|index| index + 1)
+    }
+
+    /// Undoes the last plies on the board, their moves and promotions, and gives the clocks
+    /// back the times they had before them
+    fn take_back(&mut self, plies: usize) {
+        for _ in 0..plies {
+            self.game.undo();
+            // The promotion goes with the move that reached the last rank
+            if let Some(TurnMoveType::Promotion { .. }) =
+                self.moves.last().map(|turn_move| turn_move.turn_move_type)
+            {
+                self.moves.pop();
+            }
+            self.moves.pop();
+            if let Some(clock) = self.clocks.pop() {
+                self.clock = clock;
+            }
+        }
+        let black_moves = self
+            .moves
+            .iter()
+            .filter(|turn_move| {
+                matches!(turn_move.turn_move_type, TurnMoveType::Movement(_))
+                    && turn_move.piece.get_color() == Color::Black
+            })
+            .count();
+        self.turn_number = black_moves as u32 + 1;
+        self.start_game();
+    }
+
+    /// The color of the opponent of the client, when they offered a rematch
+    fn opponent_rematch_offer(&self, client_id: ClientId) -> Result<Color, RoomError> {
+        let color = self.player_color(client_id)?;
+        if !self.is_over() {
+            return Err(RoomError::GameNotOver);
+        }
+        let opponent = color.opponent();
+        match self.rematch_offer == Some(opponent) {
+            true => Ok(opponent),
+            false => Err(RoomError::NoRematchOffer),
+        }
+    }
+
+    /// Starts a new game with the same time control, the players swapping colors along with
+    /// their session tokens
+    fn rematch(&mut self, client_id: ClientId) {
+        std::mem::swap(&mut self.white, &mut self.black);
+        self.sessions.swap(0, 1);
+        self.profiles.swap(0, 1);
+        self.rated = false;
+
+        self.game = Game::new();
+        self.moves.clear();
+        self.clocks.clear();
+        self.turn_number = 1;
+        self.clock = Clock::new(self.time_control);
+        self.flagged = None;
+        self.abandoned = None;
+        self.draw = None;
+        self.draw_offer = None;
+        self.takeback_request = None;
+        self.rematch_offer = None;
+        self.start_game();
+
+        let white = self.white.as_ref().map(|client| client.id());
+        let black = self.black.as_ref().map(|client| client.id());
+        let result = ResultMessage::rematch(self.id, client_id, white, black);
+        self.send_room_result(result);
+        if self.clock.is_timed() {
+            self.send_timer(Color::White);
+            self.send_timer(Color::Black);
+        }
+    }
+
+    fn end_in_draw(&mut self, client_id: ClientId, reason: DrawReason) {
+        self.draw = Some(reason);
+        self.draw_offer = None;
+        self.stop_game();
+        let result = ResultMessage::draw(self.id, client_id, reason);
+        self.send_room_result(result);
+        self.archive_game();
+    }
+
     fn send_room_result(&self, msg: ResultMessage) {
         match &self.white {
             Some(white_client) => {
@@ -417,84 +893,351 @@ impl Room {
 
         match &self.black {
             Some(black_client) => {
-                black_client.result_addr().do_send(msg);
+                black_client.result_addr().do_send(msg.clone());
             }
             None => {}
         };
+
+        for spectator in &self.spectators {
+            spectator.result_addr().do_send(msg.clone());
+        }
     }
 
+    /// Sends the running clock to both clients every [`CLOCK_SYNC_INTERVAL`]
     fn start_timer(&self, ctx: &mut <Self as Actor>::Context) {
-        ctx.run_interval(std::time::Duration::from_millis(1), |act, _| {
-            if act.white.is_none() || act.black.is_none() {
-                return;
+        ctx.run_interval(CLOCK_SYNC_INTERVAL, |act, _| {
+            if let Some(color) = act.clock.running() {
+                act.send_timer(color);
+            }
+        });
+    }
+
+    /// Arms a timer for the moment the running clock reaches zero, replacing the previous one
+    fn schedule_flag(&mut self, ctx: &mut <Self as Actor>::Context) {
+        if let Some(handle) = self.flag_timer.take() {
+            ctx.cancel_future(handle);
+        }
+        let Some(until_flag) = self.clock.until_flag(Instant::now()) else {
+            return;
+        };
+        let handle = ctx.run_later(until_flag, |act, ctx| {
+            act.flag_timer = None;
+            match act.check_flag() {
+                true => act.rate_game(ctx),
+                false => act.schedule_flag(ctx),
             }
+        });
+        self.flag_timer = Some(handle);
+    }
 
-            //>0 to avoid underflow
-            if act.black_timer_ticking && act.black_time > 0 {
-                act.black_time -= 1;
-                let black = &act.black;
-                let white = &act.white;
-                let black_id = black.as_ref().map(|c| c.id());
-                let timer = ResultMessage::timer(act.id, black_id, act.black_time, Color::Black);
-                match white {
-                    Some(white) => {
-                        white.result_addr().do_send(timer.clone());
-                    }
-                    None => {}
-                }
-                match black {
-                    Some(black) => {
-                        black.result_addr().do_send(timer);
+    /// Lets the bot answer its opponent and, on its turn, search its move on a blocking worker.
+    /// The move is dropped if the position changed while the bot was thinking
+    fn play_bot(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let Some(bot_id) = self.bot.as_ref().map(Bot::id) else {
+            return;
+        };
+        let Some(color) = self.client_color(bot_id) else {
+            return;
+        };
+        // The bot lets its opponent take moves back and play again, but plays every game out
+        let player = Some(color.opponent());
+        if self.takeback_request == player {
+            let _ = self.accept_takeback(bot_id);
+        }
+        if self.draw_offer == player {
+            let _ = self.decline_draw(bot_id);
+        }
+        if self.rematch_offer == player {
+            let _ = self.accept_rematch(bot_id);
+        }
+        // A takeback or a rematch set the clock back after the flag was scheduled
+        self.schedule_flag(ctx);
+
+        // A rematch swapped the colors
+        let Some(color) = self.client_color(bot_id) else {
+            return;
+        };
+        // The clock of a player away on a held seat keeps running, so the bot plays on
+        if self.is_over() || !self.is_seated(color.opponent()) || self.board().get_turn() != color {
+            return;
+        }
+        let Some(mut thinker) = self.bot.as_mut().and_then(Bot::take_thinker) else {
+            return;
+        };
+
+        let board = *self.board();
+        let history: Vec<Board> = self.game.history().copied().collect();
+        let time = self.bot_time(color);
+        let search = web::block(move || {
+            let movement = thinker.choose_move(&board, &history, time);
+            (thinker, movement)
+        });
+        ctx.spawn(search.into_actor(self).map(move |result, act, ctx| {
+            let Ok((mut thinker, movement)) = result else {
+                return;
+            };
+            if let (true, Some((from, to))) = (*act.board() == board, movement) {
+                if act.make_move(bot_id, from, to).is_ok() {
+                    if act.promotion() == Some(color) {
+                        let piece = thinker.choose_promotion(act.board());
+                        let _ = act.promote(bot_id, piece);
                     }
-                    None => {}
+                    thinker.played();
                 }
             }
+            if let Some(bot) = act.bot.as_mut() {
+                bot.return_thinker(thinker);
+            }
+            act.schedule_flag(ctx);
+            act.rate_game(ctx);
+            act.play_bot(ctx);
+        }));
+    }
 
-            //>0 to avoid underflow
-            if act.white_timer_ticking && act.white_time > 0 {
-                act.white_time -= 1;
-                let black = &act.black;
-                let white = &act.white;
-                let white_id = white.as_ref().map(|c| c.id());
-                let timer = ResultMessage::timer(act.id, white_id, act.white_time, Color::White);
-                match white {
-                    Some(white) => {
-                        white.result_addr().do_send(timer.clone());
-                    }
-                    None => {}
-                }
-                match black {
-                    Some(black) => {
-                        black.result_addr().do_send(timer);
+    /// The most the bot's move can take from its clock, with the increment or the delay it gets
+    /// back. None in untimed games
+    fn bot_time(&self, color: Color) -> Option<Duration> {
+        if !self.clock.is_timed() {
+            return None;
+        }
+        let remaining = self.clock.remaining(color, Instant::now());
+        let delay = match self.time_control.delay {
+            Some(Delay::Bronstein(delay) | Delay::Simple(delay)) => delay,
+            None => 0,
+        };
+        let increment = Duration::from_millis(self.time_control.increment as u64 + delay as u64);
+        SearchLimits::from_clock(remaining, increment).time
+    }
+
+    /// Sends the result of a finished game between two registered players to the ratings, once,
+    /// and the new ratings to the room when they come back
+    fn rate_game(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let Some(msg) = self.rate_message() else {
+            return;
+        };
+        let [Some(white), Some(black)] = &self.profiles else {
+            return;
+        };
+        self.rated = true;
+
+        let category = msg.category;
+        let before = (
+            white.ratings.get(category).rating,
+            black.ratings.get(category).rating,
+        );
+        let rated = self.ratings.send(msg).into_actor(self);
+        ctx.spawn(rated.map(move |result, act, _| {
+            let Ok(Some((white, black))) = result else {
+                return;
+            };
+            // The players may have swapped seats for a rematch meanwhile
+            for profile in act.profiles.iter_mut().flatten() {
+                for updated in [&white, &black] {
+                    if profile.id == updated.id {
+                        *profile = updated.clone();
                     }
-                    None => {}
                 }
             }
+            let client_id = act.white.as_ref().or(act.black.as_ref()).map(Client::id);
+            if let Some(client_id) = client_id {
+                let result =
+                    ResultMessage::rated(act.id, client_id, category, &white, &black, before);
+                act.send_room_result(result);
+            }
+        }));
+    }
+
+    /// The result of the game for the ratings, once it is over and not rated yet. Only timed
+    /// games between two registered players who both moved are rated, the bot has no profile
+    fn rate_message(&self) -> Option<RateGame> {
+        if self.rated || !self.is_over() {
+            return None;
+        }
+        let moved = |color| {
+            self.moves.iter().any(|turn_move| {
+                matches!(turn_move.turn_move_type, TurnMoveType::Movement(_))
+                    && turn_move.piece.get_color() == color
+            })
+        };
+        if !moved(Color::White) || !moved(Color::Black) {
+            return None;
+        }
+        let [Some(white), Some(black)] = &self.profiles else {
+            return None;
+        };
+        Some(RateGame {
+            white: white.id,
+            black: black.id,
+            category: self.time_control.category()?,
+            winner: self.winner(),
+        })
+    }
+
+    /// Ends the game when the running clock is out of time, returns true if it did. The
+    /// opponent wins when they could still mate, otherwise it's a draw
+    fn check_flag(&mut self) -> bool {
+        let Some(flagged) = self.clock.flagged(Instant::now()) else {
+            return false;
+        };
+        self.stop_game();
+        self.flagged = Some(flagged);
+
+        // The flagged player may be away on a held seat, the room learns the result anyway
+        let client_id = self.color_client(flagged).map(Client::id);
+        let winner = self.timeout_winner(flagged);
+        let result = ResultMessage::timeout(self.id, client_id, flagged, winner);
+        self.send_room_result(result);
+        self.archive_game();
+        true
+    }
+
+    fn timeout_winner(&self, flagged: Color) -> Option<Color> {
+        let winner = flagged.opponent();
+        self.game.has_mating_material(winner).then_some(winner)
+    }
+
+    fn send_timer(&self, color: Color) {
+        let client_id = self.color_client(color).map(|c| c.id());
+        let time = self.clock.remaining(color, Instant::now()).as_millis() as u32;
+        let timer = ResultMessage::timer(self.id, client_id, time, color);
+        self.send_room_result(timer);
+    }
+
+    /// Keeps the seat of a player who just disconnected for [`RECONNECT_GRACE`]. A game still
+    /// going on when it's over is lost by abandonment
+    fn hold_seat(&mut self, color: Color, client_id: ClientId, ctx: &mut <Self as Actor>::Context) {
+        if let Some(handle) = self.seat_releases[seat_index(color)].take() {
+            ctx.cancel_future(handle);
+        }
+        let handle = ctx.run_later(RECONNECT_GRACE, move |act, ctx| {
+            act.seat_releases[seat_index(color)] = None;
+            if act.color_client(color).is_some() {
+                return;
+            }
+            // The flag timer may not have fired yet
+            act.check_flag();
+            if act.in_progress() && act.is_seated(color.opponent()) {
+                act.abandon(color, client_id);
+            }
+            // Rated before the profile of the seat goes
+            act.rate_game(ctx);
+            act.sessions[seat_index(color)] = None;
+            act.profiles[seat_index(color)] = None;
+            if act.is_empty() {
+                act.send_lobby(RoomMessageType::Empty);
+                ctx.stop();
+            } else {
+                act.send_lobby(RoomMessageType::Disconnect);
+            }
+        });
+        self.seat_releases[seat_index(color)] = Some(handle);
+    }
+
+    /// Ends the game in favor of the opponent of `color`, who left the seat for good
+    fn abandon(&mut self, color: Color, client_id: ClientId) {
+        self.stop_game();
+        self.abandoned = Some(color);
+        self.draw_offer = None;
+        self.takeback_request = None;
+        let result = ResultMessage::abandoned(self.id, client_id, color, color.opponent());
+        self.send_room_result(result);
+        self.archive_game();
+    }
+
+    /// A player sits on the seat of `color`, or holds it while away from a game in progress
+    fn is_seated(&self, color: Color) -> bool {
+        self.color_client(color).is_some()
+            || self.sessions[seat_index(color)].is_some() && self.in_progress()
+    }
+
+    /// The game started and isn't over. Untimed games start with the first move
+    fn in_progress(&self) -> bool {
+        !self.is_over() && (self.clock.running().is_some() || !self.moves.is_empty())
+    }
+
+    /// The seat whose player left with this token
+    fn held_seat(&self, session: SessionToken) -> Option<Color> {
+        [Color::White, Color::Black].into_iter().find(|color| {
+            self.color_client(*color).is_none()
+                && self.sessions[seat_index(*color)] == Some(session)
+        })
+    }
+
+    /// The first seat nobody sits on or holds
+    fn free_seat(&self) -> Option<Color> {
+        [Color::White, Color::Black].into_iter().find(|color| {
+            self.color_client(*color).is_none() && self.sessions[seat_index(*color)].is_none()
+        })
+    }
+
+    /// No player but the bot, no held seat and no spectator
+    fn is_empty(&self) -> bool {
+        let seated = |client: &Option<Client>| {
+            client
+                .as_ref()
+                .is_some_and(|client| !self.is_bot(client.id()))
+        };
+        !seated(&self.white)
+            && !seated(&self.black)
+            && self.sessions.iter().all(Option::is_none)
+            && self.spectators.is_empty()
+    }
+
+    fn is_bot(&self, client_id: ClientId) -> bool {
+        self.bot.as_ref().is_some_and(|bot| bot.id() == client_id)
+    }
+
+    fn send_lobby(&self, message: RoomMessageType) {
+        self.lobby.do_send(RoomMessage {
+            room_id: self.id,
+            message,
         });
     }
 
+    fn color_client(&self, color: Color) -> Option<&Client> {
+        match color {
+            Color::White => self.white.as_ref(),
+            Color::Black => self.black.as_ref(),
+        }
+    }
+
+    /// Starts or resumes the clock of the side to move once both players are in
     fn start_game(&mut self) {
         //not enough players
         if self.black.is_none() || self.white.is_none() {
             return;
         }
 
-        //game already started
-        if self.black_timer_ticking || self.white_timer_ticking {
+        //game already started or over
+        if self.clock.running().is_some() || self.is_over() {
             return;
         }
 
-        self.black_timer_ticking = false;
-        self.white_timer_ticking = true;
+        self.clock.start(self.board().get_turn(), Instant::now());
     }
 
+    /// Stops the clock of the player who moved and starts the opponent's. Both clocks are sent
+    /// so the clients pick up the exact times
     fn change_turn(&mut self) {
-        self.black_timer_ticking = !self.black_timer_ticking;
-        self.white_timer_ticking = !self.white_timer_ticking;
+        self.clock.switch(Instant::now());
+        if self.clock.is_timed() {
+            self.send_timer(Color::White);
+            self.send_timer(Color::Black);
+        }
     }
 
     fn stop_game(&mut self) {
-        self.black_timer_ticking = false;
-        self.white_timer_ticking = false;
+        self.clock.stop(Instant::now());
+    }
+
+    fn archive_game(&self) {
+        archive::archive_game(self.id, self.game_moves(), self.winner());
+    }
+}
+
+fn seat_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
     }
 }
@@ -0,0 +1,478 @@
+use std::time::{Duration, Instant};
+
+use actix::{Actor, Handler, Message, MessageResult};
+use ai::strength::Level;
+use engine::piece::Color;
+use uuid::Uuid;
+
+use crate::{
+    commands::Command,
+    lobby::{client::Client, RoomId},
+    messages::result::ResultMessage,
+    rating::{message::RateGame, Players, Profile},
+};
+
+use super::{
+    bot::BotSettings,
+    clock::Clock,
+    message::{self, GameMoves, RoomMessage},
+    time_control::{Category, Delay, TimeControl, TimeControlError, TimeControlQuery},
+    Room,
+};
+
+/// Stands for the lobby, the ratings and the clients' connections
+struct Sink;
+
+impl Actor for Sink {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<RoomMessage> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, _: RoomMessage, _: &mut Self::Context) -> Self::Result {}
+}
+
+impl Handler<RateGame> for Sink {
+    type Result = MessageResult<RateGame>;
+
+    fn handle(&mut self, _: RateGame, _: &mut Self::Context) -> Self::Result {
+        MessageResult(None)
+    }
+}
+
+impl Handler<ResultMessage> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, _: ResultMessage, _: &mut Self::Context) -> Self::Result {}
+}
+
+/// Keeps the results sent to a client
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl Actor for Recorder {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<ResultMessage> for Recorder {
+    type Result = ();
+
+    fn handle(&mut self, msg: ResultMessage, _: &mut Self::Context) -> Self::Result {
+        self.0.push(serde_json::to_string(&msg).unwrap());
+    }
+}
+
+/// The results received so far
+#[derive(Message)]
+#[rtype(result = "Vec<String>")]
+struct Received;
+
+impl Handler<Received> for Recorder {
+    type Result = MessageResult<Received>;
+
+    fn handle(&mut self, _: Received, _: &mut Self::Context) -> Self::Result {
+        MessageResult(std::mem::take(&mut self.0))
+    }
+}
+
+fn new_room(time_control: TimeControl) -> Room {
+    let sink = Sink.start();
+    Room::new(
+        RoomId::new_v4(),
+        sink.clone().recipient(),
+        sink.recipient(),
+        time_control,
+    )
+}
+
+fn client() -> Client {
+    Client::with_recipient(Uuid::new_v4(), Sink.start().recipient())
+}
+
+/// Seats two clients, white then black, plays `moves` and lets the side to move resign
+fn resigned_game(
+    mut room: Room,
+    white: Option<Profile>,
+    black: Option<Profile>,
+    moves: &[(&str, &str)],
+) -> Room {
+    let clients = [client(), client()];
+    room.add_client(clients[0].clone(), None, white).unwrap();
+    room.add_client(clients[1].clone(), None, black).unwrap();
+    for (index, (from, to)) in moves.iter().enumerate() {
+        let (from, to) = (from.parse().unwrap(), to.parse().unwrap());
+        room.make_move(clients[index % 2].id(), from, to).unwrap();
+    }
+    room.resign(clients[moves.len() % 2].id()).unwrap();
+    room
+}
+
+/// A move of each side
+const OPENING: [(&str, &str); 2] = [("e2", "e4"), ("e7", "e5")];
+
+#[actix::test]
+async fn rated_game() {
+    let mut players = Players::default();
+    let white = players.register("white").unwrap().1;
+    let black = players.register("black").unwrap().1;
+
+    let room = resigned_game(
+        new_room(TimeControl::blitz()),
+        Some(white.clone()),
+        Some(black.clone()),
+        &OPENING,
+    );
+    let msg = room.rate_message().unwrap();
+    assert_eq!((msg.white, msg.black), (white.id, black.id));
+    assert_eq!(msg.winner, Some(Color::Black));
+}
+
+#[actix::test]
+async fn unrated_games() {
+    let mut players = Players::default();
+    let white = players.register("white").unwrap().1;
+    let black = players.register("black").unwrap().1;
+
+    // Untimed
+    let room = resigned_game(
+        new_room(TimeControl::untimed()),
+        Some(white.clone()),
+        Some(black.clone()),
+        &OPENING,
+    );
+    assert!(room.rate_message().is_none());
+
+    // Against an anonymous player
+    let room = resigned_game(
+        new_room(TimeControl::blitz()),
+        Some(white.clone()),
+        None,
+        &OPENING,
+    );
+    assert!(room.rate_message().is_none());
+
+    // Before both sides moved
+    for moves in [&OPENING[..0], &OPENING[..1]] {
+        let room = resigned_game(
+            new_room(TimeControl::blitz()),
+            Some(white.clone()),
+            Some(black.clone()),
+            moves,
+        );
+        assert!(room.is_over());
+        assert!(room.rate_message().is_none());
+    }
+
+    // Not over yet
+    let mut room = new_room(TimeControl::blitz());
+    room.add_client(client(), None, Some(white.clone()))
+        .unwrap();
+    room.add_client(client(), None, Some(black)).unwrap();
+    assert!(room.rate_message().is_none());
+
+    // Against the bot
+    let settings = BotSettings {
+        level: Level::default(),
+        color: Color::Black,
+    };
+    let mut room = new_room(TimeControl::blitz()).with_bot(settings);
+    let player = client();
+    room.add_client(player.clone(), None, Some(white)).unwrap();
+    room.resign(player.id()).unwrap();
+    assert!(room.is_over());
+    assert!(room.rate_message().is_none());
+}
+
+/// The bot's move takes a share of its clock
+#[actix::test]
+async fn bot_time() {
+    let room = new_room(TimeControl::blitz());
+    // 3 minutes for 30 moves, and most of the 2 seconds of increment
+    assert_eq!(
+        room.bot_time(Color::White),
+        Some(Duration::from_millis(7_500))
+    );
+
+    let delayed = TimeControl {
+        delay: Some(Delay::Simple(4_000)),
+        ..TimeControl::new(60_000, 0)
+    };
+    let room = new_room(delayed);
+    assert_eq!(
+        room.bot_time(Color::Black),
+        Some(Duration::from_millis(5_000))
+    );
+
+    assert_eq!(
+        new_room(TimeControl::untimed()).bot_time(Color::White),
+        None
+    );
+}
+
+fn seconds(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
+
+#[test]
+fn fischer_increment() {
+    let start = Instant::now();
+    let mut clock = Clock::new(TimeControl::new(60_000, 2_000));
+    clock.start(Color::White, start);
+
+    // White used 10 seconds and gets 2 back, then black's clock runs
+    clock.switch(start + seconds(10));
+    assert_eq!(clock.running(), Some(Color::Black));
+    assert_eq!(
+        clock.remaining(Color::White, start + seconds(15)),
+        seconds(52)
+    );
+    assert_eq!(
+        clock.remaining(Color::Black, start + seconds(15)),
+        seconds(55)
+    );
+
+    clock.switch(start + seconds(15));
+    assert_eq!(
+        clock.remaining(Color::Black, start + seconds(15)),
+        seconds(57)
+    );
+    assert_eq!(clock.millis(start + seconds(20)), Some((47_000, 57_000)));
+}
+
+#[test]
+fn bronstein_delay() {
+    let control = TimeControl {
+        delay: Some(Delay::Bronstein(3_000)),
+        ..TimeControl::new(60_000, 0)
+    };
+    let start = Instant::now();
+    let mut clock = Clock::new(control);
+    clock.start(Color::White, start);
+
+    // All of the 2 seconds used come back
+    clock.switch(start + seconds(2));
+    assert_eq!(
+        clock.remaining(Color::White, start + seconds(2)),
+        seconds(60)
+    );
+    // No more than the delay of the 5 seconds used
+    clock.switch(start + seconds(7));
+    assert_eq!(
+        clock.remaining(Color::Black, start + seconds(7)),
+        seconds(58)
+    );
+}
+
+#[test]
+fn simple_delay() {
+    let control = TimeControl {
+        delay: Some(Delay::Simple(3_000)),
+        ..TimeControl::new(60_000, 0)
+    };
+    let start = Instant::now();
+    let mut clock = Clock::new(control);
+    clock.start(Color::White, start);
+
+    // The clock waits for the delay, then goes down
+    assert_eq!(
+        clock.remaining(Color::White, start + seconds(2)),
+        seconds(60)
+    );
+    assert_eq!(
+        clock.remaining(Color::White, start + seconds(5)),
+        seconds(58)
+    );
+    assert_eq!(clock.until_flag(start), Some(seconds(63)));
+    assert_eq!(clock.until_flag(start + seconds(2)), Some(seconds(61)));
+    assert_eq!(clock.until_flag(start + seconds(5)), Some(seconds(58)));
+    assert_eq!(clock.flagged(start + seconds(62)), None);
+    assert_eq!(clock.flagged(start + seconds(63)), Some(Color::White));
+
+    // Nothing is given back after the move
+    clock.switch(start + seconds(5));
+    assert_eq!(
+        clock.remaining(Color::White, start + seconds(5)),
+        seconds(58)
+    );
+    assert_eq!(clock.until_flag(start + seconds(5)), Some(seconds(63)));
+}
+
+#[test]
+fn untimed_clock() {
+    let start = Instant::now();
+    let mut clock = Clock::new(TimeControl::untimed());
+    clock.start(Color::White, start);
+    assert_eq!(clock.running(), None);
+    assert_eq!(clock.until_flag(start), None);
+    assert_eq!(clock.millis(start), None);
+}
+
+#[test]
+fn time_control_from_query() {
+    let query = |preset: Option<&str>| TimeControlQuery {
+        preset: preset.map(str::to_string),
+        ..TimeControlQuery::default()
+    };
+    assert_eq!(
+        TimeControl::from_query(&query(None)),
+        Ok(TimeControl::rapid())
+    );
+    assert_eq!(
+        TimeControl::from_query(&query(Some("blitz"))),
+        Ok(TimeControl::blitz())
+    );
+    assert_eq!(
+        TimeControl::from_query(&query(Some("untimed"))),
+        Ok(TimeControl::untimed())
+    );
+    assert_eq!(
+        TimeControl::from_query(&query(Some("hyperbullet"))),
+        Err(TimeControlError::UnknownPreset)
+    );
+
+    // The fields given replace the preset's, in seconds
+    let overridden = TimeControlQuery {
+        base: Some(300),
+        delay: Some("bronstein".to_string()),
+        delay_time: Some(5),
+        ..query(Some("blitz"))
+    };
+    assert_eq!(
+        TimeControl::from_query(&overridden),
+        Ok(TimeControl {
+            base: Some(300_000),
+            increment: 2_000,
+            delay: Some(Delay::Bronstein(5_000)),
+        })
+    );
+    let increment = TimeControlQuery {
+        increment: Some(0),
+        delay: Some("simple".to_string()),
+        delay_time: Some(2),
+        ..query(Some("classical"))
+    };
+    assert_eq!(
+        TimeControl::from_query(&increment),
+        Ok(TimeControl {
+            base: Some(1_800_000),
+            increment: 0,
+            delay: Some(Delay::Simple(2_000)),
+        })
+    );
+
+    let errors = [
+        (
+            TimeControlQuery {
+                base: Some(0),
+                ..query(None)
+            },
+            TimeControlError::InvalidBase,
+        ),
+        (
+            TimeControlQuery {
+                increment: Some(2),
+                ..query(Some("untimed"))
+            },
+            TimeControlError::InvalidBase,
+        ),
+        (
+            TimeControlQuery {
+                delay: Some("simple".to_string()),
+                ..query(None)
+            },
+            TimeControlError::MissingDelay,
+        ),
+        (
+            TimeControlQuery {
+                delay_time: Some(2),
+                ..query(None)
+            },
+            TimeControlError::MissingDelay,
+        ),
+        (
+            TimeControlQuery {
+                delay: Some("hourglass".to_string()),
+                delay_time: Some(2),
+                ..query(None)
+            },
+            TimeControlError::UnknownDelay,
+        ),
+        (
+            TimeControlQuery {
+                base: Some(u32::MAX),
+                ..query(None)
+            },
+            TimeControlError::TooLong,
+        ),
+    ];
+    for (query, error) in errors {
+        assert_eq!(TimeControl::from_query(&query), Err(error));
+    }
+}
+
+#[test]
+fn time_control_categories() {
+    let category = |base, increment| TimeControl::new(base, increment).category();
+    assert_eq!(category(179_999, 0), Some(Category::Bullet));
+    assert_eq!(category(180_000, 0), Some(Category::Blitz));
+    assert_eq!(category(479_999, 0), Some(Category::Blitz));
+    assert_eq!(category(480_000, 0), Some(Category::Rapid));
+    assert_eq!(category(1_499_999, 0), Some(Category::Rapid));
+    assert_eq!(category(1_500_000, 0), Some(Category::Classical));
+    // 40 moves of increment count
+    assert_eq!(category(99_999, 2_000), Some(Category::Bullet));
+    assert_eq!(category(100_000, 2_000), Some(Category::Blitz));
+
+    assert_eq!(TimeControl::bullet().category(), Some(Category::Bullet));
+    assert_eq!(TimeControl::blitz().category(), Some(Category::Blitz));
+    assert_eq!(TimeControl::rapid().category(), Some(Category::Rapid));
+    assert_eq!(
+        TimeControl::classical().category(),
+        Some(Category::Classical)
+    );
+    assert_eq!(TimeControl::untimed().category(), None);
+}
+
+/// The analysis waits for the end of the game
+#[actix::test]
+async fn game_moves_tell_if_over() {
+    let mut room = new_room(TimeControl::blitz());
+    room.add_client(client(), None, None).unwrap();
+    room.add_client(client(), None, None).unwrap();
+    let game = room.start().send(GameMoves).await.unwrap();
+    assert!(!game.over);
+
+    let room = resigned_game(new_room(TimeControl::blitz()), None, None, &[]);
+    let game = room.start().send(GameMoves).await.unwrap();
+    assert!(game.over);
+}
+
+/// The bot accepts a rematch after the command scheduled the flag, the new game still has one
+#[actix::test]
+async fn bot_rematch_flag() {
+    let settings = BotSettings {
+        level: Level::default(),
+        color: Color::White,
+    };
+    let mut room = new_room(TimeControl::new(50, 0)).with_bot(settings);
+    let recorder = Recorder::default().start();
+    let player = Client::with_recipient(Uuid::new_v4(), recorder.clone().recipient());
+    room.add_client(player.clone(), None, None).unwrap();
+    room.start_game();
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(room.check_flag());
+    recorder.send(Received).await.unwrap();
+
+    // The player gets white and the first move, and never plays it
+    let room = room.start();
+    let offer = message::Command {
+        client_id: player.id(),
+        command: Command::OfferRematch(true),
+    };
+    room.send(offer).await.unwrap();
+    actix::clock::sleep(Duration::from_millis(200)).await;
+    let received = recorder.send(Received).await.unwrap();
+    assert!(received.iter().any(|result| result.contains("rematch")));
+    assert!(received.iter().any(|result| result.contains("timeout")));
+}
@@ -0,0 +1,183 @@
+use std::{error::Error, fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Times are in milliseconds, like the clocks sent to the clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeControl {
+    /// Time each player starts with, None for untimed games
+    pub base: Option<u32>,
+    /// Fischer increment, added to the clock of the player who moved
+    pub increment: u32,
+    pub delay: Option<Delay>,
+}
+
+/// Rating pools, by the expected length of a game: the base time plus 40 moves of increment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Category {
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+}
+
+/// Time a player can think every move without using their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Delay {
+    /// The time used is given back after the move, up to the delay
+    Bronstein(u32),
+    /// The clock only starts running once the delay is over
+    Simple(u32),
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl::rapid()
+    }
+}
+
+impl TimeControl {
+    pub fn new(base: u32, increment: u32) -> Self {
+        Self {
+            base: Some(base),
+            increment,
+            delay: None,
+        }
+    }
+
+    /// 1 minute
+    pub fn bullet() -> Self {
+        Self::new(60_000, 0)
+    }
+
+    /// 3 minutes plus 2 seconds a move
+    pub fn blitz() -> Self {
+        Self::new(180_000, 2_000)
+    }
+
+    /// 10 minutes
+    pub fn rapid() -> Self {
+        Self::new(600_000, 0)
+    }
+
+    /// 30 minutes plus 20 seconds a move
+    pub fn classical() -> Self {
+        Self::new(1_800_000, 20_000)
+    }
+
+    pub fn untimed() -> Self {
+        Self {
+            base: None,
+            increment: 0,
+            delay: None,
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "bullet" => Some(Self::bullet()),
+            "blitz" => Some(Self::blitz()),
+            "rapid" => Some(Self::rapid()),
+            "classical" => Some(Self::classical()),
+            "untimed" => Some(Self::untimed()),
+            _ => None,
+        }
+    }
+
+    /// Builds the time control asked for in a room creation query. A preset is the starting
+    /// point, the other fields replace its values
+    pub fn from_query(query: &TimeControlQuery) -> Result<Self, TimeControlError> {
+        let mut control = match &query.preset {
+            Some(preset) => Self::preset(preset).ok_or(TimeControlError::UnknownPreset)?,
+            None => Self::default(),
+        };
+        if let Some(base) = query.base {
+            if base == 0 {
+                return Err(TimeControlError::InvalidBase);
+            }
+            control.base = Some(seconds(base)?);
+        }
+        if let Some(increment) = query.increment {
+            control.increment = seconds(increment)?;
+        }
+        control.delay = match (query.delay.as_deref(), query.delay_time) {
+            (None, None) => control.delay,
+            (Some("bronstein"), Some(time)) => Some(Delay::Bronstein(seconds(time)?)),
+            (Some("simple"), Some(time)) => Some(Delay::Simple(seconds(time)?)),
+            (Some("bronstein" | "simple"), None) | (None, Some(_)) => {
+                return Err(TimeControlError::MissingDelay)
+            }
+            (Some(_), _) => return Err(TimeControlError::UnknownDelay),
+        };
+        if control.base.is_none() && (control.increment > 0 || control.delay.is_some()) {
+            return Err(TimeControlError::InvalidBase);
+        }
+        Ok(control)
+    }
+
+    /// The rating pool of the games, untimed games are not rated
+    pub fn category(&self) -> Option<Category> {
+        let estimate = self.base? as u64 + 40 * self.increment as u64;
+        let category = match estimate {
+            0..=179_999 => Category::Bullet,
+            180_000..=479_999 => Category::Blitz,
+            480_000..=1_499_999 => Category::Rapid,
+            _ => Category::Classical,
+        };
+        Some(category)
+    }
+
+    pub fn base_duration(&self) -> Option<Duration> {
+        self.base.map(|base| Duration::from_millis(base as u64))
+    }
+}
+
+/// Query parameters of `ws/room/create`, times are in seconds
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeControlQuery {
+    pub preset: Option<String>,
+    pub base: Option<u32>,
+    pub increment: Option<u32>,
+    /// `bronstein` or `simple`, with `delayTime`
+    pub delay: Option<String>,
+    pub delay_time: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControlError {
+    UnknownPreset,
+    UnknownDelay,
+    MissingDelay,
+    InvalidBase,
+    TooLong,
+}
+
+impl Display for TimeControlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeControlError::UnknownPreset => write!(
+                f,
+                "Unknown preset, use bullet, blitz, rapid, classical or untimed"
+            ),
+            TimeControlError::UnknownDelay => write!(f, "Unknown delay, use bronstein or simple"),
+            TimeControlError::MissingDelay => {
+                write!(f, "A delay needs both its type and its time")
+            }
+            TimeControlError::InvalidBase => write!(
+                f,
+                "The base time must be above zero, untimed games have no increment or delay"
+            ),
+            TimeControlError::TooLong => write!(f, "Time control is too long"),
+        }
+    }
+}
+
+impl Error for TimeControlError {}
+
+fn seconds(seconds: u32) -> Result<u32, TimeControlError> {
+    seconds.checked_mul(1000).ok_or(TimeControlError::TooLong)
+}
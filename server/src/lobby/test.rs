@@ -0,0 +1,294 @@
+use std::time::{Duration, Instant};
+
+use actix::{Actor, Handler, Message, MessageResult};
+use ai::strength::Level;
+use engine::piece::Color;
+use uuid::Uuid;
+
+use crate::{
+    messages::{
+        inner::{AvailableRooms, RoomListing},
+        result::ResultMessage,
+        ConnectMessage, Matched,
+    },
+    rating::{PlayerId, Players, Profile, Ratings},
+};
+
+use super::{
+    client::Client,
+    errors::RoomError,
+    queue::{Queue, QueueEntry, Search},
+    room::{
+        bot::BotSettings,
+        message::{RoomMessage, RoomMessageType},
+        time_control::TimeControl,
+    },
+    Lobby, RoomId,
+};
+
+/// Stands for the connections of the queued players
+struct Sink;
+
+impl Actor for Sink {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<ResultMessage> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, _: ResultMessage, _: &mut Self::Context) -> Self::Result {}
+}
+
+impl Handler<Matched> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, _: Matched, _: &mut Self::Context) -> Self::Result {}
+}
+
+/// Keeps the results a connection gets, as JSON
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl Actor for Recorder {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<ResultMessage> for Recorder {
+    type Result = ();
+
+    fn handle(&mut self, msg: ResultMessage, _: &mut Self::Context) -> Self::Result {
+        self.0.push(serde_json::to_string(&msg).unwrap());
+    }
+}
+
+/// The results received so far
+#[derive(Message)]
+#[rtype(result = "Vec<String>")]
+struct Received;
+
+impl Handler<Received> for Recorder {
+    type Result = MessageResult<Received>;
+
+    fn handle(&mut self, _: Received, _: &mut Self::Context) -> Self::Result {
+        MessageResult(std::mem::take(&mut self.0))
+    }
+}
+
+fn entry(time_control: TimeControl, rating: u32, range: u32, joined: Instant) -> QueueEntry {
+    let sink = Sink.start();
+    QueueEntry {
+        client: Client::with_recipient(Uuid::new_v4(), sink.clone().recipient()),
+        matched: sink.recipient(),
+        time_control,
+        search: Search { rating, range },
+        player: None,
+        joined,
+    }
+}
+
+fn profile() -> Profile {
+    Profile {
+        id: PlayerId::new_v4(),
+        name: "player".to_string(),
+        ratings: Ratings::default(),
+    }
+}
+
+/// Client ids of the pairs, the first of each pair plays white
+fn pairs(queue: &mut Queue, now: Instant) -> Vec<(Uuid, Uuid)> {
+    queue
+        .pairs(now)
+        .into_iter()
+        .map(|(first, second)| (first.client.id(), second.client.id()))
+        .collect()
+}
+
+#[actix::test]
+async fn same_time_control() {
+    let now = Instant::now();
+    let mut queue = Queue::default();
+    let blitz = entry(TimeControl::blitz(), 1500, 200, now);
+    let rapid = entry(TimeControl::rapid(), 1500, 200, now);
+    let other_blitz = entry(TimeControl::blitz(), 1500, 200, now);
+    let ids = (blitz.client.id(), other_blitz.client.id());
+
+    queue.push(blitz).unwrap();
+    queue.push(rapid).unwrap();
+    assert!(pairs(&mut queue, now).is_empty());
+
+    queue.push(other_blitz).unwrap();
+    assert_eq!(pairs(&mut queue, now), vec![ids]);
+    // The rapid player still waits
+    assert!(pairs(&mut queue, now).is_empty());
+}
+
+#[actix::test]
+async fn asymmetric_ranges() {
+    let now = Instant::now();
+    let mut queue = Queue::default();
+    // The first player accepts the second, who doesn't accept them back
+    let wide = entry(TimeControl::blitz(), 1500, 300, now);
+    let narrow = entry(TimeControl::blitz(), 1750, 100, now);
+    let ids = (wide.client.id(), narrow.client.id());
+    queue.push(wide).unwrap();
+    queue.push(narrow).unwrap();
+    assert!(pairs(&mut queue, now).is_empty());
+
+    // The narrow range reaches 250 after three steps
+    assert!(pairs(&mut queue, now + Duration::from_secs(14)).is_empty());
+    assert_eq!(pairs(&mut queue, now + Duration::from_secs(15)), vec![ids]);
+}
+
+#[actix::test]
+async fn ranges_widen() {
+    let now = Instant::now();
+    let mut queue = Queue::default();
+    let first = entry(TimeControl::rapid(), 1500, 200, now);
+    let second = entry(TimeControl::rapid(), 1800, 200, now);
+    let ids = (first.client.id(), second.client.id());
+    queue.push(first).unwrap();
+    queue.push(second).unwrap();
+
+    // 50 more points every 5 seconds
+    assert!(pairs(&mut queue, now).is_empty());
+    assert!(pairs(&mut queue, now + Duration::from_secs(5)).is_empty());
+    assert!(pairs(&mut queue, now + Duration::from_secs(9)).is_empty());
+    assert_eq!(pairs(&mut queue, now + Duration::from_secs(10)), vec![ids]);
+}
+
+#[actix::test]
+async fn longest_waiting_first() {
+    let start = Instant::now();
+    let now = start + Duration::from_secs(3);
+    let mut queue = Queue::default();
+    let oldest = entry(TimeControl::blitz(), 1500, 200, start);
+    let middle = entry(
+        TimeControl::blitz(),
+        1500,
+        200,
+        start + Duration::from_secs(1),
+    );
+    let newest = entry(
+        TimeControl::blitz(),
+        1500,
+        200,
+        start + Duration::from_secs(2),
+    );
+    let ids = [oldest.client.id(), middle.client.id(), newest.client.id()];
+    for entry in [oldest, middle, newest] {
+        queue.push(entry).unwrap();
+    }
+
+    // The two who waited the longest play, the oldest with white
+    assert_eq!(pairs(&mut queue, now), vec![(ids[0], ids[1])]);
+
+    // The oldest player skips the ones they can't play
+    let mut queue = Queue::default();
+    let oldest = entry(TimeControl::blitz(), 1500, 100, start);
+    let far = entry(
+        TimeControl::blitz(),
+        1700,
+        100,
+        start + Duration::from_secs(1),
+    );
+    let near = entry(
+        TimeControl::blitz(),
+        1550,
+        100,
+        start + Duration::from_secs(2),
+    );
+    let ids = [oldest.client.id(), far.client.id(), near.client.id()];
+    for entry in [oldest, far, near] {
+        queue.push(entry).unwrap();
+    }
+    assert_eq!(pairs(&mut queue, now), vec![(ids[0], ids[2])]);
+}
+
+#[actix::test]
+async fn one_entry_per_player() {
+    let now = Instant::now();
+    let mut queue = Queue::default();
+    let player = profile();
+
+    let mut first = entry(TimeControl::blitz(), 1500, 200, now);
+    first.player = Some(player.clone());
+    let mut second = entry(TimeControl::blitz(), 1500, 200, now);
+    second.player = Some(player);
+    queue.push(first.clone()).unwrap();
+    assert!(matches!(queue.push(second), Err(RoomError::AlreadyQueued)));
+
+    // The same connection joining again replaces its entry
+    queue.push(first).unwrap();
+    assert!(pairs(&mut queue, now).is_empty());
+
+    let mut other = entry(TimeControl::blitz(), 1500, 200, now);
+    other.player = Some(profile());
+    queue.push(other).unwrap();
+    assert_eq!(pairs(&mut queue, now).len(), 1);
+}
+
+#[actix::test]
+async fn connect_to_missing_room() {
+    let lobby = Lobby::new(Players::default().start()).start();
+    let recorder = Recorder::default().start();
+    let connect = |spectator, session| ConnectMessage {
+        room_id: RoomId::new_v4(),
+        client: Client::with_recipient(Uuid::new_v4(), recorder.clone().recipient()),
+        time_control: TimeControl::default(),
+        bot: None,
+        spectator,
+        session,
+        player: None,
+    };
+
+    // Watching, or taking back a seat, needs the room to exist
+    for msg in [connect(true, None), connect(false, Some(Uuid::new_v4()))] {
+        lobby.send(msg).await.unwrap();
+        let received = recorder.send(Received).await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(received[0].contains("Room not found"));
+    }
+}
+
+/// A room goes back on the list when a seat frees up, unless it is a game against the bot
+#[actix::test]
+async fn private_rooms_stay_unlisted() {
+    let lobby = Lobby::new(Players::default().start()).start();
+    let sink = Sink.start();
+    let connect = |room_id, bot| ConnectMessage {
+        room_id,
+        client: Client::with_recipient(Uuid::new_v4(), sink.clone().recipient()),
+        time_control: TimeControl::default(),
+        bot,
+        spectator: false,
+        session: None,
+        player: None,
+    };
+    let seat_freed = |room_id, message| RoomMessage { room_id, message };
+    let listed = |rooms: Vec<_>, room_id| rooms.iter().any(|room: &RoomListing| room.id == room_id);
+
+    let public = RoomId::new_v4();
+    lobby.send(connect(public, None)).await.unwrap();
+    lobby
+        .send(seat_freed(public, RoomMessageType::Full))
+        .await
+        .unwrap();
+    assert!(!listed(lobby.send(AvailableRooms).await.unwrap(), public));
+    lobby
+        .send(seat_freed(public, RoomMessageType::Disconnect))
+        .await
+        .unwrap();
+    assert!(listed(lobby.send(AvailableRooms).await.unwrap(), public));
+
+    let bot = BotSettings {
+        level: Level::default(),
+        color: Color::Black,
+    };
+    let private = RoomId::new_v4();
+    lobby.send(connect(private, Some(bot))).await.unwrap();
+    lobby
+        .send(seat_freed(private, RoomMessageType::Disconnect))
+        .await
+        .unwrap();
+    assert!(!listed(lobby.send(AvailableRooms).await.unwrap(), private));
+}
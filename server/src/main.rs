@@ -1,25 +1,41 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use actix::{Actor, Addr};
 use actix_cors::Cors;
 use actix_web::{
     get,
     middleware::NormalizePath,
-    web::{Data, Path, Payload},
+    post,
+    web::{self, Data, Json, Path, Payload, Query},
     App, Error, HttpRequest, HttpResponse, HttpServer, Responder,
 };
+use analysis::Analyses;
 use con::Con;
-use lobby::Lobby;
-use messages::inner::{AvailableRoom, AvailableRooms};
+use lobby::queue::{QueueQuery, Search};
+use lobby::room::{
+    bot::{BotQuery, BotSettings},
+    message::GameMoves,
+    time_control::{Category, TimeControl, TimeControlQuery},
+};
+use lobby::{Lobby, SessionToken};
+use messages::inner::{AvailableRoom, AvailableRooms, RoomAddress};
+use rating::{
+    message::{GetProfile, Identify, Ladder, Register},
+    PlayerId, PlayerToken, Players, Profile,
+};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
+mod analysis;
+mod archive;
 mod commands;
 mod con;
 mod lobby;
 mod messages;
+mod rating;
 
 #[get("api")]
 async fn hello() -> impl Responder {
@@ -35,31 +51,210 @@ async fn available_rooms(addr: Data<Addr<Lobby>>) -> impl Responder {
     }
 }
 
+#[derive(Deserialize)]
+struct Registration {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Registered {
+    /// Identifies the player on the websocket endpoints, it is only given here
+    token: PlayerToken,
+    #[serde(flatten)]
+    profile: Profile,
+}
+
+/// Registers a player by name, with default ratings in every category
+#[post("api/player")]
+async fn register_player(
+    registration: Json<Registration>,
+    players: Data<Addr<Players>>,
+) -> impl Responder {
+    match players.send(Register(registration.into_inner().name)).await {
+        Ok(Ok((token, profile))) => HttpResponse::Ok().json(Registered { token, profile }),
+        Ok(Err(error)) => HttpResponse::BadRequest().body(error.to_string()),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("api/player/{player_id}")]
+async fn player_profile(player_id: Path<PlayerId>, players: Data<Addr<Players>>) -> impl Responder {
+    match players.send(GetProfile(player_id.into_inner())).await {
+        Ok(Some(profile)) => HttpResponse::Ok().json(profile),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Players who played rated games in the category, the highest rated first
+#[get("api/ladder/{category}")]
+async fn ladder(category: Path<Category>, players: Data<Addr<Players>>) -> impl Responder {
+    match players.send(Ladder(category.into_inner())).await {
+        Ok(ladder) => HttpResponse::Ok().json(ladder),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[derive(Deserialize)]
+struct PlayerQuery {
+    /// Token of the registered player to play as, games are only rated between them
+    player: Option<PlayerToken>,
+}
+
+/// The profile of the player the query names, or the response refusing an unknown token
+async fn identify(
+    query: &PlayerQuery,
+    players: &Addr<Players>,
+) -> Result<Option<Profile>, HttpResponse> {
+    let Some(token) = query.player else {
+        return Ok(None);
+    };
+    match players.send(Identify(token)).await {
+        Ok(Some(profile)) => Ok(Some(profile)),
+        Ok(None) => Err(HttpResponse::Unauthorized().body("Unknown player token")),
+        Err(_) => Err(HttpResponse::InternalServerError().finish()),
+    }
+}
+
+/// Report of the finished game of a room, with the accuracy of both players and every error.
+/// Games still going on are not analysed, the engine's lines would help the players
+#[get("api/room/{room_id}/analysis")]
+async fn room_analysis(
+    room_id: Path<Uuid>,
+    addr: Data<Addr<Lobby>>,
+    analyses: Data<Analyses>,
+) -> impl Responder {
+    let room_id = room_id.into_inner();
+    let room = match addr.send(RoomAddress(room_id)).await {
+        Ok(Some(room)) => room,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    let game = match room.send(GameMoves).await {
+        Ok(game) if game.over => game.moves,
+        Ok(_) => return HttpResponse::Conflict().body("The game is not over"),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    if let Some(report) = analyses.cached(room_id, &game) {
+        return HttpResponse::Ok().json(report.as_ref());
+    }
+    let Some(_running) = analyses.start() else {
+        return HttpResponse::ServiceUnavailable()
+            .body("Too many analyses running, try again later");
+    };
+
+    // The search would block the server's threads
+    let moves = game.clone();
+    match web::block(move || analysis::analyse(&moves)).await {
+        Ok(Ok(report)) => {
+            let report = Arc::new(report);
+            analyses.insert(room_id, game, report.clone());
+            HttpResponse::Ok().json(report.as_ref())
+        }
+        Ok(Err(error)) => HttpResponse::BadRequest().body(error.to_string()),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// The time control comes from the query: a preset (`?preset=blitz`) and/or times in seconds
+/// (`?base=300&increment=3&delay=bronstein&delayTime=2`). With `?opponent=ai&level=5&color=black`
+/// the server plays the other color
 #[get("ws/room/create")]
 async fn create_room(
     req: HttpRequest,
     stream: Payload,
+    query: Query<TimeControlQuery>,
+    bot_query: Query<BotQuery>,
+    player_query: Query<PlayerQuery>,
     addr: Data<Addr<Lobby>>,
+    players: Data<Addr<Players>>,
 ) -> Result<HttpResponse, Error> {
+    let time_control = match TimeControl::from_query(&query) {
+        Ok(time_control) => time_control,
+        Err(error) => return Ok(HttpResponse::BadRequest().body(error.to_string())),
+    };
+    let bot = match BotSettings::from_query(&bot_query) {
+        Ok(bot) => bot,
+        Err(error) => return Ok(HttpResponse::BadRequest().body(error.to_string())),
+    };
+    let player = match identify(&player_query, &players).await {
+        Ok(player) => player,
+        Err(response) => return Ok(response),
+    };
     let group_id = Uuid::new_v4();
     let addr = addr.get_ref().clone();
-    let ws = Con::new(group_id, addr);
+    let ws = match bot {
+        Some(bot) => Con::against_bot(group_id, addr, time_control, bot),
+        None => Con::with_time_control(group_id, addr, time_control),
+    }
+    .with_player(player);
+
+    let resp = actix_web_actors::ws::start(ws, &req, stream)?;
+    Ok(resp)
+}
+
+/// Waits for an opponent with the same time control (the query of `ws/room/create`) and a
+/// rating within `?rating=1500&range=200`, the range widens as the player waits. Both players
+/// get the `connect` result of their new room
+#[get("ws/queue")]
+async fn join_queue(
+    req: HttpRequest,
+    stream: Payload,
+    query: Query<TimeControlQuery>,
+    queue_query: Query<QueueQuery>,
+    player_query: Query<PlayerQuery>,
+    addr: Data<Addr<Lobby>>,
+    players: Data<Addr<Players>>,
+) -> Result<HttpResponse, Error> {
+    let time_control = match TimeControl::from_query(&query) {
+        Ok(time_control) => time_control,
+        Err(error) => return Ok(HttpResponse::BadRequest().body(error.to_string())),
+    };
+    let player = match identify(&player_query, &players).await {
+        Ok(player) => player,
+        Err(response) => return Ok(response),
+    };
+    let search = Search::new(&queue_query, player.as_ref(), time_control.category());
+    let ws = Con::queued(addr.get_ref().clone(), time_control, search).with_player(player);
 
     let resp = actix_web_actors::ws::start(ws, &req, stream)?;
     Ok(resp)
 }
 
+#[derive(Deserialize)]
+struct JoinQuery {
+    /// Session token of a seat to take back
+    token: Option<SessionToken>,
+}
+
+/// Takes a free seat, or with `?token=` the seat held for a player who disconnected
 #[get("ws/room/{room_id}")]
 async fn join_room(
     room_id: Path<Uuid>,
+    query: Query<JoinQuery>,
+    player_query: Query<PlayerQuery>,
     req: HttpRequest,
     stream: Payload,
     addr: Data<Addr<Lobby>>,
+    players: Data<Addr<Players>>,
 ) -> Result<HttpResponse, Error> {
     let room_id = room_id.into_inner();
     let addr = addr.get_ref().clone();
+    let player = match identify(&player_query, &players).await {
+        Ok(player) => player,
+        Err(response) => return Ok(response),
+    };
 
-    let room = match addr.send(AvailableRoom(room_id)).await {
+    // A held seat doesn't make the room available
+    let room = match query.token {
+        Some(_) => addr
+            .send(RoomAddress(room_id))
+            .await
+            .map(|room| room.is_some()),
+        None => addr.send(AvailableRoom(room_id)).await,
+    };
+    let room = match room {
         Ok(room) => room,
         Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
     };
@@ -68,7 +263,34 @@ async fn join_room(
         return Ok(HttpResponse::NotFound().finish());
     }
 
-    let ws = Con::new(room_id, addr);
+    let ws = match query.token {
+        Some(token) => Con::resume(room_id, addr, token),
+        None => Con::new(room_id, addr),
+    }
+    .with_player(player);
+
+    let resp = actix_web_actors::ws::start(ws, &req, stream)?;
+    Ok(resp)
+}
+
+/// Watches the game of a room, full or not
+#[get("ws/room/{room_id}/watch")]
+async fn watch_room(
+    room_id: Path<Uuid>,
+    req: HttpRequest,
+    stream: Payload,
+    addr: Data<Addr<Lobby>>,
+) -> Result<HttpResponse, Error> {
+    let room_id = room_id.into_inner();
+    let addr = addr.get_ref().clone();
+
+    match addr.send(RoomAddress(room_id)).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Ok(HttpResponse::NotFound().finish()),
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish()),
+    }
+
+    let ws = Con::spectator(room_id, addr);
 
     let resp = actix_web_actors::ws::start(ws, &req, stream)?;
     Ok(resp)
@@ -78,8 +300,11 @@ async fn join_room(
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().ok();
     env_logger::init();
-    let lobby = Lobby::default();
+    let players = Players::load().start();
+    let lobby = Lobby::new(players.clone());
     let addr = lobby.start();
+    // Shared by the workers
+    let analyses = Data::new(Analyses::default());
 
     println!("Server running at http://localhost:8080");
     HttpServer::new(move || {
@@ -87,10 +312,18 @@ async fn main() -> std::io::Result<()> {
             .wrap(NormalizePath::trim())
             .wrap(Cors::permissive())
             .app_data(Data::new(addr.clone()))
+            .app_data(Data::new(players.clone()))
+            .app_data(analyses.clone())
             .service(hello)
+            .service(register_player)
+            .service(player_profile)
+            .service(ladder)
             .service(available_rooms)
+            .service(room_analysis)
             .service(create_room)
+            .service(join_queue)
             .service(join_room)
+            .service(watch_room)
     })
     .bind(("0.0.0.0", 8080))?
     .run()
@@ -1,11 +1,27 @@
-use actix::Message;
+use actix::{Addr, Message};
+use serde::Serialize;
 
-use crate::lobby::RoomId;
+use crate::lobby::{
+    room::{time_control::TimeControl, Room},
+    RoomId,
+};
 
 #[derive(Message)]
-#[rtype(result = "Vec<RoomId>")]
+#[rtype(result = "Vec<RoomListing>")]
 pub struct AvailableRooms;
 
+/// A room waiting for an opponent, as `api/room` lists it
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomListing {
+    pub id: RoomId,
+    pub time_control: TimeControl,
+}
+
 #[derive(Message)]
 #[rtype(result = "bool")]
 pub struct AvailableRoom(pub RoomId);
+
+#[derive(Message)]
+#[rtype(result = "Option<Addr<Room>>")]
+pub struct RoomAddress(pub RoomId);
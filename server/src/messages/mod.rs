@@ -1,11 +1,17 @@
-use actix::prelude::Message;
+use actix::prelude::{Message, Recipient};
 
 pub mod inner;
 pub mod result;
 
 use crate::{
     commands::Command,
-    lobby::{client::Client, ClientId, RoomId},
+    lobby::{
+        client::Client,
+        queue::Search,
+        room::{bot::BotSettings, time_control::TimeControl},
+        ClientId, RoomId, SessionToken,
+    },
+    rating::Profile,
 };
 
 #[derive(Message)]
@@ -22,8 +28,32 @@ pub struct CommandMessage {
 pub struct ConnectMessage {
     pub room_id: RoomId,
     pub client: Client,
+    //Only used when the connection creates the room
+    pub time_control: TimeControl,
+    //The bot the room is created with, for a game against the server
+    pub bot: Option<BotSettings>,
+    pub spectator: bool,
+    //Token of the seat a player comes back to
+    pub session: Option<SessionToken>,
+    pub player: Option<Profile>,
 }
 
+//WsConn sends this to the lobby to say "find me an opponent please"
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct JoinQueue {
+    pub client: Client,
+    pub matched: Recipient<Matched>,
+    pub time_control: TimeControl,
+    pub search: Search,
+    pub player: Option<Profile>,
+}
+
+//The lobby sends this to a queued WsConn to say "your game is in this room"
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Matched(pub RoomId);
+
 //WsConn sends this to a lobby to say "take me out please"
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -1,13 +1,20 @@
 use actix::Message;
 use engine::{
+    game::DrawReason,
     piece::{position::Position, ChessPiece, Color, Type},
     result::OkMovement,
 };
 use serde::Serialize;
 
-use crate::lobby::{
-    room::{Room, TurnMove},
-    ClientId, RoomId,
+use crate::{
+    lobby::{
+        room::{
+            time_control::{Category, TimeControl},
+            Room, TurnMove,
+        },
+        ClientId, RoomId, SessionToken,
+    },
+    rating::{PlayerId, Profile},
 };
 
 #[derive(Message, Serialize, Clone)]
@@ -44,7 +51,7 @@ impl ResultMessage {
         };
         Self::Success(SuccessMessage {
             room_id,
-            client_id,
+            client_id: Some(client_id),
             result: SuccessResult::Movement(result),
         })
     }
@@ -62,7 +69,7 @@ impl ResultMessage {
         };
         Self::Success(SuccessMessage {
             room_id,
-            client_id,
+            client_id: Some(client_id),
             result: SuccessResult::Promotion(promotion),
         })
     }
@@ -70,7 +77,7 @@ impl ResultMessage {
     pub fn disconnect(room_id: RoomId, client_id: ClientId) -> Self {
         Self::Success(SuccessMessage {
             room_id,
-            client_id,
+            client_id: Some(client_id),
             result: SuccessResult::Disconnect(DisconnectSuccess { room_id, client_id }),
         })
     }
@@ -79,48 +86,185 @@ impl ResultMessage {
         let room_id = room.id();
         let (pieces, moves) = match con_type {
             ConnectionType::EnemyClient => (None, None),
-            ConnectionType::SelfClient => (Some(room.pieces()), Some(room.moves().clone())),
+            ConnectionType::SelfClient | ConnectionType::Spectator => {
+                (Some(room.pieces()), Some(room.moves().clone()))
+            }
+        };
+        let session_token = match con_type {
+            ConnectionType::SelfClient => room.session(client_id),
+            ConnectionType::EnemyClient | ConnectionType::Spectator => None,
         };
         let check = room.check();
         let promotion = room.promotion();
 
         let enemy = room.enemy(client_id);
         let enemy_id = enemy.map(|c| c.id());
-        let color = room.client_color(client_id).unwrap(); // Should not error,client just connected
-        let (white_timer, black_timer) = room.timers();
+        let color = room.client_color(client_id); // None for spectators
+        let timers = room.timers();
 
         Self::Success(SuccessMessage {
             room_id,
-            client_id,
-            result: SuccessResult::Connect(ConnectSuccess {
+            client_id: Some(client_id),
+            result: SuccessResult::Connect(Box::new(ConnectSuccess {
                 room_id,
                 client_id,
                 enemy_id,
                 con_type,
+                session_token,
                 color,
                 pieces,
                 moves,
                 check,
                 promotion,
-                white_timer,
-                black_timer,
-            }),
+                time_control: room.time_control(),
+                white_timer: timers.map(|(white, _)| white),
+                black_timer: timers.map(|(_, black)| black),
+                white_player: room.profile(Color::White).cloned(),
+                black_player: room.profile(Color::Black).cloned(),
+            })),
         })
     }
 
     pub fn winner(room_id: RoomId, client_id: ClientId, color: Color) -> Self {
         Self::Success(SuccessMessage {
             room_id,
-            client_id,
+            client_id: Some(client_id),
             result: SuccessResult::Winner(color),
         })
     }
 
-    pub fn reset(room_id: RoomId, client_id: ClientId) -> Self {
+    pub fn rematch_offer(
+        room_id: RoomId,
+        client_id: ClientId,
+        color: Color,
+        status: OfferStatus,
+    ) -> Self {
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::RematchOffer(RematchOfferResult { color, status }),
+        })
+    }
+
+    /// A new game started with the players on their new colors
+    pub fn rematch(
+        room_id: RoomId,
+        client_id: ClientId,
+        white: Option<ClientId>,
+        black: Option<ClientId>,
+    ) -> Self {
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::Rematch(RematchResult { white, black }),
+        })
+    }
+
+    pub fn draw_offer(
+        room_id: RoomId,
+        client_id: ClientId,
+        color: Color,
+        status: OfferStatus,
+    ) -> Self {
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::DrawOffer(DrawOfferResult { color, status }),
+        })
+    }
+
+    pub fn draw(room_id: RoomId, client_id: ClientId, reason: DrawReason) -> Self {
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::Draw(reason),
+        })
+    }
+
+    pub fn takeback_request(
+        room_id: RoomId,
+        client_id: ClientId,
+        color: Color,
+        status: TakebackStatus,
+    ) -> Self {
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::TakebackRequest(TakebackRequestResult { color, status }),
+        })
+    }
+
+    /// The position after a takeback, `color` asked for it
+    pub fn takeback(
+        room_id: RoomId,
+        client_id: ClientId,
+        color: Color,
+        plies: usize,
+        room: &Room,
+    ) -> Self {
+        let result = TakebackResult {
+            color,
+            plies,
+            check: room.check(),
+            promotion: room.promotion(),
+            turn_number: room.turn_number(),
+            pieces: room.pieces(),
+            moves: room.moves().clone(),
+        };
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::Takeback(result),
+        })
+    }
+
+    /// The ratings of both players after their game, `before` are the white and black ratings
+    /// it started with
+    pub fn rated(
+        room_id: RoomId,
+        client_id: ClientId,
+        category: Category,
+        white: &Profile,
+        black: &Profile,
+        before: (f64, f64),
+    ) -> Self {
+        let change = |profile: &Profile, before: f64| {
+            let rating = profile.ratings.get(category).rating;
+            RatingChange {
+                player: profile.id,
+                rating,
+                change: rating - before,
+            }
+        };
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::Rated(RatedResult {
+                category,
+                white: change(white, before.0),
+                black: change(black, before.1),
+            }),
+        })
+    }
+
+    pub fn timeout(
+        room_id: RoomId,
+        client_id: Option<ClientId>,
+        color: Color,
+        winner: Option<Color>,
+    ) -> Self {
         Self::Success(SuccessMessage {
             room_id,
             client_id,
-            result: SuccessResult::Reset(true),
+            result: SuccessResult::Timeout(TimeoutResult { color, winner }),
+        })
+    }
+
+    pub fn abandoned(room_id: RoomId, client_id: ClientId, color: Color, winner: Color) -> Self {
+        Self::Success(SuccessMessage {
+            room_id,
+            client_id: Some(client_id),
+            result: SuccessResult::Abandoned(AbandonedResult { color, winner }),
         })
     }
 
@@ -146,7 +290,7 @@ pub struct ErrorMessage {
 #[serde(rename_all = "camelCase")]
 pub struct SuccessMessage {
     room_id: RoomId,
-    client_id: ClientId,
+    client_id: Option<ClientId>, //None when the client the result is about is disconnected
     result: SuccessResult,
 }
 
@@ -156,9 +300,17 @@ enum SuccessResult {
     Movement(MovementResult),
     Promotion(PromotionResult),
     Disconnect(DisconnectSuccess),
-    Connect(ConnectSuccess),
+    Connect(Box<ConnectSuccess>),
     Winner(Color),
-    Reset(bool),
+    Timeout(TimeoutResult),
+    Abandoned(AbandonedResult),
+    DrawOffer(DrawOfferResult),
+    Draw(DrawReason),
+    TakebackRequest(TakebackRequestResult),
+    Takeback(TakebackResult),
+    RematchOffer(RematchOfferResult),
+    Rematch(RematchResult),
+    Rated(RatedResult),
 }
 
 #[derive(Serialize, Clone, Copy)]
@@ -179,6 +331,102 @@ struct PromotionResult {
     check: Option<Color>,
 }
 
+/// `color` ran out of time, `winner` is None when the opponent can't mate
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct TimeoutResult {
+    color: Color,
+    winner: Option<Color>,
+}
+
+/// `color` left during the game and didn't come back in time
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct AbandonedResult {
+    color: Color,
+    winner: Color,
+}
+
+/// The draw offer made by `color`
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct DrawOfferResult {
+    color: Color,
+    status: OfferStatus,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum OfferStatus {
+    Offered,
+    Declined,
+    /// The opponent moved instead of answering a draw offer, or the player who offered a
+    /// rematch left
+    Expired,
+}
+
+/// The rematch offer made by `color`, in the game that just ended
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct RematchOfferResult {
+    color: Color,
+    status: OfferStatus,
+}
+
+/// The players of the new game, their colors swapped
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct RematchResult {
+    white: Option<ClientId>,
+    black: Option<ClientId>,
+}
+
+/// The request made by `color`
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct TakebackRequestResult {
+    color: Color,
+    status: TakebackStatus,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum TakebackStatus {
+    Requested,
+    Declined,
+    /// A move was played before the opponent answered
+    Expired,
+}
+
+/// The last `plies` moves were taken back, the clients replace their board and moves
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TakebackResult {
+    color: Color,
+    plies: usize,
+    check: Option<Color>,
+    promotion: Option<Color>,
+    turn_number: u32,
+    pieces: [[Option<ChessPiece>; 8]; 8],
+    moves: Vec<TurnMove>,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct RatedResult {
+    category: Category,
+    white: RatingChange,
+    black: RatingChange,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct RatingChange {
+    player: PlayerId,
+    rating: f64,
+    change: f64,
+}
+
 #[derive(Serialize, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
 struct DisconnectSuccess {
@@ -193,13 +441,20 @@ struct ConnectSuccess {
     client_id: ClientId,
     enemy_id: Option<ClientId>,
     con_type: ConnectionType,
-    color: Color,
+    //Only sent to the player it belongs to
+    session_token: Option<SessionToken>,
+    color: Option<Color>,
     check: Option<Color>,
     promotion: Option<Color>,
     pieces: Option<[[Option<ChessPiece>; 8]; 8]>,
     moves: Option<Vec<TurnMove>>, //Option so that we don't pass the moves to the client when enemy connects
-    white_timer: u32,
-    black_timer: u32,
+    time_control: TimeControl,
+    //None in untimed games
+    white_timer: Option<u32>,
+    black_timer: Option<u32>,
+    //None for anonymous players and the bot
+    white_player: Option<Profile>,
+    black_player: Option<Profile>,
 }
 
 #[derive(Serialize, Clone, Copy)]
@@ -207,6 +462,7 @@ struct ConnectSuccess {
 pub enum ConnectionType {
     EnemyClient,
     SelfClient,
+    Spectator,
 }
 
 #[derive(Serialize, Clone, Copy)]
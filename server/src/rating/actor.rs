@@ -0,0 +1,50 @@
+use actix::{Actor, Handler, MessageResult};
+
+use super::{
+    message::{GetProfile, Identify, Ladder, RateGame, Register},
+    Players,
+};
+
+impl Actor for Players {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<Register> for Players {
+    type Result = MessageResult<Register>;
+
+    fn handle(&mut self, msg: Register, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.register(&msg.0))
+    }
+}
+
+impl Handler<Identify> for Players {
+    type Result = MessageResult<Identify>;
+
+    fn handle(&mut self, msg: Identify, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.identify(msg.0))
+    }
+}
+
+impl Handler<GetProfile> for Players {
+    type Result = MessageResult<GetProfile>;
+
+    fn handle(&mut self, msg: GetProfile, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.profile(msg.0))
+    }
+}
+
+impl Handler<Ladder> for Players {
+    type Result = MessageResult<Ladder>;
+
+    fn handle(&mut self, msg: Ladder, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.ladder(msg.0))
+    }
+}
+
+impl Handler<RateGame> for Players {
+    type Result = MessageResult<RateGame>;
+
+    fn handle(&mut self, msg: RateGame, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.rate_game(msg.white, msg.black, msg.category, msg.winner))
+    }
+}
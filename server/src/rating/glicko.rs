@@ -0,0 +1,120 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+/// Converts between the Glicko scale shown to players and the Glicko-2 scale of the formulas
+const SCALE: f64 = 173.7178;
+const DEFAULT_RATING: f64 = 1500.0;
+/// Deviation of a new player, ratings never get more uncertain than this
+const DEFAULT_DEVIATION: f64 = 350.0;
+const DEFAULT_VOLATILITY: f64 = 0.06;
+/// Constrains how fast the volatility changes, Glickman suggests 0.3 to 1.2
+const TAU: f64 = 0.5;
+const CONVERGENCE: f64 = 0.000_001;
+
+/// A Glicko-2 rating, see Mark Glickman's "Example of the Glicko-2 system". Every rated game
+/// is a rating period of its own
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rating {
+    pub rating: f64,
+    /// The rating is within twice the deviation of the true strength, with 95% confidence
+    pub deviation: f64,
+    /// How erratic the player's results are
+    pub volatility: f64,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: DEFAULT_RATING,
+            deviation: DEFAULT_DEVIATION,
+            volatility: DEFAULT_VOLATILITY,
+            games: 0,
+        }
+    }
+}
+
+impl Rating {
+    /// The rating after a period with these games, given the opponents' ratings before the
+    /// period and the scores: 1 for a win, 0.5 for a draw, 0 for a loss
+    pub fn update(&self, games: &[(Rating, f64)]) -> Rating {
+        let mu = (self.rating - DEFAULT_RATING) / SCALE;
+        let phi = self.deviation / SCALE;
+        if games.is_empty() {
+            // Only the uncertainty grows
+            let phi = (phi * phi + self.volatility * self.volatility).sqrt();
+            return Rating {
+                deviation: (phi * SCALE).min(DEFAULT_DEVIATION),
+                ..*self
+            };
+        }
+
+        let mut variance = 0.0;
+        let mut improvement = 0.0;
+        for (opponent, score) in games {
+            let opponent_mu = (opponent.rating - DEFAULT_RATING) / SCALE;
+            let g = g(opponent.deviation / SCALE);
+            let expected = 1.0 / (1.0 + (-g * (mu - opponent_mu)).exp());
+            variance += g * g * expected * (1.0 - expected);
+            improvement += g * (score - expected);
+        }
+        let variance = 1.0 / variance;
+        let delta = variance * improvement;
+
+        let volatility = self.volatility(phi, variance, delta);
+        let phi_star = (phi * phi + volatility * volatility).sqrt();
+        let phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / variance).sqrt();
+        let mu = mu + phi * phi * improvement;
+
+        Rating {
+            rating: mu * SCALE + DEFAULT_RATING,
+            deviation: (phi * SCALE).min(DEFAULT_DEVIATION),
+            volatility,
+            games: self.games + games.len() as u32,
+        }
+    }
+
+    /// The new volatility, found with the Illinois algorithm
+    fn volatility(&self, phi: f64, variance: f64, delta: f64) -> f64 {
+        let a = (self.volatility * self.volatility).ln();
+        let f = |x: f64| {
+            let exp = x.exp();
+            let denominator = phi * phi + variance + exp;
+            exp * (delta * delta - phi * phi - variance - exp) / (2.0 * denominator * denominator)
+                - (x - a) / (TAU * TAU)
+        };
+
+        let mut lower = a;
+        let mut upper = if delta * delta > phi * phi + variance {
+            (delta * delta - phi * phi - variance).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * TAU) < 0.0 {
+                k += 1.0;
+            }
+            a - k * TAU
+        };
+        let mut f_lower = f(lower);
+        let mut f_upper = f(upper);
+        while (upper - lower).abs() > CONVERGENCE {
+            let c = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+            let f_c = f(c);
+            if f_c * f_upper < 0.0 {
+                lower = upper;
+                f_lower = f_upper;
+            } else {
+                f_lower /= 2.0;
+            }
+            upper = c;
+            f_upper = f_c;
+        }
+        (lower / 2.0).exp()
+    }
+}
+
+/// Weighs a result down when the opponent's rating is uncertain
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
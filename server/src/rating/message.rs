@@ -0,0 +1,34 @@
+use actix::Message;
+use engine::piece::Color;
+
+use crate::lobby::room::time_control::Category;
+
+use super::{LadderEntry, PlayerError, PlayerId, PlayerToken, Profile};
+
+#[derive(Message)]
+#[rtype(result = "Result<(PlayerToken, Profile), PlayerError>")]
+pub struct Register(pub String);
+
+/// The player a token belongs to
+#[derive(Message)]
+#[rtype(result = "Option<Profile>")]
+pub struct Identify(pub PlayerToken);
+
+#[derive(Message)]
+#[rtype(result = "Option<Profile>")]
+pub struct GetProfile(pub PlayerId);
+
+#[derive(Message)]
+#[rtype(result = "Vec<LadderEntry>")]
+pub struct Ladder(pub Category);
+
+/// A rated game is over, `winner` is None for a draw. Answers the white and black profiles
+/// with their new ratings
+#[derive(Message)]
+#[rtype(result = "Option<(Profile, Profile)>")]
+pub struct RateGame {
+    pub white: PlayerId,
+    pub black: PlayerId,
+    pub category: Category,
+    pub winner: Option<Color>,
+}
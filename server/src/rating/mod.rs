@@ -0,0 +1,265 @@
+use std::{collections::HashMap, env, error::Error, fmt::Display, fs};
+
+use engine::piece::Color;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::lobby::room::time_control::Category;
+
+use self::glicko::Rating;
+
+mod actor;
+pub mod glicko;
+pub mod message;
+
+#[cfg(test)]
+mod test;
+
+/// JSON file the players and their ratings are kept in, they only last as long as the server
+/// when it isn't set
+const STORE_VARIABLE: &str = "PLAYER_STORE";
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Public identity of a player, shown on ladders and to opponents
+pub type PlayerId = Uuid;
+/// Secret a player identifies with, given once when they register
+pub type PlayerToken = Uuid;
+
+/// One rating for every time control category
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ratings {
+    pub bullet: Rating,
+    pub blitz: Rating,
+    pub rapid: Rating,
+    pub classical: Rating,
+}
+
+impl Ratings {
+    pub fn get(&self, category: Category) -> Rating {
+        match category {
+            Category::Bullet => self.bullet,
+            Category::Blitz => self.blitz,
+            Category::Rapid => self.rapid,
+            Category::Classical => self.classical,
+        }
+    }
+
+    fn get_mut(&mut self, category: Category) -> &mut Rating {
+        match category {
+            Category::Bullet => &mut self.bullet,
+            Category::Blitz => &mut self.blitz,
+            Category::Rapid => &mut self.rapid,
+            Category::Classical => &mut self.classical,
+        }
+    }
+}
+
+/// A player as the store keeps them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Player {
+    id: PlayerId,
+    token: PlayerToken,
+    name: String,
+    ratings: Ratings,
+}
+
+/// A player without their token, as the API and the rooms see them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: PlayerId,
+    pub name: String,
+    pub ratings: Ratings,
+}
+
+impl From<&Player> for Profile {
+    fn from(player: &Player) -> Self {
+        Self {
+            id: player.id,
+            name: player.name.clone(),
+            ratings: player.ratings,
+        }
+    }
+}
+
+/// A ladder position in one category
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LadderEntry {
+    pub rank: usize,
+    pub id: PlayerId,
+    pub name: String,
+    #[serde(flatten)]
+    pub rating: Rating,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerError {
+    EmptyName,
+    NameTooLong,
+    NameTaken,
+}
+
+impl Display for PlayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerError::EmptyName => write!(f, "The name can't be empty"),
+            PlayerError::NameTooLong => {
+                write!(
+                    f,
+                    "The name can't be longer than {} characters",
+                    MAX_NAME_LENGTH
+                )
+            }
+            PlayerError::NameTaken => write!(f, "The name is already taken"),
+        }
+    }
+}
+
+impl Error for PlayerError {}
+
+/// Every registered player, saved to [`STORE_VARIABLE`] after each change
+#[derive(Default)]
+pub struct Players {
+    players: HashMap<PlayerId, Player>,
+    tokens: HashMap<PlayerToken, PlayerId>,
+}
+
+impl Players {
+    /// The players saved by a previous run of the server
+    pub fn load() -> Self {
+        let mut store = Self::default();
+        let Ok(path) = env::var(STORE_VARIABLE) else {
+            return store;
+        };
+        let players: Vec<Player> = match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(players) => players,
+                Err(error) => {
+                    println!("Couldn't read the players in {}: {}", path, error);
+                    Vec::new()
+                }
+            },
+            // No player registered yet
+            Err(_) => Vec::new(),
+        };
+        for player in players {
+            store.tokens.insert(player.token, player.id);
+            store.players.insert(player.id, player);
+        }
+        store
+    }
+
+    /// Registers a player, the token in the returned value is the only way to play as them
+    pub fn register(&mut self, name: &str) -> Result<(PlayerToken, Profile), PlayerError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(PlayerError::EmptyName);
+        }
+        if name.chars().count() > MAX_NAME_LENGTH {
+            return Err(PlayerError::NameTooLong);
+        }
+        let taken = self
+            .players
+            .values()
+            .any(|player| player.name.eq_ignore_ascii_case(name));
+        if taken {
+            return Err(PlayerError::NameTaken);
+        }
+
+        let player = Player {
+            id: PlayerId::new_v4(),
+            token: PlayerToken::new_v4(),
+            name: name.to_string(),
+            ratings: Ratings::default(),
+        };
+        let registered = (player.token, Profile::from(&player));
+        self.tokens.insert(player.token, player.id);
+        self.players.insert(player.id, player);
+        self.save();
+        Ok(registered)
+    }
+
+    pub fn identify(&self, token: PlayerToken) -> Option<Profile> {
+        let id = self.tokens.get(&token)?;
+        self.profile(*id)
+    }
+
+    pub fn profile(&self, id: PlayerId) -> Option<Profile> {
+        self.players.get(&id).map(Profile::from)
+    }
+
+    /// Players who played in the category, the highest rated first
+    pub fn ladder(&self, category: Category) -> Vec<LadderEntry> {
+        let mut players: Vec<&Player> = self
+            .players
+            .values()
+            .filter(|player| player.ratings.get(category).games > 0)
+            .collect();
+        players.sort_by(|a, b| {
+            let rating = |player: &Player| player.ratings.get(category).rating;
+            rating(b).total_cmp(&rating(a))
+        });
+        players
+            .into_iter()
+            .enumerate()
+            .map(|(index, player)| LadderEntry {
+                rank: index + 1,
+                id: player.id,
+                name: player.name.clone(),
+                rating: player.ratings.get(category),
+            })
+            .collect()
+    }
+
+    /// Updates both ratings with the result of their game, returns the white and black
+    /// profiles after it
+    pub fn rate_game(
+        &mut self,
+        white: PlayerId,
+        black: PlayerId,
+        category: Category,
+        winner: Option<Color>,
+    ) -> Option<(Profile, Profile)> {
+        if white == black {
+            return None;
+        }
+        let white_rating = self.players.get(&white)?.ratings.get(category);
+        let black_rating = self.players.get(&black)?.ratings.get(category);
+        let white_score = match winner {
+            Some(Color::White) => 1.0,
+            Some(Color::Black) => 0.0,
+            None => 0.5,
+        };
+
+        let updates = [
+            (white, white_rating.update(&[(black_rating, white_score)])),
+            (
+                black,
+                black_rating.update(&[(white_rating, 1.0 - white_score)]),
+            ),
+        ];
+        for (id, rating) in updates {
+            if let Some(player) = self.players.get_mut(&id) {
+                *player.ratings.get_mut(category) = rating;
+            }
+        }
+        self.save();
+        Some((self.profile(white)?, self.profile(black)?))
+    }
+
+    fn save(&self) {
+        let Ok(path) = env::var(STORE_VARIABLE) else {
+            return;
+        };
+        let players: Vec<&Player> = self.players.values().collect();
+        let written = serde_json::to_string_pretty(&players)
+            .map_err(|error| error.to_string())
+            .and_then(|json| fs::write(&path, json).map_err(|error| error.to_string()));
+        if let Err(error) = written {
+            println!("Couldn't save the players to {}: {}", path, error);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use engine::piece::Color;
+
+use crate::lobby::room::time_control::Category;
+
+use super::{glicko::Rating, PlayerId, Players, Profile};
+
+fn rating(rating: f64, deviation: f64) -> Rating {
+    Rating {
+        rating,
+        deviation,
+        ..Rating::default()
+    }
+}
+
+fn register(players: &mut Players, name: &str) -> Profile {
+    players.register(name).unwrap().1
+}
+
+/// The rating period of Glickman's "Example of the Glicko-2 system"
+#[test]
+fn glickman_example() {
+    let player = rating(1500.0, 200.0);
+    let games = [
+        (rating(1400.0, 30.0), 1.0),
+        (rating(1550.0, 100.0), 0.0),
+        (rating(1700.0, 300.0), 0.0),
+    ];
+
+    let updated = player.update(&games);
+    assert!((updated.rating - 1464.06).abs() < 0.01);
+    assert!((updated.deviation - 151.52).abs() < 0.01);
+    assert!((updated.volatility - 0.05999).abs() < 0.00001);
+    assert_eq!(updated.games, 3);
+}
+
+#[test]
+fn deviation_grows_without_games() {
+    let player = rating(1500.0, 200.0);
+    let updated = player.update(&[]);
+    assert_eq!(updated.rating, 1500.0);
+    assert!(updated.deviation > 200.0);
+    assert_eq!(updated.games, 0);
+
+    // Never more uncertain than a new player
+    assert_eq!(Rating::default().update(&[]).deviation, 350.0);
+}
+
+#[test]
+fn rate_win() {
+    let mut players = Players::default();
+    let white = register(&mut players, "white");
+    let black = register(&mut players, "black");
+
+    let (white, black) = players
+        .rate_game(white.id, black.id, Category::Blitz, Some(Color::White))
+        .unwrap();
+    let (white_blitz, black_blitz) = (white.ratings.blitz, black.ratings.blitz);
+    assert!(white_blitz.rating > 1500.0);
+    assert!(black_blitz.rating < 1500.0);
+    // Same ratings before the game, the points won are the points lost
+    assert!((white_blitz.rating - 1500.0 - (1500.0 - black_blitz.rating)).abs() < 1e-9);
+    assert_eq!((white_blitz.games, black_blitz.games), (1, 1));
+    // The other categories are left alone
+    assert_eq!(white.ratings.rapid, Rating::default());
+    assert_eq!(players.profile(white.id), Some(white));
+}
+
+#[test]
+fn rate_loss() {
+    let mut players = Players::default();
+    let white = register(&mut players, "white");
+    let black = register(&mut players, "black");
+
+    let (white, black) = players
+        .rate_game(white.id, black.id, Category::Rapid, Some(Color::Black))
+        .unwrap();
+    assert!(white.ratings.rapid.rating < 1500.0);
+    assert!(black.ratings.rapid.rating > 1500.0);
+}
+
+#[test]
+fn rate_draw() {
+    let mut players = Players::default();
+    let white = register(&mut players, "white");
+    let black = register(&mut players, "black");
+
+    let (white, black) = players
+        .rate_game(white.id, black.id, Category::Classical, None)
+        .unwrap();
+    for player in [white, black] {
+        let classical = player.ratings.classical;
+        assert!((classical.rating - 1500.0).abs() < 1e-9);
+        // The ratings are more certain after a game
+        assert!(classical.deviation < 350.0);
+        assert_eq!(classical.games, 1);
+    }
+
+    // The lower rated player gains from a draw
+    let stronger = register(&mut players, "stronger");
+    let weaker = register(&mut players, "weaker");
+    let (stronger, weaker) = players
+        .rate_game(stronger.id, weaker.id, Category::Bullet, Some(Color::White))
+        .unwrap();
+    let (drawn_stronger, drawn_weaker) = players
+        .rate_game(stronger.id, weaker.id, Category::Bullet, None)
+        .unwrap();
+    assert!(drawn_stronger.ratings.bullet.rating < stronger.ratings.bullet.rating);
+    assert!(drawn_weaker.ratings.bullet.rating > weaker.ratings.bullet.rating);
+}
+
+/// Anonymous players and the bot have no profile, their games leave the ratings alone
+#[test]
+fn unrated_games() {
+    let mut players = Players::default();
+    let player = register(&mut players, "player");
+
+    let stranger = PlayerId::new_v4();
+    assert_eq!(
+        players.rate_game(player.id, stranger, Category::Blitz, Some(Color::White)),
+        None
+    );
+    assert_eq!(
+        players.rate_game(stranger, player.id, Category::Blitz, Some(Color::Black)),
+        None
+    );
+    // Against themselves
+    assert_eq!(
+        players.rate_game(player.id, player.id, Category::Blitz, Some(Color::White)),
+        None
+    );
+    assert_eq!(players.profile(player.id), Some(player));
+}
@@ -0,0 +1,168 @@
+use std::{error::Error, fmt::Display, str::FromStr, time::Duration};
+
+use ai::moves::Move;
+
+/// Commands sent by the GUI, anything not listed here is ignored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Uci,
+    IsReady,
+    UciNewGame,
+    /// `fen` is None for the initial position
+    Position {
+        fen: Option<String>,
+        moves: Vec<Move>,
+    },
+    Go(GoOptions),
+    Stop,
+    Quit,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GoOptions {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    /// Search until `stop` is received
+    pub infinite: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    InvalidArgument(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "Empty command"),
+            CommandError::UnknownCommand(command) => write!(f, "Unknown command: {}", command),
+            CommandError::MissingArgument(argument) => write!(f, "Missing argument: {}", argument),
+            CommandError::InvalidArgument(argument) => {
+                write!(f, "Invalid argument: {}", argument)
+            }
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        let (command, arguments) = tokens.split_first().ok_or(CommandError::Empty)?;
+        match *command {
+            "uci" => Ok(Command::Uci),
+            "isready" => Ok(Command::IsReady),
+            "ucinewgame" => Ok(Command::UciNewGame),
+            "position" => parse_position(arguments),
+            "go" => parse_go(arguments).map(Command::Go),
+            "stop" => Ok(Command::Stop),
+            "quit" => Ok(Command::Quit),
+            "setoption" => parse_set_option(arguments),
+            _ => Err(CommandError::UnknownCommand(command.to_string())),
+        }
+    }
+}
+
+/// `position [fen <fenstring> | startpos] [moves <move1> ... <movei>]`
+fn parse_position(arguments: &[&str]) -> Result<Command, CommandError> {
+    let (kind, rest) = arguments
+        .split_first()
+        .ok_or(CommandError::MissingArgument("startpos or fen"))?;
+
+    let moves_index = rest.iter().position(|token| *token == "moves");
+    let (position, moves) = match moves_index {
+        Some(index) => (&rest[..index], &rest[index + 1..]),
+        None => (rest, &[][..]),
+    };
+
+    let fen = match *kind {
+        "startpos" => None,
+        "fen" if position.is_empty() => return Err(CommandError::MissingArgument("fen")),
+        "fen" => Some(position.join(" ")),
+        _ => return Err(CommandError::InvalidArgument(kind.to_string())),
+    };
+
+    let moves = moves
+        .iter()
+        .map(|movement| {
+            Move::from_str(movement)
+                .map_err(|_| CommandError::InvalidArgument(movement.to_string()))
+        })
+        .collect::<Result<Vec<Move>, CommandError>>()?;
+
+    Ok(Command::Position { fen, moves })
+}
+
+fn parse_go(arguments: &[&str]) -> Result<GoOptions, CommandError> {
+    let mut options = GoOptions::default();
+    let mut tokens = arguments.iter();
+    while let Some(token) = tokens.next() {
+        match *token {
+            "infinite" => options.infinite = true,
+            "depth" => options.depth = Some(parse_value(tokens.next(), "depth")?),
+            "nodes" => options.nodes = Some(parse_value(tokens.next(), "nodes")?),
+            "movestogo" => options.movestogo = Some(parse_value(tokens.next(), "movestogo")?),
+            "movetime" => options.movetime = Some(parse_millis(tokens.next(), "movetime")?),
+            "wtime" => options.wtime = Some(parse_millis(tokens.next(), "wtime")?),
+            "btime" => options.btime = Some(parse_millis(tokens.next(), "btime")?),
+            "winc" => options.winc = Some(parse_millis(tokens.next(), "winc")?),
+            "binc" => options.binc = Some(parse_millis(tokens.next(), "binc")?),
+            // Pondering and restricted root moves are not supported
+            _ => {}
+        }
+    }
+    Ok(options)
+}
+
+/// `setoption name <id> [value <x>]`, the name and the value can contain spaces
+fn parse_set_option(arguments: &[&str]) -> Result<Command, CommandError> {
+    let (first, rest) = arguments
+        .split_first()
+        .ok_or(CommandError::MissingArgument("name"))?;
+    if *first != "name" {
+        return Err(CommandError::InvalidArgument(first.to_string()));
+    }
+
+    let value_index = rest.iter().position(|token| *token == "value");
+    let (name, value) = match value_index {
+        Some(index) => (&rest[..index], Some(rest[index + 1..].join(" "))),
+        None => (rest, None),
+    };
+    if name.is_empty() {
+        return Err(CommandError::MissingArgument("name"));
+    }
+
+    Ok(Command::SetOption {
+        name: name.join(" "),
+        value,
+    })
+}
+
+fn parse_value<T: FromStr>(token: Option<&&str>, name: &'static str) -> Result<T, CommandError> {
+    let token = token.ok_or(CommandError::MissingArgument(name))?;
+    token
+        .parse()
+        .map_err(|_| CommandError::InvalidArgument(token.to_string()))
+}
+
+/// Clocks can go negative when a GUI is late, they are treated as zero
+fn parse_millis(token: Option<&&str>, name: &'static str) -> Result<Duration, CommandError> {
+    let millis: i64 = parse_value(token, name)?;
+    Ok(Duration::from_millis(millis.max(0) as u64))
+}
@@ -0,0 +1,36 @@
+use std::{
+    io::{self, BufRead},
+    str::FromStr,
+};
+
+use command::{Command, CommandError};
+use session::Session;
+
+mod command;
+mod session;
+
+#[cfg(test)]
+mod test;
+
+fn main() {
+    let mut session = Session::new();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        match Command::from_str(&line) {
+            Ok(command) => {
+                if !session.execute(command) {
+                    return;
+                }
+            }
+            Err(CommandError::Empty) => {}
+            Err(error) => println!("info string {}", error),
+        }
+    }
+
+    // The GUI closed the input, stop searching before exiting
+    session.execute(Command::Quit);
+}
@@ -0,0 +1,323 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use ai::{
+    book::{Book, BookSettings},
+    moves::{self, Move},
+    search::{self, SearchLimits, SearchResult, Searcher, DEFAULT_HASH_SIZE_MB, MATE, MATE_BOUND},
+    tablebase::{dtm::DtmTablebase, syzygy::SyzygyTablebase, Tablebase},
+};
+use engine::{board::Board, piece::Color};
+
+use crate::command::{Command, GoOptions};
+
+pub const NAME: &str = "Rust Chess";
+pub const AUTHOR: &str = "Victor Ribeiro Lima";
+
+pub const MAX_HASH_SIZE_MB: usize = 1024;
+pub const MAX_THREADS: usize = 64;
+/// Book variety is set in percent, 100 follows the book weights
+pub const MAX_BOOK_VARIETY: usize = 300;
+
+/// Time kept aside on every move for the communication with the GUI
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// A search running in the background, `stop` interrupts it and `release` lets an
+/// infinite search report its best move
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    release: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+pub struct Session {
+    searcher: Arc<Mutex<Searcher>>,
+    board: Board,
+    /// Hashes of the positions played before the current one
+    history: Vec<u64>,
+    search: Option<RunningSearch>,
+    book: Option<Book>,
+    book_settings: BookSettings,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session {
+            searcher: Arc::new(Mutex::new(Searcher::default())),
+            board: Board::new(),
+            history: Vec::new(),
+            search: None,
+            book: None,
+            book_settings: BookSettings::default(),
+        }
+    }
+
+    /// Executes a command, returns false when the engine must quit
+    pub fn execute(&mut self, command: Command) -> bool {
+        match command {
+            Command::Uci => {
+                println!("id name {}", NAME);
+                println!("id author {}", AUTHOR);
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_SIZE_MB, MAX_HASH_SIZE_MB
+                );
+                println!(
+                    "option name Threads type spin default 1 min 1 max {}",
+                    MAX_THREADS
+                );
+                println!("option name BookFile type string default <empty>");
+                println!("option name BookDepth type spin default 0 min 0 max 1000");
+                println!(
+                    "option name BookVariety type spin default 100 min 0 max {}",
+                    MAX_BOOK_VARIETY
+                );
+                println!("option name TablebasePath type string default <empty>");
+                println!("option name SyzygyPath type string default <empty>");
+                println!("uciok");
+            }
+            Command::IsReady => println!("readyok"),
+            Command::UciNewGame => {
+                self.stop();
+                self.lock_searcher().clear();
+                self.board = Board::new();
+                self.history.clear();
+            }
+            Command::Position { fen, moves } => {
+                self.stop();
+                match position(fen.as_deref(), &moves) {
+                    Ok((board, history)) => {
+                        self.board = board;
+                        self.history = history;
+                    }
+                    Err(error) => println!("info string {}", error),
+                }
+            }
+            Command::Go(options) => {
+                self.stop();
+                self.go(options);
+            }
+            Command::Stop => self.stop(),
+            Command::Quit => {
+                self.stop();
+                return false;
+            }
+            Command::SetOption { name, value } => {
+                self.stop();
+                self.set_option(&name, value.as_deref());
+            }
+        }
+        true
+    }
+
+    fn go(&mut self, options: GoOptions) {
+        // Book moves are played at once, the history holds every ply of the game
+        if !options.infinite {
+            let book_move = self.book.as_ref().and_then(|book| {
+                let ply = self.history.len();
+                book.choose_move(
+                    &self.board,
+                    ply,
+                    self.book_settings,
+                    &mut rand::thread_rng(),
+                )
+            });
+            if let Some(book_move) = book_move {
+                println!("info string Book move");
+                println!("bestmove {}", book_move);
+                return;
+            }
+        }
+
+        let limits = limits(&options, self.board.get_turn());
+        let stop = Arc::new(AtomicBool::new(false));
+        let (release, released) = mpsc::channel();
+
+        let searcher = Arc::clone(&self.searcher);
+        let board = self.board;
+        let history = self.history.clone();
+        let search_stop = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+            let result = {
+                let mut searcher = searcher.lock().unwrap_or_else(|error| error.into_inner());
+                searcher.set_history(history);
+                searcher.search_with(&board, limits, &search_stop, |result| {
+                    println!("{}", info(result, start.elapsed()));
+                })
+            };
+
+            // The best move of an infinite search is only reported after `stop`
+            if options.infinite {
+                let _ = released.recv();
+            }
+
+            match result.best_move {
+                Some(best_move) => println!("bestmove {}", best_move),
+                None => println!("bestmove 0000"),
+            }
+        });
+
+        self.search = Some(RunningSearch {
+            stop,
+            release,
+            handle,
+        });
+    }
+
+    /// Stops the running search, if any, and waits for its best move to be reported
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.release.send(());
+            let _ = search.handle.join();
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) {
+        let name = name.to_ascii_lowercase();
+        if name == "bookfile" {
+            self.book = match value {
+                None | Some("") | Some("<empty>") => None,
+                Some(path) => match Book::open(path) {
+                    Ok(book) => Some(book),
+                    Err(error) => {
+                        println!("info string {}", error);
+                        None
+                    }
+                },
+            };
+            return;
+        }
+        if name == "tablebasepath" || name == "syzygypath" {
+            let opened = match (value, name.as_str()) {
+                (None | Some("") | Some("<empty>"), _) => Ok(None),
+                (Some(path), "tablebasepath") => DtmTablebase::open(path)
+                    .map(|tablebase| Some(Arc::new(tablebase) as Arc<dyn Tablebase>)),
+                (Some(path), _) => SyzygyTablebase::open(path)
+                    .map(|tablebase| Some(Arc::new(tablebase) as Arc<dyn Tablebase>)),
+            };
+            let tablebase = opened.unwrap_or_else(|error| {
+                println!("info string {}", error);
+                None
+            });
+            self.lock_searcher().set_tablebase(tablebase);
+            return;
+        }
+
+        let value = value.and_then(|value| value.parse::<usize>().ok());
+        match (name.as_str(), value) {
+            ("hash", Some(size)) => self
+                .lock_searcher()
+                .set_hash_size(size.clamp(1, MAX_HASH_SIZE_MB)),
+            ("threads", Some(threads)) => self
+                .lock_searcher()
+                .set_threads(threads.clamp(1, MAX_THREADS)),
+            ("bookdepth", Some(0)) => self.book_settings.depth = None,
+            ("bookdepth", Some(depth)) => self.book_settings.depth = Some(depth),
+            ("bookvariety", Some(variety)) => {
+                self.book_settings.variety = variety.min(MAX_BOOK_VARIETY) as f64 / 100.0
+            }
+            _ => println!("info string Unsupported option: {}", name),
+        }
+    }
+
+    fn lock_searcher(&self) -> std::sync::MutexGuard<'_, Searcher> {
+        self.searcher
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// Builds the board of a `position` command and the hashes of the positions played before it
+pub fn position(fen: Option<&str>, moves: &[Move]) -> Result<(Board, Vec<u64>), String> {
+    let mut board = match fen {
+        Some(fen) => Board::from_fen(fen).map_err(|error| error.to_string())?,
+        None => Board::new(),
+    };
+
+    let mut history = Vec::with_capacity(moves.len());
+    for movement in moves {
+        history.push(search::hash::hash(&board));
+        board =
+            moves::apply(&board, *movement).ok_or_else(|| format!("Illegal move: {}", movement))?;
+    }
+
+    Ok((board, history))
+}
+
+/// Search limits of a `go` command for the side to move
+pub fn limits(options: &GoOptions, turn: Color) -> SearchLimits {
+    if options.infinite {
+        return SearchLimits::default();
+    }
+
+    let (remaining, increment) = match turn {
+        Color::White => (options.wtime, options.winc),
+        Color::Black => (options.btime, options.binc),
+    };
+
+    let time = match (options.movetime, remaining) {
+        (Some(movetime), _) => Some(movetime.saturating_sub(MOVE_OVERHEAD).max(movetime / 2)),
+        (None, Some(remaining)) => {
+            let remaining = remaining.saturating_sub(MOVE_OVERHEAD);
+            let increment = increment.unwrap_or_default();
+            let limits = match options.movestogo {
+                Some(moves_to_go) => {
+                    SearchLimits::from_clock_and_moves(remaining, increment, moves_to_go)
+                }
+                None => SearchLimits::from_clock(remaining, increment),
+            };
+            limits.time
+        }
+        (None, None) => None,
+    };
+
+    SearchLimits {
+        depth: options.depth,
+        time,
+        nodes: options.nodes,
+    }
+}
+
+/// `info` line of a completed iteration
+pub fn info(result: &SearchResult, elapsed: Duration) -> String {
+    let score = if result.score >= MATE_BOUND {
+        format!("mate {}", (MATE - result.score + 1) / 2)
+    } else if result.score <= -MATE_BOUND {
+        format!("mate -{}", (MATE + result.score) / 2)
+    } else {
+        format!("cp {}", result.score)
+    };
+
+    let millis = elapsed.as_millis() as u64;
+    let nps = result.nodes * 1000 / millis.max(1);
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|movement| movement.to_string())
+        .collect();
+
+    format!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        nps,
+        millis,
+        pv.join(" ")
+    )
+}
@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod command {
+    use std::{str::FromStr, time::Duration};
+
+    use ai::moves::Move;
+
+    use crate::command::{Command, CommandError, GoOptions};
+
+    #[test]
+    fn simple_commands() {
+        assert_eq!(Command::from_str("uci"), Ok(Command::Uci));
+        assert_eq!(Command::from_str("isready"), Ok(Command::IsReady));
+        assert_eq!(Command::from_str("ucinewgame"), Ok(Command::UciNewGame));
+        assert_eq!(Command::from_str("  stop "), Ok(Command::Stop));
+        assert_eq!(Command::from_str("quit"), Ok(Command::Quit));
+        assert_eq!(Command::from_str(""), Err(CommandError::Empty));
+        assert_eq!(
+            Command::from_str("debug on"),
+            Err(CommandError::UnknownCommand("debug".to_string()))
+        );
+    }
+
+    #[test]
+    fn position_startpos_with_moves() {
+        let command = Command::from_str("position startpos moves e2e4 e7e5 g1f3").unwrap();
+        let moves = ["e2e4", "e7e5", "g1f3"]
+            .iter()
+            .map(|movement| Move::from_str(movement).unwrap())
+            .collect();
+        assert_eq!(command, Command::Position { fen: None, moves });
+    }
+
+    #[test]
+    fn position_fen() {
+        let command =
+            Command::from_str("position fen 4k3/P7/8/8/8/8/8/4K3 w - - 0 1 moves a7a8n").unwrap();
+        let expected = Command::Position {
+            fen: Some("4k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string()),
+            moves: vec![Move::from_str("a7a8n").unwrap()],
+        };
+        assert_eq!(command, expected);
+    }
+
+    #[test]
+    fn invalid_position() {
+        assert_eq!(
+            Command::from_str("position"),
+            Err(CommandError::MissingArgument("startpos or fen"))
+        );
+        assert_eq!(
+            Command::from_str("position startpos moves e2e9"),
+            Err(CommandError::InvalidArgument("e2e9".to_string()))
+        );
+    }
+
+    #[test]
+    fn go() {
+        let command = Command::from_str("go wtime 60000 btime -20 winc 1000 movestogo 40").unwrap();
+        let expected = GoOptions {
+            wtime: Some(Duration::from_secs(60)),
+            btime: Some(Duration::ZERO),
+            winc: Some(Duration::from_secs(1)),
+            movestogo: Some(40),
+            ..Default::default()
+        };
+        assert_eq!(command, Command::Go(expected));
+
+        let command = Command::from_str("go depth 6 infinite").unwrap();
+        let expected = GoOptions {
+            depth: Some(6),
+            infinite: true,
+            ..Default::default()
+        };
+        assert_eq!(command, Command::Go(expected));
+
+        assert_eq!(
+            Command::from_str("go movetime"),
+            Err(CommandError::MissingArgument("movetime"))
+        );
+    }
+
+    #[test]
+    fn set_option() {
+        let command = Command::from_str("setoption name Hash value 64").unwrap();
+        let expected = Command::SetOption {
+            name: "Hash".to_string(),
+            value: Some("64".to_string()),
+        };
+        assert_eq!(command, expected);
+
+        let command = Command::from_str("setoption name Clear Hash").unwrap();
+        let expected = Command::SetOption {
+            name: "Clear Hash".to_string(),
+            value: None,
+        };
+        assert_eq!(command, expected);
+    }
+}
+
+#[cfg(test)]
+mod session {
+    use std::{str::FromStr, time::Duration};
+
+    use ai::{
+        moves::Move,
+        search::{SearchResult, MATE},
+    };
+    use engine::piece::Color;
+
+    use crate::{
+        command::GoOptions,
+        session::{info, limits, position},
+    };
+
+    #[test]
+    fn position_with_moves() {
+        let moves: Vec<Move> = ["e2e4", "e7e5", "g1f3"]
+            .iter()
+            .map(|movement| Move::from_str(movement).unwrap())
+            .collect();
+        let (board, history) = position(None, &moves).unwrap();
+
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 0 1"
+        );
+        assert_eq!(history.len(), 3);
+    }
+
+    #[test]
+    fn position_with_illegal_move() {
+        let moves = vec![Move::from_str("e2e5").unwrap()];
+        assert!(position(None, &moves).is_err());
+        assert!(position(Some("not a fen"), &[]).is_err());
+    }
+
+    #[test]
+    fn limits_use_the_clock_of_the_side_to_move() {
+        let options = GoOptions {
+            wtime: Some(Duration::from_secs(300)),
+            btime: Some(Duration::from_secs(3)),
+            ..Default::default()
+        };
+        let white = limits(&options, Color::White).time.unwrap();
+        let black = limits(&options, Color::Black).time.unwrap();
+
+        assert!(white > black);
+        assert!(black < Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn infinite_search_has_no_limits() {
+        let options = GoOptions {
+            depth: Some(3),
+            movetime: Some(Duration::from_secs(1)),
+            infinite: true,
+            ..Default::default()
+        };
+        let limits = limits(&options, Color::White);
+
+        assert_eq!(limits.depth, None);
+        assert_eq!(limits.time, None);
+    }
+
+    #[test]
+    fn info_line() {
+        let mut result = SearchResult {
+            best_move: Some(Move::from_str("h5f7").unwrap()),
+            score: MATE - 1,
+            depth: 3,
+            nodes: 2000,
+            pv: vec![Move::from_str("h5f7").unwrap()],
+        };
+        assert_eq!(
+            info(&result, Duration::from_millis(500)),
+            "info depth 3 score mate 1 nodes 2000 nps 4000 time 500 pv h5f7"
+        );
+
+        result.score = -MATE + 4;
+        result.pv.clear();
+        assert!(info(&result, Duration::ZERO).contains("score mate -2"));
+
+        result.score = -35;
+        assert!(info(&result, Duration::ZERO).contains("score cp -35"));
+    }
+}
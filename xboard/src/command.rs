@@ -0,0 +1,165 @@
+use std::{error::Error, fmt::Display, str::FromStr, time::Duration};
+
+use ai::moves::Move;
+
+/// Commands sent by the GUI, see https://www.gnu.org/software/xboard/engine-intf.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Xboard,
+    Protover(u32),
+    New,
+    Force,
+    Go,
+    UserMove(Move),
+    /// `level MPS BASE INC`, `moves_per_session` is 0 for a sudden death game
+    Level {
+        moves_per_session: u32,
+        base: Duration,
+        increment: Duration,
+    },
+    /// Exact time for every move
+    St(Duration),
+    /// Maximum search depth
+    Sd(u8),
+    /// Time left on the engine's clock
+    Time(Duration),
+    /// Time left on the opponent's clock
+    Otim(Duration),
+    Undo,
+    Remove,
+    Result(String),
+    SetBoard(String),
+    Ping(String),
+    Post,
+    NoPost,
+    Cores(usize),
+    Memory(usize),
+    Quit,
+    /// Commands that need no answer and don't change anything for this engine
+    Ignored,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    InvalidArgument(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "Empty command"),
+            CommandError::UnknownCommand(command) => write!(f, "Unknown command: {}", command),
+            CommandError::MissingArgument(argument) => write!(f, "Missing argument: {}", argument),
+            CommandError::InvalidArgument(argument) => {
+                write!(f, "Invalid argument: {}", argument)
+            }
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+/// Known commands this engine has nothing to do with
+const IGNORED_COMMANDS: [&str; 14] = [
+    "accepted", "rejected", "random", "hard", "easy", "computer", "name", "rating", "ics", "draw",
+    "white", "black", "?", "variant",
+];
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (command, arguments) = s.split_once(' ').unwrap_or((s, ""));
+        let arguments = arguments.trim();
+        match command {
+            "" => Err(CommandError::Empty),
+            "xboard" => Ok(Command::Xboard),
+            "protover" => parse_value(arguments, "version").map(Command::Protover),
+            "new" => Ok(Command::New),
+            "force" => Ok(Command::Force),
+            "go" => Ok(Command::Go),
+            "usermove" => parse_move(arguments).map(Command::UserMove),
+            "level" => parse_level(arguments),
+            "st" => parse_seconds(arguments, "time").map(Command::St),
+            "sd" => parse_value(arguments, "depth").map(Command::Sd),
+            "time" => parse_centiseconds(arguments).map(Command::Time),
+            "otim" => parse_centiseconds(arguments).map(Command::Otim),
+            "undo" => Ok(Command::Undo),
+            "remove" => Ok(Command::Remove),
+            "result" => Ok(Command::Result(arguments.to_string())),
+            "setboard" if arguments.is_empty() => Err(CommandError::MissingArgument("fen")),
+            "setboard" => Ok(Command::SetBoard(arguments.to_string())),
+            "ping" => Ok(Command::Ping(arguments.to_string())),
+            "post" => Ok(Command::Post),
+            "nopost" => Ok(Command::NoPost),
+            "cores" => parse_value(arguments, "cores").map(Command::Cores),
+            "memory" => parse_value(arguments, "memory").map(Command::Memory),
+            "quit" => Ok(Command::Quit),
+            _ if IGNORED_COMMANDS.contains(&command) => Ok(Command::Ignored),
+            // Without the usermove feature moves are sent on their own
+            _ => match Move::from_str(command) {
+                Ok(movement) if arguments.is_empty() => Ok(Command::UserMove(movement)),
+                _ => Err(CommandError::UnknownCommand(command.to_string())),
+            },
+        }
+    }
+}
+
+fn parse_move(argument: &str) -> Result<Move, CommandError> {
+    if argument.is_empty() {
+        return Err(CommandError::MissingArgument("move"));
+    }
+    Move::from_str(argument).map_err(|_| CommandError::InvalidArgument(argument.to_string()))
+}
+
+/// `level 40 5 0` or `level 0 2:30 1.5`, the base is in minutes (optionally with seconds)
+/// and the increment in seconds
+fn parse_level(arguments: &str) -> Result<Command, CommandError> {
+    let arguments: Vec<&str> = arguments.split_whitespace().collect();
+    let (moves_per_session, base, increment) = match arguments[..] {
+        [moves_per_session, base, increment] => (moves_per_session, base, increment),
+        _ => return Err(CommandError::MissingArgument("level")),
+    };
+
+    let moves_per_session = parse_value(moves_per_session, "moves per session")?;
+    let base = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            let minutes: u64 = parse_value(minutes, "minutes")?;
+            let seconds: u64 = parse_value(seconds, "seconds")?;
+            Duration::from_secs(minutes * 60 + seconds)
+        }
+        None => Duration::from_secs(parse_value::<u64>(base, "minutes")? * 60),
+    };
+    let increment = parse_seconds(increment, "increment")?;
+
+    Ok(Command::Level {
+        moves_per_session,
+        base,
+        increment,
+    })
+}
+
+fn parse_value<T: FromStr>(argument: &str, name: &'static str) -> Result<T, CommandError> {
+    if argument.is_empty() {
+        return Err(CommandError::MissingArgument(name));
+    }
+    argument
+        .parse()
+        .map_err(|_| CommandError::InvalidArgument(argument.to_string()))
+}
+
+fn parse_seconds(argument: &str, name: &'static str) -> Result<Duration, CommandError> {
+    let seconds: f64 = parse_value(argument, name)?;
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| CommandError::InvalidArgument(argument.to_string()))
+}
+
+/// Clocks are sent in centiseconds and can go negative when the engine is late
+fn parse_centiseconds(argument: &str) -> Result<Duration, CommandError> {
+    let centiseconds: i64 = parse_value(argument, "time")?;
+    Ok(Duration::from_millis(centiseconds.max(0) as u64 * 10))
+}
@@ -0,0 +1,38 @@
+use std::{
+    io::{self, BufRead},
+    str::FromStr,
+};
+
+use command::{Command, CommandError};
+use session::Session;
+
+mod command;
+mod session;
+
+#[cfg(test)]
+mod test;
+
+/// The engine thinks on the main thread, commands sent while it is thinking are read
+/// once its move is played
+fn main() {
+    let mut session = Session::new(io::stdout());
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        match Command::from_str(&line) {
+            Ok(command) => {
+                if !session.execute(command) {
+                    return;
+                }
+            }
+            Err(CommandError::Empty) => {}
+            Err(CommandError::UnknownCommand(command)) => {
+                println!("Error (unknown command): {}", command)
+            }
+            Err(error) => println!("Error ({}): {}", error, line),
+        }
+    }
+}
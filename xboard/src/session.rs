@@ -0,0 +1,298 @@
+use std::{
+    io::Write,
+    sync::atomic::AtomicBool,
+    time::{Duration, Instant},
+};
+
+use ai::{
+    moves::Move,
+    search::{self, SearchLimits, SearchResult, Searcher, MATE, MATE_BOUND},
+};
+use engine::{
+    game::Game,
+    piece::{ChessPiece, Color, Type},
+};
+
+use crate::command::Command;
+
+pub const NAME: &str = "Rust Chess";
+
+pub const MAX_THREADS: usize = 64;
+
+/// Time kept aside on every move for the communication with the GUI
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// Score from which the engine plays on instead of claiming a draw, half a pawn
+const CLAIM_MARGIN: i32 = 50;
+
+/// Mates are reported as 100000 + moves to mate
+const MATE_SCORE: i32 = 100_000;
+
+/// Time control set by `level` or `st`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    /// Moves to play before the clock gets `base` again, 0 for the whole game
+    pub moves_per_session: u32,
+    pub base: Duration,
+    pub increment: Duration,
+    /// Exact time for every move, ignores the clock
+    pub fixed: Option<Duration>,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl {
+            moves_per_session: 0,
+            base: Duration::from_secs(5 * 60),
+            increment: Duration::ZERO,
+            fixed: None,
+        }
+    }
+}
+
+pub struct Session<W: Write> {
+    out: W,
+    searcher: Searcher,
+    game: Game,
+    /// None in force mode, the engine only plays the moves it is told
+    engine_color: Option<Color>,
+    time_control: TimeControl,
+    /// Time left on the engine's clock
+    remaining: Duration,
+    max_depth: Option<u8>,
+    post: bool,
+}
+
+impl<W: Write> Session<W> {
+    pub fn new(out: W) -> Session<W> {
+        let time_control = TimeControl::default();
+        Session {
+            out,
+            searcher: Searcher::default(),
+            game: Game::new(),
+            engine_color: Some(Color::Black),
+            time_control,
+            remaining: time_control.base,
+            max_depth: None,
+            post: false,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    #[cfg(test)]
+    pub fn get_output(&self) -> &W {
+        &self.out
+    }
+
+    /// Executes a command, returns false when the engine must quit
+    pub fn execute(&mut self, command: Command) -> bool {
+        match command {
+            Command::Xboard | Command::Ignored => {}
+            Command::Protover(_) => {
+                self.send(&format!(
+                    "feature myname=\"{}\" ping=1 setboard=1 usermove=1 time=1 draw=0 \
+                     sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 smp=1 memory=1 done=1",
+                    NAME
+                ));
+            }
+            Command::New => {
+                self.game = Game::new();
+                self.engine_color = Some(Color::Black);
+                self.remaining = self.time_control.base;
+                self.max_depth = None;
+                self.searcher.clear();
+            }
+            Command::Force | Command::Result(_) => self.engine_color = None,
+            Command::Go => {
+                self.engine_color = Some(self.game.get_board().get_turn());
+                self.think();
+            }
+            Command::UserMove(movement) => {
+                if !play(&mut self.game, movement) {
+                    self.send(&format!("Illegal move: {}", movement));
+                } else if self.engine_color == Some(self.game.get_board().get_turn()) {
+                    self.think();
+                }
+            }
+            Command::Level {
+                moves_per_session,
+                base,
+                increment,
+            } => {
+                self.time_control = TimeControl {
+                    moves_per_session,
+                    base,
+                    increment,
+                    fixed: None,
+                };
+                self.remaining = base;
+            }
+            Command::St(time) => self.time_control.fixed = Some(time),
+            Command::Sd(depth) => self.max_depth = Some(depth),
+            Command::Time(time) => self.remaining = time,
+            Command::Otim(_) => {}
+            Command::Undo => {
+                self.game.undo();
+            }
+            Command::Remove => {
+                self.game.undo();
+                self.game.undo();
+            }
+            Command::SetBoard(fen) => match Game::from_fen(&fen) {
+                Ok(game) => self.game = game,
+                Err(error) => self.send(&format!("tellusererror Illegal position: {}", error)),
+            },
+            Command::Ping(id) => self.send(&format!("pong {}", id)),
+            Command::Post => self.post = true,
+            Command::NoPost => self.post = false,
+            Command::Cores(cores) => self.searcher.set_threads(cores.clamp(1, MAX_THREADS)),
+            Command::Memory(size_mb) => self.searcher.set_hash_size(size_mb.max(1)),
+            Command::Quit => return false,
+        }
+        true
+    }
+
+    /// Searches and plays the engine's move, unless the game is already over. A draw available
+    /// to the engine (repetition, fifty-move rule) is claimed when the search doesn't see it
+    /// winning, before or after its move
+    fn think(&mut self) {
+        if self.report_result() {
+            return;
+        }
+
+        let board = *self.game.get_board();
+        let history = self.game.history().map(search::hash::hash).collect();
+        self.searcher.set_history(history);
+
+        let limits = self.limits();
+        let stop = AtomicBool::new(false);
+        let start = Instant::now();
+        let post = self.post;
+        let out = &mut self.out;
+        let result = self.searcher.search_with(&board, limits, &stop, |result| {
+            if post {
+                let _ = writeln!(out, "{}", thinking(result, start.elapsed()));
+            }
+        });
+
+        if self.claim_draw(result.score) {
+            return;
+        }
+        if let Some(best_move) = result.best_move {
+            play(&mut self.game, best_move);
+            self.send(&format!("move {}", best_move));
+        }
+        if !self.report_result() {
+            self.claim_draw(result.score);
+        }
+    }
+
+    /// Announces the end of the game: checkmate, stalemate or dead position. Returns true if the
+    /// game ended
+    fn report_result(&mut self) -> bool {
+        let board = self.game.get_board();
+        let result = match (board.get_winner(), self.game.draw()) {
+            (Some(Color::White), _) => "1-0 {White mates}".to_string(),
+            (Some(Color::Black), _) => "0-1 {Black mates}".to_string(),
+            (None, Some(reason)) => format!("1/2-1/2 {{{}}}", reason),
+            (None, None) => return false,
+        };
+        self.end_game(&result);
+        true
+    }
+
+    /// Claims a draw available to the engine unless `score`, the engine's, is worth playing on
+    /// for. Returns true if it claimed
+    fn claim_draw(&mut self, score: i32) -> bool {
+        if score >= CLAIM_MARGIN {
+            return false;
+        }
+        let Some(reason) = self.game.claimable_draw() else {
+            return false;
+        };
+        self.end_game(&format!("1/2-1/2 {{{}}}", reason));
+        true
+    }
+
+    fn end_game(&mut self, result: &str) {
+        self.send(result);
+        self.engine_color = None;
+    }
+
+    fn limits(&self) -> SearchLimits {
+        let time = match self.time_control.fixed {
+            Some(time) => time,
+            None => {
+                let remaining = self.remaining.saturating_sub(MOVE_OVERHEAD);
+                let increment = self.time_control.increment;
+                let limits = match self.time_control.moves_per_session {
+                    0 => SearchLimits::from_clock(remaining, increment),
+                    moves_per_session => {
+                        let played = (self.game.history().count() / 2) as u32;
+                        let moves_to_go = moves_per_session - played % moves_per_session;
+                        SearchLimits::from_clock_and_moves(remaining, increment, moves_to_go)
+                    }
+                };
+                limits.time.unwrap_or_default()
+            }
+        };
+
+        SearchLimits {
+            depth: self.max_depth,
+            time: Some(time),
+            nodes: None,
+        }
+    }
+
+    fn send(&mut self, line: &str) {
+        let _ = writeln!(self.out, "{}", line);
+        let _ = self.out.flush();
+    }
+}
+
+/// Plays a move with its promotion (a queen when missing), returns false if it is illegal
+pub fn play(game: &mut Game, movement: Move) -> bool {
+    let color = game.get_board().get_turn();
+    if game.move_piece(movement.from, movement.to).is_err() {
+        return false;
+    }
+
+    if game.get_board().get_promotion().is_some() {
+        let promotion = movement.promotion.unwrap_or(Type::Queen);
+        if game.promote(ChessPiece::new(promotion, color)).is_err() {
+            game.undo();
+            return false;
+        }
+    }
+    true
+}
+
+/// Thinking output of a completed iteration: `ply score time nodes pv`, with the time in
+/// centiseconds
+pub fn thinking(result: &SearchResult, elapsed: Duration) -> String {
+    let score = if result.score >= MATE_BOUND {
+        MATE_SCORE + (MATE - result.score + 1) / 2
+    } else if result.score <= -MATE_BOUND {
+        -MATE_SCORE - (MATE + result.score) / 2
+    } else {
+        result.score
+    };
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|movement| movement.to_string())
+        .collect();
+
+    format!(
+        "{} {} {} {} {}",
+        result.depth,
+        score,
+        elapsed.as_millis() / 10,
+        result.nodes,
+        pv.join(" ")
+    )
+}
@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod command {
+    use std::{str::FromStr, time::Duration};
+
+    use ai::moves::Move;
+
+    use crate::command::{Command, CommandError};
+
+    #[test]
+    fn moves() {
+        let movement = Move::from_str("e7e8q").unwrap();
+        assert_eq!(
+            Command::from_str("usermove e7e8q"),
+            Ok(Command::UserMove(movement))
+        );
+        assert_eq!(Command::from_str("e7e8q"), Ok(Command::UserMove(movement)));
+        assert_eq!(
+            Command::from_str("usermove e9e8"),
+            Err(CommandError::InvalidArgument("e9e8".to_string()))
+        );
+        assert_eq!(
+            Command::from_str("hint"),
+            Err(CommandError::UnknownCommand("hint".to_string()))
+        );
+    }
+
+    #[test]
+    fn level() {
+        assert_eq!(
+            Command::from_str("level 40 5 0"),
+            Ok(Command::Level {
+                moves_per_session: 40,
+                base: Duration::from_secs(300),
+                increment: Duration::ZERO,
+            })
+        );
+        assert_eq!(
+            Command::from_str("level 0 2:30 1.5"),
+            Ok(Command::Level {
+                moves_per_session: 0,
+                base: Duration::from_secs(150),
+                increment: Duration::from_millis(1500),
+            })
+        );
+        assert_eq!(
+            Command::from_str("level 0 2"),
+            Err(CommandError::MissingArgument("level"))
+        );
+    }
+
+    #[test]
+    fn time_and_depth() {
+        assert_eq!(
+            Command::from_str("st 2"),
+            Ok(Command::St(Duration::from_secs(2)))
+        );
+        assert_eq!(Command::from_str("sd 7"), Ok(Command::Sd(7)));
+        assert_eq!(
+            Command::from_str("time 12345"),
+            Ok(Command::Time(Duration::from_millis(123450)))
+        );
+        assert_eq!(
+            Command::from_str("otim -10"),
+            Ok(Command::Otim(Duration::ZERO))
+        );
+    }
+
+    #[test]
+    fn setboard_and_result() {
+        assert_eq!(
+            Command::from_str("setboard 7k/8/8/8/8/8/8/K7 w - - 0 1"),
+            Ok(Command::SetBoard("7k/8/8/8/8/8/8/K7 w - - 0 1".to_string()))
+        );
+        assert_eq!(
+            Command::from_str("result 1-0 {White mates}"),
+            Ok(Command::Result("1-0 {White mates}".to_string()))
+        );
+        assert_eq!(Command::from_str("accepted usermove"), Ok(Command::Ignored));
+    }
+}
+
+#[cfg(test)]
+mod session {
+    use std::str::FromStr;
+
+    use crate::{command::Command, session::Session};
+
+    /// Runs the commands and returns what the engine answered
+    fn run(commands: &[&str]) -> (Session<Vec<u8>>, Vec<String>) {
+        let mut session = Session::new(Vec::new());
+        for command in commands {
+            session.execute(Command::from_str(command).unwrap());
+        }
+        let output = String::from_utf8(session.get_output().clone()).unwrap();
+        let lines = output.lines().map(|line| line.to_string()).collect();
+        (session, lines)
+    }
+
+    #[test]
+    fn features() {
+        let (_, output) = run(&["xboard", "protover 2", "ping 7"]);
+        assert!(output[0].starts_with("feature myname="));
+        assert!(output[0].contains("usermove=1"));
+        assert!(output[0].ends_with("done=1"));
+        assert_eq!(output[1], "pong 7");
+    }
+
+    #[test]
+    fn answers_user_moves() {
+        let (session, output) = run(&["new", "sd 2", "usermove e2e4"]);
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("move "));
+        assert_eq!(session.get_game().history().count(), 2);
+    }
+
+    #[test]
+    fn illegal_move() {
+        let (session, output) = run(&["new", "usermove e2e5"]);
+        assert_eq!(output, vec!["Illegal move: e2e5"]);
+        assert_eq!(session.get_game().history().count(), 0);
+    }
+
+    #[test]
+    fn force_mode() {
+        let (session, output) = run(&["new", "force", "usermove e2e4", "usermove e7e5"]);
+        assert!(output.is_empty());
+        assert_eq!(session.get_game().history().count(), 2);
+    }
+
+    #[test]
+    fn go_plays_the_side_to_move() {
+        let (session, output) = run(&["new", "force", "usermove e2e4", "sd 1", "go"]);
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("move "));
+        assert_eq!(
+            session.get_game().get_board().get_turn(),
+            engine::piece::Color::White
+        );
+    }
+
+    #[test]
+    fn undo_and_remove() {
+        let (session, _) = run(&[
+            "new",
+            "force",
+            "usermove e2e4",
+            "usermove e7e5",
+            "usermove g1f3",
+            "undo",
+        ]);
+        assert_eq!(session.get_game().history().count(), 2);
+
+        let (session, _) = run(&[
+            "new",
+            "force",
+            "usermove e2e4",
+            "usermove e7e5",
+            "usermove g1f3",
+            "remove",
+        ]);
+        assert_eq!(session.get_game().history().count(), 1);
+    }
+
+    #[test]
+    fn announces_checkmate() {
+        let (_, output) = run(&[
+            "new",
+            "force",
+            "setboard 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+            "sd 3",
+            "go",
+        ]);
+        assert_eq!(output, vec!["move a1a8", "1-0 {White mates}"]);
+    }
+
+    #[test]
+    fn announces_stalemate() {
+        let (_, output) = run(&[
+            "new",
+            "force",
+            "setboard 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            "go",
+        ]);
+        assert_eq!(output, vec!["1/2-1/2 {Stalemate}"]);
+    }
+
+    #[test]
+    fn claims_repetition() {
+        let (_, output) = run(&[
+            "new",
+            "force",
+            "usermove g1f3",
+            "usermove g8f6",
+            "usermove f3g1",
+            "usermove f6g8",
+            "usermove g1f3",
+            "usermove g8f6",
+            "usermove f3g1",
+            "usermove f6g8",
+            "usermove g1f3",
+            "sd 3",
+            "go",
+        ]);
+        assert_eq!(output, vec!["1/2-1/2 {Threefold repetition}"]);
+    }
+
+    #[test]
+    fn claims_fifty_move_rule() {
+        let (_, output) = run(&[
+            "new",
+            "force",
+            "setboard 8/8/4k3/8/8/3K4/8/R7 b - - 100 80",
+            "sd 3",
+            "go",
+        ]);
+        assert_eq!(output, vec!["1/2-1/2 {Fifty-move rule}"]);
+    }
+
+    #[test]
+    fn plays_on_when_winning() {
+        let (_, output) = run(&[
+            "new",
+            "force",
+            "setboard 8/8/4k3/8/8/3K4/8/R7 w - - 100 80",
+            "sd 3",
+            "go",
+        ]);
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("move "));
+    }
+
+    #[test]
+    fn invalid_position() {
+        let (_, output) = run(&["new", "force", "setboard 8/8/8/8/8/8/8/8 w - - 0 1"]);
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("tellusererror"));
+    }
+}